@@ -0,0 +1,133 @@
+//! Benchmarks for the result formatters.
+//!
+//! Every tool response goes through a `ResultFormatter`, so each output format
+//! is measured for web, news, and local results over a typical page (10
+//! results) and a large one (50 results). Local search's
+//! `format_local_results` only maps POI fields onto `LocalItem`s before
+//! calling `ResultFormatter::local`, which is what is measured here.
+//!
+//! Usage: `cargo bench --bench formatters`
+
+use bravesearch_mcp::tools::bravesearch::{
+    JsonFormatter, LocalDetails, LocalItem, MarkdownFormatter, NewsItem, OutputOptions,
+    ResultFormatter, TextFormatter, WebSearchItem,
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+// A typical page and the largest payload worth planning for
+const SIZES: [usize; 2] = [10, 50];
+
+fn formatters() -> [(&'static str, Box<dyn ResultFormatter>); 3] {
+    [
+        ("text", Box::new(TextFormatter)),
+        ("json", Box::new(JsonFormatter)),
+        ("markdown", Box::new(MarkdownFormatter)),
+    ]
+}
+
+/// Numbered output under a byte budget, so truncation is part of the measurement
+fn options() -> OutputOptions {
+    OutputOptions {
+        first_position: Some(1),
+        max_output_bytes: Some(16 * 1024),
+        thumbnails_as_gallery: false,
+        pretty_json: false,
+    }
+}
+
+fn web_items(count: usize) -> Vec<WebSearchItem> {
+    (0..count)
+        .map(|i| WebSearchItem {
+            title: format!("Result {}: The Rust Programming Language &amp; its <strong>ecosystem</strong>", i),
+            description: "A language empowering everyone to build reliable and efficient software. Rust is blazingly fast and memory-efficient, with no runtime or garbage collector.".to_string(),
+            url: format!("https://www.example{}.com/docs/book/ch{:02}.html", i, i),
+            source: (i % 2 == 0).then(|| format!("Example {}", i)),
+            kind: None,
+            page_age: None,
+            updated: None,
+        })
+        .collect()
+}
+
+fn news_items(count: usize) -> Vec<NewsItem> {
+    (0..count)
+        .map(|i| NewsItem {
+            title: format!("Rust 1.{} released with faster compile times", i),
+            description: "The release stabilizes several long-awaited features and improves incremental compilation across large workspaces.".to_string(),
+            url: format!("https://news.example.com/2024/05/rust-1-{}", i),
+            age: Some(format!("{} hours ago", i + 1)),
+            published_at: Some("2024-05-10T10:00:00Z".to_string()),
+            breaking: i == 0,
+            thumbnail: Some(format!("https://img.example.com/thumbs/{}.jpg", i)),
+            extra_thumbnails: Vec::new(),
+            source: Some("news.example.com".to_string()),
+        })
+        .collect()
+}
+
+fn local_items(count: usize) -> Vec<LocalItem> {
+    (0..count)
+        .map(|i| LocalItem {
+            id: format!("loc-{}", i),
+            name: Some(format!("Corner Cafe #{}", i)),
+            address: Some(format!("{} Main St, Seattle, WA, 98101", 100 + i)),
+            coordinates: Some([47.6 + i as f64 / 1000.0, -122.3]),
+            coordinates_note: None,
+            details: Some(LocalDetails {
+                phone: Some("+1 206-555-0100".to_string()),
+                rating: Some(4.5),
+                rating_count: Some(120 + i as u32),
+                price_range: Some("$$".to_string()),
+                opening_hours: vec![
+                    "Mon-Fri 07:00-18:00".to_string(),
+                    "Sat-Sun 08:00-16:00".to_string(),
+                ],
+                description: Some(
+                    "Neighbourhood cafe serving espresso, pastries, and light lunches.".to_string(),
+                ),
+            }),
+        })
+        .collect()
+}
+
+fn bench_web(c: &mut Criterion) {
+    let mut group = c.benchmark_group("web");
+    for size in SIZES {
+        let items = web_items(size);
+        for (name, formatter) in formatters() {
+            group.bench_with_input(BenchmarkId::new(name, size), &items, |b, items| {
+                b.iter(|| formatter.web(items, options()))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench_news(c: &mut Criterion) {
+    let mut group = c.benchmark_group("news");
+    for size in SIZES {
+        let items = news_items(size);
+        for (name, formatter) in formatters() {
+            group.bench_with_input(BenchmarkId::new(name, size), &items, |b, items| {
+                b.iter(|| formatter.news(items, options()))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench_local(c: &mut Criterion) {
+    let mut group = c.benchmark_group("local");
+    for size in SIZES {
+        let items = local_items(size);
+        for (name, formatter) in formatters() {
+            group.bench_with_input(BenchmarkId::new(name, size), &items, |b, items| {
+                b.iter(|| formatter.local(items, options()))
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_web, bench_news, bench_local);
+criterion_main!(benches);
@@ -0,0 +1,173 @@
+//! End-to-end MCP client over the SSE transport.
+//!
+//! Starts the Brave Search server in-process on a free local port, connects to
+//! it as an SSE (EventSource) client, initializes, lists the tools, and runs
+//! one web search, printing the result. The server is shut down before exit,
+//! whether or not the session succeeded.
+//!
+//! Usage: `BRAVE_API_KEY=... cargo run --example client -- [query]`
+
+use std::net::{SocketAddr, TcpListener};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use bravesearch_mcp::BraveSearchRouter;
+use futures::StreamExt;
+use reqwest_eventsource::{Event, EventSource};
+use rmcp::transport::sse_server::SseServer;
+use serde_json::{json, Value};
+
+// How long to wait for any single server event before giving up
+const EVENT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let api_key =
+        std::env::var("BRAVE_API_KEY").context("Set BRAVE_API_KEY to run the client example")?;
+    let query = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "rust programming language".to_string());
+
+    let addr = free_local_addr()?;
+    let router = BraveSearchRouter::new(api_key);
+    let server = SseServer::serve(addr).await?;
+    let ct = server.with_service(move || router.clone());
+
+    let result = run_session(addr, &query).await;
+    ct.cancel();
+    result
+}
+
+/// A loopback address with a port the OS reports as free
+fn free_local_addr() -> Result<SocketAddr> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    Ok(listener.local_addr()?)
+}
+
+async fn run_session(addr: SocketAddr, query: &str) -> Result<()> {
+    let mut session = SseSession::connect(&format!("http://{}", addr)).await?;
+    println!("Connected; posting messages to {}", session.post_url);
+
+    let init = session
+        .request(
+            1,
+            "initialize",
+            json!({
+                "protocolVersion": "2024-11-05",
+                "capabilities": {},
+                "clientInfo": {"name": "bravesearch-example-client", "version": "0.1.0"}
+            }),
+        )
+        .await?;
+    println!(
+        "Initialized with {} {}",
+        init["serverInfo"]["name"].as_str().unwrap_or("?"),
+        init["serverInfo"]["version"].as_str().unwrap_or("?")
+    );
+    session
+        .notify("notifications/initialized", json!({}))
+        .await?;
+
+    let tools = session.request(2, "tools/list", json!({})).await?;
+    let names = tools["tools"]
+        .as_array()
+        .map(|tools| {
+            tools
+                .iter()
+                .filter_map(|tool| tool["name"].as_str())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    println!("Tools: {}", names.join(", "));
+
+    let result = session
+        .request(
+            3,
+            "tools/call",
+            json!({"name": "brave_web_search", "arguments": {"query": query, "count": 3}}),
+        )
+        .await?;
+    let text = result["content"][0]["text"]
+        .as_str()
+        .ok_or_else(|| anyhow!("Tool result has no text content: {}", result))?;
+    println!("\nResults for {:?}:\n\n{}", query, text);
+
+    session.close();
+    Ok(())
+}
+
+/// One MCP session: responses arrive on the event stream, requests are posted
+/// to the endpoint the server announces when the stream opens
+struct SseSession {
+    events: EventSource,
+    http: reqwest::Client,
+    post_url: String,
+}
+
+impl SseSession {
+    async fn connect(base_url: &str) -> Result<Self> {
+        let mut events = EventSource::get(format!("{}/sse", base_url));
+        loop {
+            match next_event(&mut events).await? {
+                Event::Open => {}
+                Event::Message(message) if message.event == "endpoint" => {
+                    return Ok(Self {
+                        events,
+                        http: reqwest::Client::new(),
+                        post_url: format!("{}{}", base_url, message.data),
+                    });
+                }
+                Event::Message(message) => {
+                    bail!("Expected the endpoint event, got {:?}", message.event)
+                }
+            }
+        }
+    }
+
+    /// Send a request and wait for its response, skipping any notifications
+    async fn request(&mut self, id: u64, method: &str, params: Value) -> Result<Value> {
+        self.post(json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params}))
+            .await?;
+        loop {
+            let Event::Message(message) = next_event(&mut self.events).await? else {
+                continue;
+            };
+            let reply: Value = serde_json::from_str(&message.data)
+                .with_context(|| format!("Invalid JSON-RPC message: {}", message.data))?;
+            if reply["id"] != json!(id) {
+                continue;
+            }
+            if let Some(error) = reply.get("error") {
+                bail!("{} failed: {}", method, error);
+            }
+            return Ok(reply["result"].clone());
+        }
+    }
+
+    async fn notify(&self, method: &str, params: Value) -> Result<()> {
+        self.post(json!({"jsonrpc": "2.0", "method": method, "params": params}))
+            .await
+    }
+
+    async fn post(&self, message: Value) -> Result<()> {
+        self.http
+            .post(&self.post_url)
+            .json(&message)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    fn close(mut self) {
+        self.events.close();
+    }
+}
+
+async fn next_event(events: &mut EventSource) -> Result<Event> {
+    match tokio::time::timeout(EVENT_TIMEOUT, events.next()).await {
+        Err(_) => bail!("Timed out waiting for the server"),
+        Ok(None) => bail!("The server closed the event stream"),
+        Ok(Some(event)) => event.map_err(|e| anyhow!("Event stream error: {}", e)),
+    }
+}
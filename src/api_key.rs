@@ -0,0 +1,141 @@
+use std::fmt;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+
+// Resolving the Brave API key from the CLI flags, a key file (`--api-key-file`),
+// or the environment.
+//
+// Editors and secret managers often add a UTF-8 byte order mark or a trailing
+// newline (CRLF on Windows), so both are removed. Characters inside the key
+// are kept as they are.
+//
+// Users often export the key under a guessed name instead of `BRAVE_API_KEY`.
+// Those names are checked last and used with a warning, rather than failing
+// with a "required" error that does not say what was wrong.
+
+const BOM: char = '\u{feff}';
+
+/// Environment variables commonly set instead of `BRAVE_API_KEY`, in lookup order
+pub const MISNAMED_ENV_VARS: &[&str] = &["BRAVE_SEARCH_API_KEY", "BRAVE_KEY", "BRAVE_SEARCH_KEY"];
+
+/// A resolved API key
+pub struct ApiKey {
+    pub key: String,
+    /// Set when the key came from a misnamed environment variable, telling the user to rename it
+    pub warning: Option<String>,
+}
+
+impl fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiKey")
+            .field("key", &"[redacted]")
+            .field("warning", &self.warning)
+            .finish()
+    }
+}
+
+/// The API key in `contents`, without a leading BOM or surrounding whitespace
+pub fn parse_api_key(contents: &str) -> Result<String> {
+    let key = contents.strip_prefix(BOM).unwrap_or(contents).trim();
+    if key.is_empty() {
+        return Err(anyhow!("API key is empty"));
+    }
+    Ok(key.to_string())
+}
+
+/// Read and clean the API key stored in the file at `path`
+pub fn read_api_key_file(path: &Path) -> Result<String> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read API key file {}", path.display()))?;
+    parse_api_key(&contents).with_context(|| format!("Invalid API key file {}", path.display()))
+}
+
+/// The first misnamed environment variable (see `MISNAMED_ENV_VARS`) with a non-blank value,
+/// with that value, looking variables up with `env`
+pub fn find_misnamed_env_key(
+    env: impl Fn(&str) -> Option<String>,
+) -> Option<(&'static str, String)> {
+    MISNAMED_ENV_VARS.iter().find_map(|&name| {
+        let key = parse_api_key(&env(name)?).ok()?;
+        Some((name, key))
+    })
+}
+
+/// The API key from `key_file`, else `key` (`--api-key` or `BRAVE_API_KEY`), else a misnamed
+/// environment variable looked up with `env`, which is used but comes with a warning
+pub fn get_api_key(
+    key: Option<&str>,
+    key_file: Option<&Path>,
+    env: impl Fn(&str) -> Option<String>,
+) -> Result<ApiKey> {
+    if let Some(path) = key_file {
+        return Ok(ApiKey {
+            key: read_api_key_file(path)?,
+            warning: None,
+        });
+    }
+    if let Some(key) = key {
+        return Ok(ApiKey {
+            key: key.to_string(),
+            warning: None,
+        });
+    }
+    let (name, key) = find_misnamed_env_key(env).ok_or_else(|| {
+        anyhow!("Brave API key is required: set BRAVE_API_KEY or pass --api-key or --api-key-file")
+    })?;
+    Ok(ApiKey {
+        key,
+        warning: Some(format!(
+            "Using the API key from {}; rename it to BRAVE_API_KEY, the variable this server reads",
+            name
+        )),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_file_with_bom_and_crlf_is_cleaned() {
+        let dir = temp_dir::TempDir::new().unwrap();
+        let path = dir.child("api_key");
+        std::fs::write(&path, "\u{feff}BSA-abc def\r\n").unwrap();
+
+        assert_eq!(read_api_key_file(&path).unwrap(), "BSA-abc def");
+    }
+
+    #[test]
+    fn test_blank_key_is_rejected() {
+        assert_eq!(parse_api_key("  key\n").unwrap(), "key");
+        let err = parse_api_key("\u{feff} \r\n").unwrap_err();
+        assert_eq!(err.to_string(), "API key is empty");
+
+        let missing = read_api_key_file(Path::new("/nonexistent/api_key")).unwrap_err();
+        assert!(missing
+            .to_string()
+            .starts_with("Failed to read API key file"));
+    }
+
+    #[test]
+    fn test_misnamed_env_var_is_used_with_warning() {
+        let env = |name: &str| (name == "BRAVE_KEY").then(|| "BSA-misnamed\n".to_string());
+
+        let resolved = get_api_key(None, None, env).unwrap();
+        assert_eq!(resolved.key, "BSA-misnamed");
+        let warning = resolved.warning.unwrap();
+        assert!(warning.contains("BRAVE_KEY"));
+        assert!(warning.contains("rename it to BRAVE_API_KEY"));
+
+        // The canonical variable (or flag) wins without a warning
+        let resolved = get_api_key(Some("BSA-canonical"), None, env).unwrap();
+        assert_eq!(resolved.key, "BSA-canonical");
+        assert!(resolved.warning.is_none());
+
+        // Blank values do not count as a key
+        let blank = |name: &str| (name == "BRAVE_SEARCH_API_KEY").then(|| " ".to_string());
+        let err = get_api_key(None, None, blank).unwrap_err();
+        assert!(err.to_string().starts_with("Brave API key is required"));
+    }
+}
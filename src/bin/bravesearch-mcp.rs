@@ -1,7 +1,21 @@
 use anyhow::Result;
-use bravesearch_mcp::tools::BraveSearchRouter;
-use clap::{Parser, Subcommand};
+use bravesearch_mcp::api_key;
+use bravesearch_mcp::logging::{self, LogFormat};
+use bravesearch_mcp::transport::sse_server::{self, SseOptions};
+use bravesearch_mcp::{
+    BraveSearchRouter, HmacSigner, OutputFormat, RateLimitBehavior, RateLimiterConfig,
+    SafeSearchLevel, DEFAULT_COMPACT_TITLE_WIDTH, DEFAULT_DEEP_PAGINATION_THRESHOLD,
+    DEFAULT_HIGHLIGHT_MARKER, DEFAULT_MAX_RETRIES, DEFAULT_QUOTA_LOG_INTERVAL,
+    DEFAULT_RETRY_BASE_MS, RATE_LIMIT_PER_MONTH, RATE_LIMIT_PER_SECOND,
+};
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
+use std::ffi::OsString;
 use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 use tracing_subscriber::{self, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 #[derive(Parser)]
@@ -10,8 +24,110 @@ use tracing_subscriber::{self, layer::SubscriberExt, util::SubscriberInitExt, En
 #[command(disable_version_flag = true)]
 struct Cli {
     /// Brave API key, required via BRAVE_API_KEY environment variable or --api-key flag
-    #[arg(short, long, env = "BRAVE_API_KEY", required = true)]
-    api_key: String,
+    /// unless --api-key-file is given; BRAVE_SEARCH_API_KEY and similar misnamed variables
+    /// are used as a last resort, with a warning
+    #[arg(short, long, env = "BRAVE_API_KEY")]
+    api_key: Option<String>,
+
+    /// File containing the Brave API key; takes precedence over BRAVE_API_KEY, and cannot be
+    /// combined with --api-key
+    #[arg(long, env = "BRAVE_API_KEY_FILE")]
+    api_key_file: Option<PathBuf>,
+
+    /// Default freshness for news searches that omit one (e.g. pd, pw, pm, py)
+    #[arg(long, env = "BRAVE_DEFAULT_NEWS_FRESHNESS")]
+    default_news_freshness: Option<String>,
+
+    /// Monthly sub-budget for a single tool as TOOL=COUNT (e.g. brave_local_search=2000); repeatable
+    #[arg(long = "tool-budget", value_parser = parse_tool_budget)]
+    tool_budgets: Vec<(String, usize)>,
+
+    /// Requests allowed per second; raise it for plans above the free tier
+    #[arg(long, value_name = "N", default_value_t = RATE_LIMIT_PER_SECOND)]
+    rate_per_second: usize,
+
+    /// Requests allowed per 30-day month; raise it for plans above the free tier
+    #[arg(long, value_name = "N", default_value_t = RATE_LIMIT_PER_MONTH)]
+    rate_per_month: usize,
+
+    /// What a request over the per-second limit does: fail at once, or wait for the next
+    /// second (up to 5 seconds in total); the monthly limit always fails at once
+    #[arg(long, value_enum, default_value_t = RateLimitBehavior::Error)]
+    rate_limit_behavior: RateLimitBehavior,
+
+    /// Minimum seconds between logs of an exhausted monthly quota; 0 logs every rejected call
+    #[arg(long, default_value_t = DEFAULT_QUOTA_LOG_INTERVAL.as_secs())]
+    quota_log_interval_secs: u64,
+
+    /// Requests already used this month by another process sharing the API key, counted
+    /// against the monthly limit from the start; must be below the limit
+    #[arg(long, default_value_t = 0)]
+    initial_monthly_count: usize,
+
+    /// Retries for transient API failures (connection errors, 429, 5xx); 0 disables retrying
+    #[arg(long, default_value_t = DEFAULT_MAX_RETRIES)]
+    max_retries: u32,
+
+    /// Base backoff delay in milliseconds, doubled per retry and jittered by ±25%; no retry waits more than 10 minutes
+    #[arg(long, default_value_t = DEFAULT_RETRY_BASE_MS)]
+    retry_base_ms: u64,
+
+    /// Minimum milliseconds between outbound API requests, delaying bursts instead of rejecting them; 0 disables pacing
+    #[arg(long, default_value_t = 0)]
+    min_request_interval_ms: u64,
+
+    /// Depth (offset x count) beyond which results get a deep-pagination note; 0 disables the note
+    #[arg(long, default_value_t = DEFAULT_DEEP_PAGINATION_THRESHOLD)]
+    deep_pagination_threshold: usize,
+
+    /// Seconds to cache successful API responses (partitioned by API key); 0 disables caching
+    #[arg(long, default_value_t = 0)]
+    cache_ttl_secs: u64,
+
+    /// Seconds to cache responses without results; must be shorter than --cache-ttl-secs when both are set; 0 disables
+    #[arg(long, default_value_t = 0)]
+    negative_cache_ttl_secs: u64,
+
+    /// Serve the cached response for a query, even an expired one, when the per-second limit is hit
+    #[arg(long)]
+    serve_stale_on_limit: bool,
+
+    /// Secret for signing requests with HMAC-SHA256 (X-Signature headers) for a gateway in front of Brave
+    #[arg(long, env = "BRAVE_GATEWAY_HMAC_SECRET", hide_env_values = true)]
+    gateway_hmac_secret: Option<String>,
+
+    /// Extra header sent on every Brave request, as NAME:VALUE (repeatable); the API key header
+    /// and other reserved or credential-like names are rejected
+    #[arg(long = "header", value_name = "NAME:VALUE", value_parser = parse_header)]
+    headers: Vec<(String, String)>,
+
+    /// Safesearch level sent on every search, overriding any level a client asks for
+    #[arg(long, value_enum)]
+    force_safesearch: Option<SafeSearchLevel>,
+
+    /// Enable operator tools such as brave_cache_clear
+    #[arg(long)]
+    admin_tools: bool,
+
+    /// Serve canned demo results for a few queries instead of calling Brave; no API key needed
+    #[arg(long)]
+    demo: bool,
+
+    /// Marker wrapped around query terms when a call passes highlight
+    #[arg(long, default_value = DEFAULT_HIGHLIGHT_MARKER)]
+    highlight_marker: String,
+
+    /// Result format used when a tool call does not pass output_format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    output_format: OutputFormat,
+
+    /// Characters of each title kept by the compact output format before it is cut with an ellipsis
+    #[arg(long, default_value_t = DEFAULT_COMPACT_TITLE_WIDTH)]
+    compact_title_width: usize,
+
+    /// Log output format: human-readable lines or one JSON object per line
+    #[arg(long, value_enum, default_value_t = LogFormat::Human)]
+    log_format: LogFormat,
 
     #[command(subcommand)]
     command: Commands,
@@ -24,6 +140,10 @@ enum Commands {
         /// Enable debug logging
         #[arg(short, long)]
         debug: bool,
+
+        /// Exit after answering the first tool call (for per-invocation environments)
+        #[arg(long)]
+        once: bool,
     },
     /// Run the server with HTTP/SSE interface
     Http {
@@ -34,57 +154,276 @@ enum Commands {
         /// Enable debug logging
         #[arg(short, long)]
         debug: bool,
+
+        /// Maximum concurrent SSE sessions; further connections get 429 (unlimited when unset)
+        #[arg(long)]
+        max_sessions: Option<usize>,
+
+        /// Origin allowed to connect from a browser via CORS (repeatable; `*` for any).
+        /// Without it no CORS headers are sent
+        #[arg(long = "allow-origin", value_name = "ORIGIN")]
+        allow_origins: Vec<String>,
+
+        /// Require `Authorization: Bearer <TOKEN>` on every request (off by default)
+        #[arg(long, env = "BRAVE_MCP_AUTH_TOKEN", value_name = "TOKEN")]
+        auth_token: Option<String>,
+
+        /// Tool calls each SSE session may make per minute; the next call gets 429 (unlimited when unset)
+        #[arg(long, value_name = "N")]
+        session_calls_per_minute: Option<u32>,
+
+        /// Serve a search page at `/` for trying the tools from a browser
+        #[arg(long)]
+        serve_ui: bool,
     },
+    /// Check the API key, rate limiter, and response parsing, then exit
+    Selftest,
+}
+
+impl Cli {
+    /// Parse `args`, rejecting flag combinations that cannot all take effect with a usage error
+    /// instead of letting one of them silently win
+    fn try_parse_validated<I, T>(args: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let mut command = Self::command();
+        let matches = command.try_get_matches_from_mut(args)?;
+        let cli = Self::from_arg_matches(&matches)?;
+        if let Some(message) = cli.conflict(&matches) {
+            return Err(command.error(ErrorKind::ArgumentConflict, message));
+        }
+        if !cli.demo
+            && cli.api_key.is_none()
+            && cli.api_key_file.is_none()
+            && api_key::find_misnamed_env_key(|name| std::env::var(name).ok()).is_none()
+        {
+            return Err(command.error(
+                ErrorKind::MissingRequiredArgument,
+                "the Brave API key is required: set BRAVE_API_KEY or pass --api-key or --api-key-file",
+            ));
+        }
+        Ok(cli)
+    }
+
+    /// The first incompatible combination of flags, described for the user
+    fn conflict(&self, matches: &ArgMatches) -> Option<String> {
+        // BRAVE_API_KEY from the environment may stay set; only an explicit flag is ambiguous
+        if self.api_key_file.is_some()
+            && matches.value_source("api_key") == Some(ValueSource::CommandLine)
+        {
+            return Some(
+                "--api-key cannot be used with --api-key-file; pass the key one way".to_string(),
+            );
+        }
+        if self.cache_ttl_secs > 0
+            && self.negative_cache_ttl_secs > 0
+            && self.negative_cache_ttl_secs >= self.cache_ttl_secs
+        {
+            return Some(format!(
+                "--negative-cache-ttl-secs ({}) must be shorter than --cache-ttl-secs ({})",
+                self.negative_cache_ttl_secs, self.cache_ttl_secs
+            ));
+        }
+        if self.demo && matches!(self.command, Commands::Selftest) {
+            return Some("selftest checks the API key, which --demo does not use".to_string());
+        }
+        if self.serve_stale_on_limit
+            && self.cache_ttl_secs == 0
+            && self.negative_cache_ttl_secs == 0
+        {
+            return Some("--serve-stale-on-limit needs a cache; set --cache-ttl-secs".to_string());
+        }
+        None
+    }
+}
+
+/// Parse a `TOOL=COUNT` tool budget argument
+fn parse_tool_budget(value: &str) -> Result<(String, usize), String> {
+    let (tool, count) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected TOOL=COUNT, got '{}'", value))?;
+    let count = count
+        .trim()
+        .parse::<usize>()
+        .map_err(|e| format!("invalid budget count '{}': {}", count, e))?;
+    Ok((tool.trim().to_string(), count))
+}
+
+fn parse_header(value: &str) -> Result<(String, String), String> {
+    let (name, header_value) = value
+        .split_once(':')
+        .ok_or_else(|| format!("expected NAME:VALUE, got '{}'", value))?;
+    Ok((name.trim().to_string(), header_value.trim().to_string()))
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let cli = Cli::parse();
-    let api_key = cli.api_key;
+    let cli = Cli::try_parse_validated(std::env::args_os()).unwrap_or_else(|e| e.exit());
+
+    // Demo mode never calls Brave, so it runs without a key
+    let api_key = if cli.demo {
+        String::new()
+    } else {
+        let api_key = api_key::get_api_key(
+            cli.api_key.as_deref(),
+            cli.api_key_file.as_deref(),
+            |name| std::env::var(name).ok(),
+        )?;
+        // Logging is set up per transport later, so warn on stderr directly
+        if let Some(warning) = &api_key.warning {
+            eprintln!("Warning: {}", warning);
+        }
+        api_key.key
+    };
+
+    let rate_limits = RateLimiterConfig {
+        per_second: cli.rate_per_second,
+        per_month: cli.rate_per_month,
+    };
+    let mut router = BraveSearchRouter::with_rate_limits(api_key, rate_limits)?
+        .with_default_news_freshness(cli.default_news_freshness)
+        .with_retries(cli.max_retries, Duration::from_millis(cli.retry_base_ms))
+        .with_request_pacing(
+            Some(Duration::from_millis(cli.min_request_interval_ms)).filter(|i| !i.is_zero()),
+        )
+        .with_output_format(cli.output_format)
+        .with_compact_title_width(cli.compact_title_width)
+        .with_highlight_marker(cli.highlight_marker)
+        .with_forced_safesearch(cli.force_safesearch)
+        .with_admin_tools(cli.admin_tools)
+        .with_demo_mode(cli.demo)
+        .with_cache_ttl(Some(Duration::from_secs(cli.cache_ttl_secs)).filter(|ttl| !ttl.is_zero()))
+        .with_negative_cache_ttl(
+            Some(Duration::from_secs(cli.negative_cache_ttl_secs)).filter(|ttl| !ttl.is_zero()),
+        )
+        .with_serve_stale_on_limit(cli.serve_stale_on_limit)
+        .with_rate_limit_behavior(cli.rate_limit_behavior)
+        .with_quota_log_interval(Duration::from_secs(cli.quota_log_interval_secs))
+        .with_deep_pagination_threshold(
+            Some(cli.deep_pagination_threshold).filter(|&threshold| threshold > 0),
+        );
+    if let Some(secret) = &cli.gateway_hmac_secret {
+        router = router.with_signer(Arc::new(HmacSigner::new(secret)));
+    }
+    for (name, value) in &cli.headers {
+        router = router.with_custom_header(name, value)?;
+    }
+    for (tool, monthly_limit) in &cli.tool_budgets {
+        router = router.with_tool_budget(tool, *monthly_limit)?;
+    }
+    if cli.initial_monthly_count > 0 {
+        router = router.with_initial_monthly_count(cli.initial_monthly_count)?;
+    }
 
     match cli.command {
-        Commands::Stdio { debug } => run_stdio_server(api_key, debug).await,
-        Commands::Http { address, debug } => run_http_server(api_key, address, debug).await,
+        Commands::Stdio { debug, once } => {
+            run_stdio_server(router, debug, once, cli.log_format).await
+        }
+        Commands::Http {
+            address,
+            debug,
+            max_sessions,
+            allow_origins,
+            auth_token,
+            session_calls_per_minute,
+            serve_ui,
+        } => {
+            let options = SseOptions {
+                max_sessions,
+                allowed_origins: allow_origins,
+                auth_token,
+                session_calls_per_minute,
+                serve_ui,
+            };
+            run_http_server(router, address, debug, options, cli.log_format).await
+        }
+        Commands::Selftest => run_self_test(router).await,
     }
 }
 
-async fn run_stdio_server(api_key: String, debug: bool) -> Result<()> {
+async fn run_self_test(router: BraveSearchRouter) -> Result<()> {
+    let report = router.run_self_test().await;
+    println!("{}", report);
+
+    if report.passed() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "Self-test failed: {}",
+            report.failures().join(", ")
+        ))
+    }
+}
+
+async fn run_stdio_server(
+    router: BraveSearchRouter,
+    debug: bool,
+    once: bool,
+    log_format: LogFormat,
+) -> Result<()> {
     // Initialize the tracing subscriber with stderr logging
     let level = if debug {
         tracing::Level::DEBUG
     } else {
         tracing::Level::INFO
     };
+    let filter = EnvFilter::from_default_env().add_directive(level.into());
 
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env().add_directive(level.into()))
-        .with_writer(std::io::stderr) // Explicitly use stderr for logging
-        .with_target(false)
-        .with_thread_ids(true)
-        .with_file(true)
-        .with_line_number(true)
-        .with_ansi(false) // Disable ANSI color codes
-        .init();
+    match log_format {
+        LogFormat::Human => tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .with_writer(std::io::stderr) // Explicitly use stderr for logging
+            .with_target(false)
+            .with_thread_ids(true)
+            .with_file(true)
+            .with_line_number(true)
+            .with_ansi(false) // Disable ANSI color codes
+            .init(),
+        LogFormat::Json => logging::json_subscriber(filter, std::io::stderr).init(),
+    }
 
     tracing::info!("Starting Brave Search MCP server in STDIN/STDOUT mode");
 
-    // Run the server using the implementation
-    bravesearch_mcp::transport::stdio::run_stdio_server(api_key)
-        .await
-        .map_err(|e| anyhow::anyhow!("Error running STDIO server: {}", e))
+    // Run the server using the implementation; Ctrl+C stops it once in-flight calls are answered
+    let result = if once {
+        bravesearch_mcp::transport::stdio::run_stdio_server_once(router).await
+    } else {
+        bravesearch_mcp::transport::stdio::run_stdio_server(router).await
+    };
+    tracing::info!("Brave Search MCP server stopped");
+
+    // Tokio's stdin reader is parked in a blocking read that would hold up runtime shutdown
+    // until the client sends another line, so exit as soon as the server has stopped
+    match result {
+        Ok(()) => std::process::exit(0),
+        Err(e) => {
+            eprintln!("Error running STDIO server: {}", e);
+            std::process::exit(1)
+        }
+    }
 }
 
-async fn run_http_server(api_key: String, address: String, debug: bool) -> Result<()> {
+async fn run_http_server(
+    router: BraveSearchRouter,
+    address: String,
+    debug: bool,
+    options: SseOptions,
+    log_format: LogFormat,
+) -> Result<()> {
     // Setup tracing
     let level = if debug { "debug" } else { "info" };
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| format!("{},{}", level, env!("CARGO_CRATE_NAME")).into());
 
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| format!("{},{}", level, env!("CARGO_CRATE_NAME")).into()),
-        )
-        .with(tracing_subscriber::fmt::layer().with_ansi(false)) // Disable ANSI color codes
-        .init();
+    match log_format {
+        LogFormat::Human => tracing_subscriber::registry()
+            .with(filter)
+            .with(tracing_subscriber::fmt::layer().with_ansi(false)) // Disable ANSI color codes
+            .init(),
+        LogFormat::Json => logging::json_subscriber(filter, std::io::stdout).init(),
+    }
 
     // Parse socket address
     let addr: SocketAddr = address.parse()?;
@@ -92,9 +431,8 @@ async fn run_http_server(api_key: String, address: String, debug: bool) -> Resul
     tracing::debug!("Brave Search MCP Server listening on {}", addr);
     tracing::info!("Access the Brave Search MCP Server at http://{}/sse", addr);
 
-    // Create and run server
-    let service = BraveSearchRouter::new(api_key);
-    let server = bravesearch_mcp::transport::sse_server::serve(service, addr.port())
+    // Run server
+    let server = sse_server::serve(router, addr.port(), options)
         .await
         .map_err(|e| anyhow::anyhow!("Error starting SSE server: {}", e))?;
 
@@ -103,3 +441,64 @@ async fn run_http_server(api_key: String, address: String, debug: bool) -> Resul
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_validated(["bravesearch-mcp"].iter().chain(args))
+    }
+
+    #[test]
+    fn test_conflicting_flags_are_usage_errors() {
+        for args in [
+            &[
+                "--api-key",
+                "key",
+                "--api-key-file",
+                "/run/secrets/key",
+                "stdio",
+            ][..],
+            &[
+                "--api-key",
+                "key",
+                "--cache-ttl-secs",
+                "60",
+                "--negative-cache-ttl-secs",
+                "60",
+                "stdio",
+            ],
+            &["--api-key", "key", "--serve-stale-on-limit", "stdio"],
+            &["--demo", "selftest"],
+        ] {
+            let err = parse(args).err().expect("conflict was accepted");
+            assert_eq!(err.kind(), ErrorKind::ArgumentConflict, "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn test_compatible_flags_parse() {
+        let cli = parse(&[
+            "--api-key",
+            "key",
+            "--cache-ttl-secs",
+            "60",
+            "--serve-stale-on-limit",
+            "--header",
+            "X-Tenant-Id: acme",
+            "--rate-limit-behavior",
+            "wait",
+            "stdio",
+        ])
+        .unwrap();
+        assert!(cli.serve_stale_on_limit);
+        assert_eq!(cli.rate_limit_behavior, RateLimitBehavior::Wait);
+        assert_eq!(
+            cli.headers,
+            vec![("X-Tenant-Id".to_string(), "acme".to_string())]
+        );
+        // Demo mode needs no API key
+        assert!(parse(&["--demo", "stdio"]).unwrap().demo);
+    }
+}
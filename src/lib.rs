@@ -1,5 +1,15 @@
+pub mod api_key;
+pub mod logging;
 pub mod tools;
 pub mod transport;
 
 // Re-export the main router for easier access
-pub use tools::BraveSearchRouter;
+pub use tools::{
+    BraveSearchRouter, HmacSigner, LocalSearchParams, MemoryCache, NewsSearchParams, NoopSigner,
+    OutputFormat, QueryBuilder, QueryOperators, QueryPreprocessor, RateLimitBehavior,
+    RateLimiterConfig, RequestSigner, ResultFormatter, SafeSearchLevel, SearchCache,
+    SiteRestriction, SuggestParams, WebSearchBatchParams, WebSearchItem, WebSearchParams,
+    DEFAULT_COMPACT_TITLE_WIDTH, DEFAULT_DEEP_PAGINATION_THRESHOLD, DEFAULT_HIGHLIGHT_MARKER,
+    DEFAULT_MAX_RETRIES, DEFAULT_QUOTA_LOG_INTERVAL, DEFAULT_RETRY_BASE_MS, RATE_LIMIT_PER_MONTH,
+    RATE_LIMIT_PER_SECOND,
+};
@@ -0,0 +1,116 @@
+use tracing::Subscriber;
+use tracing_subscriber::{fmt::MakeWriter, EnvFilter};
+
+/// Output format for server logs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable lines (default)
+    #[default]
+    Human,
+    /// One JSON object per line, for log aggregators
+    Json,
+}
+
+/// Build a subscriber that writes each event as a JSON object on its own line.
+///
+/// Lines carry `timestamp`, `level`, `target`, and the event's `fields` (including `message`).
+/// Only fields recorded on events are serialized; the API key is never recorded.
+pub fn json_subscriber<W>(filter: EnvFilter, writer: W) -> impl Subscriber + Send + Sync
+where
+    W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt()
+        .json()
+        .with_env_filter(filter)
+        .with_writer(writer)
+        .with_current_span(false)
+        .with_span_list(false)
+        .finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BraveSearchRouter, WebSearchParams};
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    // Writer collecting log output in memory
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'writer> MakeWriter<'writer> for Buffer {
+        type Writer = Buffer;
+
+        fn make_writer(&'writer self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    impl Buffer {
+        fn lines(&self) -> Vec<serde_json::Value> {
+            let output = String::from_utf8(self.0.lock().unwrap().clone()).unwrap();
+            output
+                .lines()
+                .map(|line| serde_json::from_str(line).expect("log line is not valid JSON"))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_json_log_lines_have_expected_fields() {
+        let buffer = Buffer::default();
+        let subscriber = json_subscriber(EnvFilter::new("info"), buffer.clone());
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(port = 3000, "Server listening");
+            tracing::debug!("Filtered out below info");
+        });
+
+        let lines = buffer.lines();
+        assert_eq!(lines.len(), 1);
+        let line = &lines[0];
+        assert!(line["timestamp"].is_string());
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["target"], "bravesearch_mcp::logging::tests");
+        assert_eq!(line["fields"]["message"], "Server listening");
+        assert_eq!(line["fields"]["port"], 3000);
+    }
+
+    #[tokio::test]
+    async fn test_json_logs_never_contain_api_key() {
+        let buffer = Buffer::default();
+        let subscriber = json_subscriber(EnvFilter::new("trace"), buffer.clone());
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        // A failing, retried search exercises the request and retry log paths
+        let api_key = "BSA-secret-key-0123456789";
+        let router = BraveSearchRouter::new(api_key.to_string())
+            .with_base_url("http://127.0.0.1:1".to_string())
+            .with_retries(1, Duration::from_millis(1));
+        router
+            .brave_web_search(WebSearchParams {
+                query: "rust".to_string(),
+                ..Default::default()
+            })
+            .await;
+
+        let lines = buffer.lines();
+        assert!(!lines.is_empty());
+        for line in lines {
+            assert!(!line.to_string().contains(api_key), "{}", line);
+        }
+    }
+}
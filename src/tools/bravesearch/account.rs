@@ -0,0 +1,224 @@
+use anyhow::Result;
+use reqwest::header::HeaderMap;
+
+use super::{demo, BraveSearchRouter};
+
+// Best-effort account report for `brave_account_info`.
+//
+// Brave does not expose plan or subscription details through the Search API,
+// so the report is built from the rate-limit headers returned with a minimal
+// web search. Brave sends comma-separated values with one entry per window,
+// e.g. `X-RateLimit-Limit: 1, 15000` with `X-RateLimit-Policy: 1;w=1, 15000;w=2592000`.
+
+/// Quota state of one rate-limit window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct QuotaWindow {
+    /// Window length in seconds, if the policy header named it
+    pub(crate) window_secs: Option<u64>,
+    pub(crate) limit: u64,
+    pub(crate) remaining: Option<u64>,
+    pub(crate) reset_secs: Option<u64>,
+}
+
+/// Parse a comma-separated header of integers, keeping the entries that parse
+fn header_values(headers: &HeaderMap, name: &str) -> Vec<Option<u64>> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| {
+            value
+                .split(',')
+                .map(|entry| entry.trim().parse::<u64>().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Window lengths from `X-RateLimit-Policy` entries such as `15000;w=2592000`
+fn policy_windows(headers: &HeaderMap) -> Vec<Option<u64>> {
+    headers
+        .get("x-ratelimit-policy")
+        .and_then(|value| value.to_str().ok())
+        .map(|value| {
+            value
+                .split(',')
+                .map(|entry| {
+                    entry
+                        .split(';')
+                        .find_map(|part| part.trim().strip_prefix("w="))
+                        .and_then(|window| window.parse::<u64>().ok())
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Extract the quota windows described by Brave's rate-limit headers
+pub(crate) fn quota_windows(headers: &HeaderMap) -> Vec<QuotaWindow> {
+    let remaining = header_values(headers, "x-ratelimit-remaining");
+    let reset = header_values(headers, "x-ratelimit-reset");
+    let windows = policy_windows(headers);
+
+    header_values(headers, "x-ratelimit-limit")
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, limit)| {
+            Some(QuotaWindow {
+                window_secs: windows.get(i).copied().flatten(),
+                limit: limit?,
+                remaining: remaining.get(i).copied().flatten(),
+                reset_secs: reset.get(i).copied().flatten(),
+            })
+        })
+        .collect()
+}
+
+/// Human-readable name for a window length
+fn window_label(window_secs: Option<u64>) -> String {
+    match window_secs {
+        Some(1) => "Per second".to_string(),
+        Some(60) => "Per minute".to_string(),
+        Some(3600) => "Per hour".to_string(),
+        Some(86400) => "Per day".to_string(),
+        // Brave uses a 30-day window for monthly quotas
+        Some(2592000) => "Per month".to_string(),
+        Some(secs) => format!("Per {} seconds", secs),
+        None => "Window unknown".to_string(),
+    }
+}
+
+fn format_window(window: &QuotaWindow) -> String {
+    let mut line = format!(
+        "{}: limit {}",
+        window_label(window.window_secs),
+        window.limit
+    );
+    if let Some(remaining) = window.remaining {
+        line.push_str(&format!(", remaining {}", remaining));
+    }
+    if let Some(reset) = window.reset_secs {
+        line.push_str(&format!(", resets in {}s", reset));
+    }
+    line
+}
+
+impl BraveSearchRouter {
+    /// Report quota limits from the rate-limit headers of a minimal web search
+    pub(crate) async fn perform_account_info(&self) -> Result<String> {
+        if self.demo {
+            return Ok(demo::DEMO_ACCOUNT_INFO.to_string());
+        }
+        let url = reqwest::Url::parse_with_params(
+            &format!("{}/res/v1/web/search", self.base_url),
+            &[("q", "brave"), ("count", "1")],
+        )?;
+        let (headers, _) = self.send_request_with_headers(url).await?;
+
+        let mut lines = vec![
+            "Plan: not exposed by the Brave API (limits below are reported by the API's rate-limit headers)".to_string(),
+        ];
+        let windows = quota_windows(&headers);
+        if windows.is_empty() {
+            lines.push("API quota: unknown (no rate-limit headers were returned)".to_string());
+        } else {
+            lines.extend(windows.iter().map(format_window));
+        }
+
+        let (per_second, per_month, used) = self.rate_limiter.usage().await;
+        lines.push(format!(
+            "Server limiter: {} per second, {} per month ({} used this month, including this check)",
+            per_second, per_month, used
+        ));
+
+        Ok(lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_fixtures::{json_mock, router_for};
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_quota_windows_from_headers() {
+        let windows = quota_windows(&headers(&[
+            ("x-ratelimit-limit", "1, 15000"),
+            ("x-ratelimit-policy", "1;w=1, 15000;w=2592000"),
+            ("x-ratelimit-remaining", "0, 14321"),
+            ("x-ratelimit-reset", "1, 1419704"),
+        ]));
+        assert_eq!(
+            windows,
+            vec![
+                QuotaWindow {
+                    window_secs: Some(1),
+                    limit: 1,
+                    remaining: Some(0),
+                    reset_secs: Some(1),
+                },
+                QuotaWindow {
+                    window_secs: Some(2592000),
+                    limit: 15000,
+                    remaining: Some(14321),
+                    reset_secs: Some(1419704),
+                },
+            ]
+        );
+
+        // Missing companion headers leave the corresponding fields empty
+        let windows = quota_windows(&headers(&[("x-ratelimit-limit", "20")]));
+        assert_eq!(windows.len(), 1);
+        assert_eq!(windows[0].window_secs, None);
+        assert_eq!(windows[0].remaining, None);
+    }
+
+    #[tokio::test]
+    async fn test_account_info_from_mocked_headers() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::UrlEncoded("count".into(), "1".into()))
+            .with_header("content-type", "application/json")
+            .with_header("x-ratelimit-limit", "1, 15000")
+            .with_header("x-ratelimit-policy", "1;w=1, 15000;w=2592000")
+            .with_header("x-ratelimit-remaining", "0, 14321")
+            .with_header("x-ratelimit-reset", "1, 1419704")
+            .with_body(r#"{"type":"search","web":{"results":[]}}"#)
+            .create_async()
+            .await;
+
+        let router = router_for(&server);
+
+        let report = router.brave_account_info().await;
+        assert!(report.starts_with("Plan: not exposed by the Brave API"));
+        assert!(report.contains("\nPer second: limit 1, remaining 0, resets in 1s\n"));
+        assert!(report.contains("\nPer month: limit 15000, remaining 14321, resets in 1419704s\n"));
+        assert!(
+            report.contains("Server limiter: 10 per second, 15000 per month (1 used this month")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_account_info_without_headers() {
+        let mut server = mockito::Server::new_async().await;
+        json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","web":{"results":[]}}"#,
+        )
+        .create_async()
+        .await;
+
+        let router = router_for(&server);
+        let report = router.brave_account_info().await;
+        assert!(report.contains("API quota: unknown (no rate-limit headers were returned)"));
+    }
+}
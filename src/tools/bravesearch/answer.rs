@@ -0,0 +1,109 @@
+use super::highlight::query_terms;
+
+// Direct-answer extraction for web searches.
+//
+// A lightweight heuristic rather than a model: each sentence in the top
+// results' descriptions is scored by how many query words it contains and
+// whether it reads like an answer (a definition or a figure). The best
+// sentence wins, with earlier results preferred on ties.
+
+// Only the top results are considered; lower results rarely hold the answer
+const ANSWER_CANDIDATES: usize = 3;
+
+// Phrases typical of definitional or factual answers
+const ANSWER_CUES: &[&str] = &[" is ", " are ", " was ", " were ", " means ", " refers to "];
+
+/// The most likely direct-answer sentence for `query` among the first descriptions, if any
+/// sentence mentions the query and reads like an answer
+pub(crate) fn extract_answer<'a>(
+    query: &str,
+    descriptions: impl IntoIterator<Item = &'a str>,
+) -> Option<String> {
+    let terms = query_terms(query);
+    if terms.is_empty() {
+        return None;
+    }
+
+    let mut best: Option<(usize, String)> = None;
+    for description in descriptions.into_iter().take(ANSWER_CANDIDATES) {
+        for sentence in sentences(&strip_tags(description)) {
+            let score = score(sentence, &terms);
+            if score > 0 && best.as_ref().is_none_or(|(best, _)| score > *best) {
+                best = Some((score, sentence.to_string()));
+            }
+        }
+    }
+    best.map(|(_, sentence)| sentence)
+}
+
+/// Two points per query word present, plus one for an answer cue and one for a figure;
+/// zero unless the sentence has both a query word and an answer cue or figure
+fn score(sentence: &str, terms: &[String]) -> usize {
+    let words = query_terms(sentence);
+    let matched = terms
+        .iter()
+        .filter(|term| words.binary_search(term).is_ok())
+        .count();
+    let lowercase = format!(" {} ", sentence.to_lowercase());
+    let cue = ANSWER_CUES.iter().any(|cue| lowercase.contains(cue));
+    let figure = sentence.chars().any(|c| c.is_ascii_digit());
+    if matched == 0 || !(cue || figure) {
+        return 0;
+    }
+    matched * 2 + usize::from(cue) + usize::from(figure)
+}
+
+/// Sentences of `text`, split after `.`, `?`, or `!` followed by whitespace
+fn sentences(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text.trim();
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let end = rest
+            .char_indices()
+            .zip(rest.chars().skip(1))
+            .find(|((_, c), next)| matches!(c, '.' | '?' | '!') && next.is_whitespace())
+            .map_or(rest.len(), |((i, _), _)| i + 1);
+        let (sentence, tail) = rest.split_at(end);
+        rest = tail.trim_start();
+        Some(sentence.trim())
+    })
+}
+
+/// `text` without HTML tags such as the `<strong>` emphasis Brave adds to descriptions
+fn strip_tags(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => output.push(c),
+            _ => {}
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_answer_prefers_answer_style_sentence() {
+        let descriptions = [
+            "Visit Paris this summer. <strong>Paris</strong> is the capital of France, with a population of 2.1 million. Book now!",
+            "France is a country in Western Europe.",
+        ];
+        assert_eq!(
+            extract_answer("capital of France", descriptions).as_deref(),
+            Some("Paris is the capital of France, with a population of 2.1 million.")
+        );
+        // Nothing answer-like about the query
+        assert_eq!(
+            extract_answer("tokio runtime", ["Learn more about our products."]),
+            None
+        );
+    }
+}
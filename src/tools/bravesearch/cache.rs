@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::future::BoxFuture;
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+
+// Cache of successful Brave API response bodies.
+//
+// Entries are keyed by the request URL and the SHA-256 of the API key, so
+// routers using different keys never see each other's results even when they
+// share a cache. The raw key is never stored, and the hash is stable across
+// builds and processes, so a shared backend keeps its entries over restarts. Storage is pluggable (`SearchCache`);
+// by default it is an in-memory map (`MemoryCache`).
+//
+// Responses without any results are cached separately, under a shorter
+// negative TTL, so retrying a dud query does not spend quota while a query
+// that starts returning results is picked up soon.
+//
+// The memory backend keeps expired entries until they are evicted, so a
+// rate-limited request can still be answered from them (`get_stale`).
+
+// Upper bound on cached responses; the least recently used entry is evicted beyond this
+const MAX_CACHE_ENTRIES: usize = 1000;
+
+// Result containers found in Brave responses (web, news, local POIs, descriptions)
+const RESULT_POINTERS: &[&str] = &[
+    "/results",
+    "/web/results",
+    "/locations/results",
+    "/news/results",
+    "/videos/results",
+    "/descriptions",
+];
+
+/// Whether a response body has at least one of Brave's result containers, all of them empty.
+///
+/// Bodies without any known container (e.g. the suggest endpoint's array) are not empty.
+pub(crate) fn has_no_results(body: &str) -> bool {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(body) else {
+        return false;
+    };
+    let mut containers = RESULT_POINTERS
+        .iter()
+        .filter_map(|pointer| value.pointer(pointer))
+        .peekable();
+    containers.peek().is_some()
+        && containers.all(|container| match container {
+            serde_json::Value::Array(items) => items.is_empty(),
+            serde_json::Value::Object(map) => map.is_empty(),
+            _ => true,
+        })
+}
+
+/// Storage behind the response cache.
+///
+/// The default, `MemoryCache`, keeps entries in process memory. Implement this to share one
+/// cache across a fleet of servers (e.g. backed by Redis or memcached). Keys already include a
+/// hash of the API key, and the router decides each entry's TTL.
+pub trait SearchCache: Send + Sync {
+    /// Stored value for `key`, if present and not yet expired
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<String>>;
+
+    /// Store `value` under `key` for `ttl`, replacing any previous value
+    fn put<'a>(&'a self, key: &'a str, value: String, ttl: Duration) -> BoxFuture<'a, ()>;
+
+    /// Stored value for `key` even after its TTL, for serving stale content when rate limited;
+    /// backends that drop expired entries can keep the default, which finds nothing
+    fn get_stale<'a>(&'a self, _key: &'a str) -> BoxFuture<'a, Option<String>> {
+        Box::pin(async { None })
+    }
+
+    /// Drop every entry, returning how many were stored, or None when the backend cannot
+    /// clear itself or count what it cleared
+    fn clear(&self) -> BoxFuture<'_, Option<usize>> {
+        Box::pin(async { None })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct CacheKey(String);
+
+impl CacheKey {
+    pub(crate) fn new(api_key: &str, url: &reqwest::Url) -> Self {
+        let hash =
+            Sha256::digest(api_key.as_bytes())
+                .iter()
+                .fold(String::new(), |mut hex, byte| {
+                    let _ = write!(hex, "{:02x}", byte);
+                    hex
+                });
+        Self(format!("{}:{}", hash, url))
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+struct CacheEntry {
+    body: String,
+    expires: Instant,
+    last_used: Instant,
+}
+
+/// The default in-process cache backend, evicting the least recently used entry once full
+#[derive(Clone, Default)]
+pub struct MemoryCache {
+    entries: Arc<Mutex<HashMap<String, CacheEntry>>>,
+}
+
+impl SearchCache for MemoryCache {
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<String>> {
+        Box::pin(async move {
+            let mut entries = self.entries.lock().await;
+            let now = Instant::now();
+            let entry = entries.get_mut(key).filter(|entry| now < entry.expires)?;
+            entry.last_used = now;
+            Some(entry.body.clone())
+        })
+    }
+
+    fn put<'a>(&'a self, key: &'a str, value: String, ttl: Duration) -> BoxFuture<'a, ()> {
+        Box::pin(async move {
+            let mut entries = self.entries.lock().await;
+            let now = Instant::now();
+            if entries.len() >= MAX_CACHE_ENTRIES && !entries.contains_key(key) {
+                entries.retain(|_, entry| now < entry.expires);
+                if entries.len() >= MAX_CACHE_ENTRIES {
+                    let least_recent = entries
+                        .iter()
+                        .min_by_key(|(_, entry)| entry.last_used)
+                        .map(|(key, _)| key.clone());
+                    if let Some(least_recent) = least_recent {
+                        entries.remove(&least_recent);
+                    }
+                }
+            }
+            entries.insert(
+                key.to_string(),
+                CacheEntry {
+                    body: value,
+                    expires: now + ttl,
+                    last_used: now,
+                },
+            );
+        })
+    }
+
+    fn get_stale<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<String>> {
+        Box::pin(async move {
+            let mut entries = self.entries.lock().await;
+            let entry = entries.get_mut(key)?;
+            entry.last_used = Instant::now();
+            Some(entry.body.clone())
+        })
+    }
+
+    fn clear(&self) -> BoxFuture<'_, Option<usize>> {
+        Box::pin(async move {
+            let mut entries = self.entries.lock().await;
+            let cleared = entries.len();
+            entries.clear();
+            Some(cleared)
+        })
+    }
+}
+
+/// The router's cache policy (positive and negative TTLs) over a `SearchCache` backend
+#[derive(Clone)]
+pub(crate) struct ResponseCache {
+    ttl: Option<Duration>,
+    negative_ttl: Option<Duration>,
+    backend: Arc<dyn SearchCache>,
+}
+
+impl ResponseCache {
+    /// A cache for responses with results (`ttl`) and without (`negative_ttl`) stored in
+    /// `backend`, or None when both are disabled
+    pub(crate) fn new(
+        ttl: Option<Duration>,
+        negative_ttl: Option<Duration>,
+        backend: Arc<dyn SearchCache>,
+    ) -> Option<Self> {
+        (ttl.is_some() || negative_ttl.is_some()).then(|| Self {
+            ttl,
+            negative_ttl,
+            backend,
+        })
+    }
+
+    pub(crate) fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    pub(crate) fn negative_ttl(&self) -> Option<Duration> {
+        self.negative_ttl
+    }
+
+    /// Cached body for `key`, if present and not yet expired
+    pub(crate) async fn get(&self, key: &CacheKey) -> Option<String> {
+        self.backend.get(key.as_str()).await
+    }
+
+    /// Cached body for `key` even if it has expired, as long as the backend still has it
+    pub(crate) async fn get_stale(&self, key: &CacheKey) -> Option<String> {
+        self.backend.get_stale(key.as_str()).await
+    }
+
+    /// Drop every entry, returning how many were stored when the backend can tell
+    pub(crate) async fn clear(&self) -> Option<usize> {
+        self.backend.clear().await
+    }
+
+    /// Store a response body under the positive or negative TTL, depending on
+    /// whether it has results. Without a negative TTL, empty responses are
+    /// cached like any other; bodies whose TTL is disabled are not stored.
+    pub(crate) async fn insert(&self, key: CacheKey, body: String) {
+        let ttl = if has_no_results(&body) {
+            self.negative_ttl.or(self.ttl)
+        } else {
+            self.ttl
+        };
+        if let Some(ttl) = ttl {
+            self.backend.put(key.as_str(), body, ttl).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(query: &str) -> reqwest::Url {
+        reqwest::Url::parse_with_params(
+            "https://api.search.brave.com/res/v1/web/search",
+            &[("q", query)],
+        )
+        .unwrap()
+    }
+
+    const BODY: &str = r#"{"web":{"results":[{"title":"t"}]}}"#;
+    const EMPTY_BODY: &str = r#"{"type":"search","web":{"results":[]}}"#;
+
+    fn cache(ttl: Duration) -> ResponseCache {
+        ResponseCache::new(Some(ttl), None, Arc::new(MemoryCache::default())).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_entries_expire_after_ttl() {
+        let cache = cache(Duration::from_millis(20));
+        let key = CacheKey::new("key", &url("rust"));
+        cache.insert(key.clone(), BODY.to_string()).await;
+
+        assert_eq!(cache.get(&key).await.as_deref(), Some(BODY));
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(cache.get(&key).await, None);
+        // Still available to callers that accept stale content
+        assert_eq!(cache.get_stale(&key).await.as_deref(), Some(BODY));
+    }
+
+    #[tokio::test]
+    async fn test_empty_responses_use_negative_ttl() {
+        let both = ResponseCache::new(
+            Some(Duration::from_secs(60)),
+            Some(Duration::from_millis(20)),
+            Arc::new(MemoryCache::default()),
+        )
+        .unwrap();
+        let full = CacheKey::new("key", &url("rust"));
+        let empty = CacheKey::new("key", &url("qwzxv"));
+        both.insert(full.clone(), BODY.to_string()).await;
+        both.insert(empty.clone(), EMPTY_BODY.to_string()).await;
+
+        assert!(both.get(&empty).await.is_some());
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(both.get(&empty).await, None);
+        assert!(both.get(&full).await.is_some());
+
+        // Without a negative TTL, empty responses fall back to the positive TTL
+        let positive_only = cache(Duration::from_secs(60));
+        positive_only
+            .insert(empty.clone(), EMPTY_BODY.to_string())
+            .await;
+        assert!(positive_only.get(&empty).await.is_some());
+
+        // With only a negative TTL, responses with results are not cached
+        let negative_only = ResponseCache::new(
+            None,
+            Some(Duration::from_secs(60)),
+            Arc::new(MemoryCache::default()),
+        )
+        .unwrap();
+        negative_only.insert(full.clone(), BODY.to_string()).await;
+        assert_eq!(negative_only.get(&full).await, None);
+    }
+
+    #[test]
+    fn test_has_no_results() {
+        assert!(has_no_results(EMPTY_BODY));
+        assert!(has_no_results(r#"{"type":"news","results":[]}"#));
+        assert!(has_no_results(r#"{"descriptions":{}}"#));
+        assert!(!has_no_results(BODY));
+        assert!(!has_no_results(
+            r#"{"web":{"results":[]},"locations":{"results":[{"id":"a"}]}}"#
+        ));
+        // Unparseable bodies, and bodies of unknown shape, are never treated as empty
+        assert!(!has_no_results("not json"));
+        assert!(!has_no_results(r#"{"type":"search"}"#));
+        assert!(!has_no_results(r#"["qwzx",[]]"#));
+    }
+
+    #[tokio::test]
+    async fn test_keys_are_partitioned_by_api_key() {
+        let cache = cache(Duration::from_secs(60));
+        cache
+            .insert(CacheKey::new("tenant-a", &url("rust")), BODY.to_string())
+            .await;
+
+        assert_eq!(
+            cache.get(&CacheKey::new("tenant-b", &url("rust"))).await,
+            None
+        );
+        assert!(!format!("{:?}", CacheKey::new("tenant-a", &url("rust"))).contains("tenant-a"));
+        // Shared backends outlive the process, so the key hash must not change between builds
+        assert!(CacheKey::new("tenant-a", &url("rust"))
+            .as_str()
+            .starts_with("80a707af7dc77ee1228f9127180f3964835e5beb4c4ab0d812f0fe7593579b3a:"));
+    }
+
+    #[tokio::test]
+    async fn test_least_recently_used_entry_is_evicted_when_full() {
+        let memory = MemoryCache::default();
+        let cache = ResponseCache::new(
+            Some(Duration::from_secs(60)),
+            None,
+            Arc::new(memory.clone()),
+        )
+        .unwrap();
+        let key = |i: usize| CacheKey::new("key", &url(&i.to_string()));
+        for i in 0..MAX_CACHE_ENTRIES {
+            cache.insert(key(i), BODY.to_string()).await;
+        }
+        // Reading the oldest entry keeps it from being evicted
+        tokio::time::sleep(Duration::from_millis(2)).await;
+        assert!(cache.get(&key(0)).await.is_some());
+        cache.insert(key(MAX_CACHE_ENTRIES), BODY.to_string()).await;
+
+        assert_eq!(memory.entries.lock().await.len(), MAX_CACHE_ENTRIES);
+        assert!(cache.get(&key(0)).await.is_some());
+        assert!(cache.get(&key(MAX_CACHE_ENTRIES)).await.is_some());
+    }
+}
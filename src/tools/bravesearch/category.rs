@@ -0,0 +1,128 @@
+use anyhow::{anyhow, Result};
+
+// Category filter for `brave_local_search`.
+//
+// Brave tags location results with free-form category labels such as
+// "Italian restaurant" or "Coffee shop". Each supported category lists the
+// label fragments that count as a match, so a filter for `cafe` also keeps
+// coffee shops and tea houses.
+
+/// Supported category names and the label fragments each one matches
+const LOCAL_CATEGORIES: &[(&str, &[&str])] = &[
+    (
+        "restaurant",
+        &[
+            "restaurant",
+            "diner",
+            "bistro",
+            "pizzeria",
+            "steakhouse",
+            "eatery",
+        ],
+    ),
+    ("cafe", &["cafe", "café", "coffee", "tea house", "tea room"]),
+    ("bar", &["bar", "pub", "tavern", "brewery", "lounge"]),
+    ("bakery", &["bakery", "patisserie", "pastry"]),
+    ("hotel", &["hotel", "motel", "hostel", "inn", "lodging"]),
+    (
+        "grocery",
+        &["grocery", "supermarket", "market", "convenience store"],
+    ),
+    ("pharmacy", &["pharmacy", "drugstore", "chemist"]),
+    ("gas_station", &["gas station", "petrol station", "fuel"]),
+    ("park", &["park", "garden", "playground"]),
+    ("museum", &["museum", "gallery"]),
+];
+
+/// A validated local search category
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LocalCategory {
+    name: &'static str,
+    terms: &'static [&'static str],
+}
+
+impl LocalCategory {
+    /// Look up a category by name (case-insensitive, spaces and dashes read as underscores)
+    pub(crate) fn parse(value: &str) -> Result<Self> {
+        let normalized = value.trim().to_lowercase().replace([' ', '-'], "_");
+        LOCAL_CATEGORIES
+            .iter()
+            .find(|(name, _)| *name == normalized)
+            .map(|&(name, terms)| Self { name, terms })
+            .ok_or_else(|| {
+                let names: Vec<&str> = LOCAL_CATEGORIES.iter().map(|(name, _)| *name).collect();
+                anyhow!(
+                    "Unknown category: {} (expected one of: {})",
+                    value,
+                    names.join(", ")
+                )
+            })
+    }
+
+    /// Words appended to the query to steer Brave toward this category
+    pub(crate) fn query_context(&self) -> String {
+        self.name.replace('_', " ")
+    }
+
+    /// Whether the query already names the category, so appending it would be redundant
+    pub(crate) fn mentioned_in(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        self.terms.iter().any(|term| contains_word(&query, term))
+    }
+
+    /// Whether a result with these Brave category labels belongs to the category.
+    ///
+    /// Results without labels are kept, since Brave does not tag every location.
+    pub(crate) fn matches(&self, labels: &[String]) -> bool {
+        labels.is_empty()
+            || labels.iter().any(|label| {
+                let label = label.to_lowercase();
+                self.terms.iter().any(|term| contains_word(&label, term))
+            })
+    }
+}
+
+/// Whether `term` occurs in `text` on word boundaries ("bar" matches "wine bar", not "barber")
+fn contains_word(text: &str, term: &str) -> bool {
+    text.match_indices(term).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + term.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_validates_against_known_categories() {
+        assert_eq!(LocalCategory::parse("Cafe").unwrap().name, "cafe");
+        assert_eq!(
+            LocalCategory::parse("gas station").unwrap().name,
+            "gas_station"
+        );
+
+        let err = LocalCategory::parse("spaceport").unwrap_err().to_string();
+        assert!(err.starts_with("Unknown category: spaceport (expected one of: restaurant, cafe"));
+    }
+
+    #[test]
+    fn test_matches_on_word_boundaries() {
+        let bar = LocalCategory::parse("bar").unwrap();
+        assert!(bar.matches(&labels(&["Wine bar"])));
+        assert!(bar.matches(&labels(&["Irish Pub", "Restaurant"])));
+        assert!(!bar.matches(&labels(&["Barber shop"])));
+        // Untagged results are kept
+        assert!(bar.matches(&[]));
+
+        let cafe = LocalCategory::parse("cafe").unwrap();
+        assert!(cafe.matches(&labels(&["Coffee shop"])));
+        assert!(cafe.mentioned_in("coffee near Union Square"));
+        assert!(!cafe.mentioned_in("food near Union Square"));
+    }
+}
@@ -0,0 +1,218 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rmcp::model::{LoggingLevel, LoggingMessageNotificationParam};
+use rmcp::service::Peer;
+use rmcp::RoleServer;
+use tokio::sync::Mutex;
+
+// Forwarding of server-side events to the MCP client as logging notifications.
+//
+// The peer is attached when a session starts, and the minimum level follows the
+// client's `logging/setLevel` requests. Without a peer, messages are dropped.
+
+const LOGGER_NAME: &str = "bravesearch";
+
+// Level used until the client asks for another one
+const DEFAULT_LEVEL: LoggingLevel = LoggingLevel::Info;
+
+/// Default minimum gap between logs of a condition that repeats on every call
+pub const DEFAULT_QUOTA_LOG_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Numeric severity of a logging level, lowest first
+fn severity(level: &LoggingLevel) -> u8 {
+    match level {
+        LoggingLevel::Debug => 0,
+        LoggingLevel::Info => 1,
+        LoggingLevel::Notice => 2,
+        LoggingLevel::Warning => 3,
+        LoggingLevel::Error => 4,
+        LoggingLevel::Critical => 5,
+        LoggingLevel::Alert => 6,
+        LoggingLevel::Emergency => 7,
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct ClientLogger {
+    peer: Option<Peer<RoleServer>>,
+    min_level: Arc<Mutex<LoggingLevel>>,
+}
+
+impl Default for ClientLogger {
+    fn default() -> Self {
+        Self {
+            peer: None,
+            min_level: Arc::new(Mutex::new(DEFAULT_LEVEL)),
+        }
+    }
+}
+
+impl ClientLogger {
+    /// Create a logger for a new session, starting from the default level
+    pub(crate) fn for_peer(peer: Peer<RoleServer>) -> Self {
+        Self {
+            peer: Some(peer),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn peer(&self) -> Option<Peer<RoleServer>> {
+        self.peer.clone()
+    }
+
+    pub(crate) async fn set_level(&self, level: LoggingLevel) {
+        *self.min_level.lock().await = level;
+    }
+
+    /// Send `message` to the client if `level` is at or above the requested minimum
+    pub(crate) async fn log(&self, level: LoggingLevel, message: impl Into<String>) {
+        let Some(peer) = &self.peer else {
+            return;
+        };
+        if severity(&level) < severity(&*self.min_level.lock().await) {
+            return;
+        }
+
+        let params = LoggingMessageNotificationParam {
+            level,
+            logger: Some(LOGGER_NAME.to_string()),
+            data: serde_json::Value::String(message.into()),
+        };
+        // A client that went away should not fail the search that triggered the log
+        if let Err(e) = peer.notify_logging_message(params).await {
+            tracing::debug!("Failed to forward log message to client: {}", e);
+        }
+    }
+}
+
+/// Lets a repeating log through at most once per interval. Clones share the last log time,
+/// so all sessions of a router are throttled together
+#[derive(Clone)]
+pub(crate) struct LogThrottle {
+    interval: Duration,
+    last_logged: Arc<std::sync::Mutex<Option<Instant>>>,
+}
+
+impl LogThrottle {
+    pub(crate) fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_logged: Arc::new(std::sync::Mutex::new(None)),
+        }
+    }
+
+    pub(crate) fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Whether to log now, recording it as logged if so
+    pub(crate) fn allow(&self) -> bool {
+        let mut last_logged = self.last_logged.lock().unwrap();
+        let now = Instant::now();
+        if last_logged.is_some_and(|last| now.duration_since(last) < self.interval) {
+            return false;
+        }
+        *last_logged = Some(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_client::{notifications, TestClient};
+    use super::super::{BraveSearchRouter, RateLimiter};
+    use serde_json::{json, Value};
+    use std::time::Duration;
+
+    fn log_notifications(messages: &[Value]) -> Vec<&Value> {
+        notifications(messages, "notifications/message")
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_emits_log_notification() {
+        let mut router = BraveSearchRouter::new("test_key".to_string())
+            .with_base_url("http://127.0.0.1:1".to_string());
+        // No request is admitted, so the first search is rate limited
+        router.rate_limiter = RateLimiter::with_limits(0, 10);
+
+        let mut client = TestClient::start(router).await;
+        let messages = client
+            .call_tool(2, "brave_web_search", json!({"query": "rust"}))
+            .await;
+
+        let logs = log_notifications(&messages);
+        assert_eq!(logs.len(), 1, "{:?}", messages);
+        assert_eq!(logs[0]["level"], "warning");
+        assert_eq!(logs[0]["logger"], "bravesearch");
+        assert!(logs[0]["data"]
+            .as_str()
+            .unwrap()
+            .contains("Rate limit exceeded"));
+    }
+
+    #[tokio::test]
+    async fn test_log_notifications_respect_client_level() {
+        let mut router = BraveSearchRouter::new("test_key".to_string())
+            .with_base_url("http://127.0.0.1:1".to_string());
+        router.rate_limiter = RateLimiter::with_limits(0, 10);
+
+        let mut client = TestClient::start(router).await;
+        client
+            .send(json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "logging/setLevel",
+                "params": {"level": "error"}
+            }))
+            .await;
+        assert!(client.recv().await["result"].is_object());
+
+        // Rate-limit warnings fall below the requested level
+        let messages = client
+            .call_tool(3, "brave_web_search", json!({"query": "rust"}))
+            .await;
+        assert!(log_notifications(&messages).is_empty(), "{:?}", messages);
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_quota_is_logged_once_per_interval() {
+        let mut router = BraveSearchRouter::new("test_key".to_string())
+            .with_base_url("http://127.0.0.1:1".to_string());
+        // The monthly quota is used up before the first search
+        router.rate_limiter = RateLimiter::with_limits(10, 0);
+
+        let mut client = TestClient::start(router.clone()).await;
+        let mut logs = Vec::new();
+        for id in 2..12 {
+            let messages = client
+                .call_tool(id, "brave_web_search", json!({"query": "rust"}))
+                .await;
+            // Every caller still gets the error
+            let text = messages.last().unwrap()["result"]["content"][0]["text"].clone();
+            assert!(
+                text.as_str()
+                    .unwrap()
+                    .starts_with("Error: monthly quota exhausted"),
+                "{:?}",
+                messages
+            );
+            logs.extend(log_notifications(&messages).into_iter().cloned());
+        }
+        assert_eq!(logs.len(), 1, "{:?}", logs);
+        assert!(logs[0]["data"]
+            .as_str()
+            .unwrap()
+            .starts_with("monthly quota exhausted"));
+
+        // Without an interval every rejection is logged
+        let router = router.with_quota_log_interval(Duration::ZERO);
+        let mut client = TestClient::start(router).await;
+        for id in 2..5 {
+            let messages = client
+                .call_tool(id, "brave_web_search", json!({"query": "rust"}))
+                .await;
+            assert_eq!(log_notifications(&messages).len(), 1, "{:?}", messages);
+        }
+    }
+}
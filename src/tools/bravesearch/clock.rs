@@ -0,0 +1,68 @@
+use std::time::Instant;
+
+use chrono::{DateTime, Utc};
+
+// Time source for the rate limiter.
+//
+// The per-second window is measured on the monotonic clock and the monthly
+// window on the wall clock. Both come from one `Clock`, so tests can swap in
+// `MockClock` and step through window resets instead of sleeping or editing
+// the limiter's counters.
+
+pub(crate) trait Clock: Send + Sync {
+    /// Monotonic time, for short windows
+    fn now(&self) -> Instant;
+    /// Wall-clock time, for windows measured in days
+    fn utc_now(&self) -> DateTime<Utc>;
+}
+
+/// The system clocks
+pub(crate) struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn utc_now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock that only moves when advanced
+#[cfg(test)]
+pub(crate) struct MockClock {
+    start: Instant,
+    start_utc: DateTime<Utc>,
+    elapsed: std::sync::Mutex<std::time::Duration>,
+}
+
+#[cfg(test)]
+impl MockClock {
+    pub(crate) fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            start_utc: Utc::now(),
+            elapsed: std::sync::Mutex::new(std::time::Duration::ZERO),
+        }
+    }
+
+    pub(crate) fn advance(&self, by: std::time::Duration) {
+        *self.elapsed.lock().unwrap() += by;
+    }
+
+    fn elapsed(&self) -> std::time::Duration {
+        *self.elapsed.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn utc_now(&self) -> DateTime<Utc> {
+        self.start_utc + self.elapsed()
+    }
+}
@@ -0,0 +1,211 @@
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+
+// Canned responses for demo mode (`--demo`), for trying an MCP client before
+// getting a Brave API key.
+//
+// In demo mode `send_api_response` answers every Brave request from the
+// fixtures below and never touches the network, so demo results still go
+// through the same parsing, caching, and formatting as live ones. Every
+// title, name, and suggestion is marked `[Demo]`, so canned data cannot be
+// mistaken for real search results.
+
+/// The canned queries, named in the error for any other query
+const DEMO_QUERIES: &str =
+    "'rust' (web, news, suggest), 'model context protocol' (web), or 'coffee' (local)";
+
+/// What `brave_account_info` reports in demo mode, where there is no quota to look up
+pub(crate) const DEMO_ACCOUNT_INFO: &str =
+    "Demo mode: results are canned fixtures and no API key is used, so there is no quota to report";
+
+fn web_result(title: &str, url: &str, description: &str) -> Value {
+    json!({
+        "title": format!("[Demo] {}", title),
+        "url": url,
+        "description": description,
+    })
+}
+
+fn web(results: Vec<Value>) -> Value {
+    json!({"type": "search", "web": {"type": "search", "results": results}})
+}
+
+fn pois() -> Vec<Value> {
+    vec![
+        json!({
+            "id": "demo-cafe-1",
+            "name": "[Demo] Ferris Coffee Roasters",
+            "address": {
+                "street_address": "1 Crab Lane",
+                "address_locality": "Portland",
+                "address_region": "OR",
+                "postal_code": "97201"
+            },
+            "phone": "+1 555 0100",
+            "rating": {"rating_value": 4.7, "rating_count": 212},
+            "opening_hours": ["Mo-Fr 07:00-18:00", "Sa-Su 08:00-16:00"],
+            "price_range": "$$"
+        }),
+        json!({
+            "id": "demo-cafe-2",
+            "name": "[Demo] The Borrow Checker Cafe",
+            "address": {
+                "street_address": "42 Lifetime Street",
+                "address_locality": "Portland",
+                "address_region": "OR",
+                "postal_code": "97205"
+            },
+            "rating": {"rating_value": 4.2, "rating_count": 87},
+            "price_range": "$"
+        }),
+    ]
+}
+
+/// The canned body for a request to `url`, or an error naming the canned queries
+pub(crate) fn fixture(url: &reqwest::Url) -> Result<Value> {
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+    let ids = url
+        .query_pairs()
+        .filter(|(key, _)| key == "ids")
+        .map(|(_, value)| value.into_owned())
+        .collect::<Vec<_>>();
+    let endpoint = url.path().rsplit("/res/v1/").next().unwrap_or_default();
+    let query = param("q").unwrap_or_default().trim().to_lowercase();
+
+    let body = match (endpoint, query.as_str()) {
+        ("local/pois", _) => json!({
+            "results": pois()
+                .into_iter()
+                .filter(|poi| ids.iter().any(|id| poi["id"] == id.as_str()))
+                .collect::<Vec<_>>()
+        }),
+        ("local/descriptions", _) => json!({
+            "descriptions": {
+                "demo-cafe-1": "Demo description: small-batch roaster with a rotating pour-over menu.",
+                "demo-cafe-2": "Demo description: quiet cafe with long tables, popular for pairing sessions."
+            }
+        }),
+        ("web/search", "coffee") if param("result_filter").as_deref() == Some("locations") => {
+            json!({
+                "type": "search",
+                "locations": {"results": [
+                    {"id": "demo-cafe-1", "title": "[Demo] Ferris Coffee Roasters"},
+                    {"id": "demo-cafe-2", "title": "[Demo] The Borrow Checker Cafe"}
+                ]}
+            })
+        }
+        ("web/search", "rust") => web(vec![
+            web_result(
+                "Rust Programming Language",
+                "https://www.rust-lang.org/",
+                "A language empowering everyone to build reliable and efficient software.",
+            ),
+            web_result(
+                "The Rust Programming Language - The Book",
+                "https://doc.rust-lang.org/book/",
+                "An introductory book about Rust, from getting started to advanced features.",
+            ),
+        ]),
+        ("web/search", "model context protocol") => web(vec![web_result(
+            "Model Context Protocol",
+            "https://modelcontextprotocol.io/",
+            "An open protocol for connecting AI applications to tools and data sources.",
+        )]),
+        ("news/search", "rust") => json!({
+            "type": "news",
+            "results": [{
+                "title": "[Demo] Rust release brings faster builds",
+                "url": "https://blog.rust-lang.org/",
+                "description": "Demo article: a canned news result for trying the news tool.",
+                "age": "2 days ago",
+                "meta_url": {"hostname": "blog.rust-lang.org"}
+            }]
+        }),
+        ("suggest/search", "rust") => json!([
+            &query,
+            ["[Demo] rust lang", "[Demo] rust book", "[Demo] rust async"]
+        ]),
+        _ => {
+            return Err(anyhow!(
+                "Demo mode has no canned results for '{}' on {}; try {}",
+                query,
+                endpoint,
+                DEMO_QUERIES
+            ))
+        }
+    };
+    Ok(body)
+}
+
+/// `fixture` as an HTTP response, for callers expecting one from the API
+pub(crate) fn response(url: &reqwest::Url) -> Result<reqwest::Response> {
+    let response = hyper::Response::builder()
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(fixture(url)?.to_string())?;
+    Ok(reqwest::Response::from(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{BraveSearchRouter, LocalSearchParams, NewsSearchParams, WebSearchParams};
+
+    #[tokio::test]
+    async fn test_demo_mode_serves_fixtures_without_the_network() {
+        // Nothing listens here, so any real request would fail
+        let router = BraveSearchRouter::new(String::new())
+            .with_base_url("http://127.0.0.1:9".to_string())
+            .with_demo_mode(true);
+
+        let result = router
+            .brave_web_search(WebSearchParams {
+                query: "Rust".to_string(),
+                ..Default::default()
+            })
+            .await;
+        assert!(
+            result.contains("Title: [Demo] Rust Programming Language"),
+            "{}",
+            result
+        );
+
+        let result = router
+            .brave_news_search(NewsSearchParams {
+                query: "rust".to_string(),
+                ..Default::default()
+            })
+            .await;
+        assert!(result.contains("[Demo] Rust release"), "{}", result);
+
+        let result = router
+            .brave_local_search(LocalSearchParams {
+                query: "coffee".to_string(),
+                ..Default::default()
+            })
+            .await;
+        assert!(
+            result.contains("Name: [Demo] Ferris Coffee Roasters"),
+            "{}",
+            result
+        );
+
+        let result = router
+            .brave_web_search(WebSearchParams {
+                query: "weather tomorrow".to_string(),
+                ..Default::default()
+            })
+            .await;
+        assert!(
+            result.starts_with("Error: Demo mode has no canned results for 'weather tomorrow'"),
+            "{}",
+            result
+        );
+
+        // Demo calls spend no quota
+        let (_, _, used) = router.rate_limiter.usage().await;
+        assert_eq!(used, 0);
+    }
+}
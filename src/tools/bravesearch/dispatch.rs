@@ -0,0 +1,249 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+
+use super::category::LocalCategory;
+use super::timestamp;
+use super::{
+    BraveSearchRouter, CountryCode, LanguageCode, LocalSearchParams, NewsSearchParams,
+    ThumbnailSize, WebSearchParams,
+};
+
+// Dynamic dispatch over the search endpoints.
+//
+// The tools and any combined or batch handling go through `perform`, which
+// parses the typed filters each endpoint needs and calls the matching
+// `perform_*` method.
+
+/// The Brave search endpoints behind the search tools
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SearchKind {
+    Web,
+    News,
+    Local,
+}
+
+impl fmt::Display for SearchKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SearchKind::Web => "web",
+            SearchKind::News => "news",
+            SearchKind::Local => "local",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Parameters for one search of any kind
+#[derive(Debug, Clone)]
+pub(crate) enum SearchParams {
+    Web(WebSearchParams),
+    News(NewsSearchParams),
+    Local(LocalSearchParams),
+}
+
+impl SearchParams {
+    pub(crate) fn kind(&self) -> SearchKind {
+        match self {
+            SearchParams::Web(_) => SearchKind::Web,
+            SearchParams::News(_) => SearchKind::News,
+            SearchParams::Local(_) => SearchKind::Local,
+        }
+    }
+}
+
+/// Typed news filters parsed from `NewsSearchParams`
+pub(crate) struct NewsFilters {
+    pub(crate) country: Option<CountryCode>,
+    pub(crate) lang: Option<LanguageCode>,
+    pub(crate) tz: Option<chrono_tz::Tz>,
+    pub(crate) thumbnail_size: ThumbnailSize,
+}
+
+impl NewsFilters {
+    /// Parse the country, language, timezone, and thumbnail size, with the tool's error
+    /// message on failure
+    pub(crate) fn parse(params: &NewsSearchParams) -> Result<Self, String> {
+        let country = params
+            .country
+            .as_deref()
+            .map(CountryCode::from_str)
+            .transpose()
+            .map_err(|e| format!("Error parsing country code: {}", e))?;
+        let lang = params
+            .search_lang
+            .as_deref()
+            .map(LanguageCode::from_str)
+            .transpose()
+            .map_err(|e| format!("Error parsing language code: {}", e))?;
+        let tz = params
+            .tz
+            .as_deref()
+            .map(timestamp::parse_timezone)
+            .transpose()
+            .map_err(|e| format!("Error parsing timezone: {}", e))?;
+        let thumbnail_size = params
+            .thumbnail_size
+            .as_deref()
+            .map(ThumbnailSize::from_str)
+            .transpose()
+            .map_err(|e| format!("Error parsing thumbnail_size: {}", e))?
+            .unwrap_or_default();
+        Ok(Self {
+            country,
+            lang,
+            tz,
+            thumbnail_size,
+        })
+    }
+}
+
+/// Typed local filters parsed from `LocalSearchParams`
+pub(crate) struct LocalFilters {
+    pub(crate) category: Option<LocalCategory>,
+    pub(crate) tz: Option<chrono_tz::Tz>,
+}
+
+impl LocalFilters {
+    /// Parse the category and timezone, with the tool's error message on failure
+    pub(crate) fn parse(params: &LocalSearchParams) -> Result<Self, String> {
+        let category = params
+            .category
+            .as_deref()
+            .map(LocalCategory::parse)
+            .transpose()
+            .map_err(|e| format!("Error parsing category: {}", e))?;
+        let tz = params
+            .tz
+            .as_deref()
+            .map(timestamp::parse_timezone)
+            .transpose()
+            .map_err(|e| format!("Error parsing timezone: {}", e))?;
+        Ok(Self { category, tz })
+    }
+}
+
+impl BraveSearchRouter {
+    /// Run a search of `kind`, which must match the variant of `params`.
+    ///
+    /// Tool budgets are not charged here; callers charge the tool that was invoked.
+    pub(crate) async fn perform(&self, kind: SearchKind, params: SearchParams) -> Result<String> {
+        match (kind, params) {
+            (SearchKind::Web, SearchParams::Web(params)) => self.perform_web_search(&params).await,
+            (SearchKind::News, SearchParams::News(params)) => {
+                let filters = NewsFilters::parse(&params).map_err(anyhow::Error::msg)?;
+                self.perform_news_search(&params, &filters).await
+            }
+            (SearchKind::Local, SearchParams::Local(params)) => {
+                let filters = LocalFilters::parse(&params).map_err(anyhow::Error::msg)?;
+                self.perform_local_search(&params, &filters).await
+            }
+            (kind, params) => Err(anyhow!(
+                "Cannot run a {} search with {} search parameters",
+                kind,
+                params.kind()
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_fixtures::{json_mock, router_for};
+    use super::*;
+
+    #[tokio::test]
+    async fn test_perform_dispatches_each_kind() {
+        let mut server = mockito::Server::new_async().await;
+        let web = json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","web":{"results":[{"title":"Rust","description":"d","url":"https://www.rust-lang.org/"}]},"locations":{"results":[{"id":"loc1","title":"Corner Cafe"}]}}"#,
+        )
+        .expect(2)
+        .create_async()
+        .await;
+        let news = json_mock(
+            &mut server,
+            "/res/v1/news/search",
+            r#"{"type":"news","results":[{"title":"Rust 2.0","description":"d","url":"https://blog.rust-lang.org/"}]}"#,
+        )
+        .expect(1)
+        .create_async()
+        .await;
+        let router = router_for(&server);
+
+        let result = router
+            .perform(
+                SearchKind::Web,
+                SearchParams::Web(WebSearchParams {
+                    query: "rust".to_string(),
+                    ..Default::default()
+                }),
+            )
+            .await
+            .unwrap();
+        assert!(result.contains("Title: Rust"), "{}", result);
+
+        let result = router
+            .perform(
+                SearchKind::News,
+                SearchParams::News(NewsSearchParams {
+                    query: "rust".to_string(),
+                    country: Some("us".to_string()),
+                    ..Default::default()
+                }),
+            )
+            .await
+            .unwrap();
+        assert!(result.contains("Title: Rust 2.0"), "{}", result);
+
+        let result = router
+            .perform(
+                SearchKind::Local,
+                SearchParams::Local(LocalSearchParams {
+                    query: "cafe".to_string(),
+                    ..Default::default()
+                }),
+            )
+            .await
+            .unwrap();
+        assert!(result.contains("Corner Cafe"), "{}", result);
+
+        // Local search finds its locations through the web endpoint
+        web.assert_async().await;
+        news.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_perform_rejects_mismatched_or_invalid_params() {
+        let server = mockito::Server::new_async().await;
+        let router = router_for(&server);
+
+        let err = router
+            .perform(
+                SearchKind::News,
+                SearchParams::Web(WebSearchParams::default()),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Cannot run a news search with web search parameters"
+        );
+
+        let err = router
+            .perform(
+                SearchKind::News,
+                SearchParams::News(NewsSearchParams {
+                    query: "rust".to_string(),
+                    country: Some("XX".to_string()),
+                    ..Default::default()
+                }),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().starts_with("Error parsing country code"));
+    }
+}
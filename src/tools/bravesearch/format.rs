@@ -0,0 +1,1133 @@
+use std::sync::Arc;
+
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+
+// Output formatting for search results.
+//
+// Each search path converts the Brave response into display items, then hands
+// them to a `ResultFormatter`. Adding an output format is a matter of one
+// `ResultFormatter` impl plus an `OutputFormat` variant.
+
+/// A web search result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebSearchItem {
+    pub title: String,
+    pub description: String,
+    pub url: String,
+    /// Name of the site the result comes from, when the call asked for sources
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Type of a result placed by Brave's mixed layout (`news` or `video`); None for web pages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    /// When Brave last saw the page change, as reported (`page_age`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_age: Option<String>,
+    /// How long ago `page_age` was, at search time (e.g. "~3 months ago")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated: Option<String>,
+}
+
+impl WebSearchItem {
+    /// Title with a `[news]`-style label for results that are not web pages
+    fn labeled_title(&self) -> String {
+        match &self.kind {
+            Some(kind) => format!("[{}] {}", kind, self.title),
+            None => self.title.clone(),
+        }
+    }
+}
+
+/// A news search result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewsItem {
+    pub title: String,
+    pub description: String,
+    pub url: String,
+    /// Relative age as reported by Brave (e.g. "2 hours ago")
+    pub age: Option<String>,
+    /// Normalized publication time: ISO-8601 UTC, or local time when a timezone was requested
+    pub published_at: Option<String>,
+    pub breaking: bool,
+    pub thumbnail: Option<String>,
+    /// Further thumbnails after `thumbnail`, when the call asked for more than one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_thumbnails: Vec<String>,
+    /// Hostname of the outlet
+    pub source: Option<String>,
+}
+
+impl NewsItem {
+    /// Every thumbnail URL, `thumbnail` first
+    pub fn thumbnails(&self) -> impl Iterator<Item = &str> {
+        self.thumbnail
+            .iter()
+            .chain(&self.extra_thumbnails)
+            .map(String::as_str)
+    }
+}
+
+/// A local search result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LocalItem {
+    pub id: String,
+    pub name: Option<String>,
+    pub address: Option<String>,
+    /// Latitude and longitude
+    pub coordinates: Option<[f64; 2]>,
+    /// Why Brave's coordinates were dropped, when they were present but malformed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coordinates_note: Option<String>,
+    /// Business details, present when the result came from the POI endpoint
+    #[serde(flatten)]
+    pub details: Option<LocalDetails>,
+}
+
+/// Business details of a local result
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct LocalDetails {
+    pub phone: Option<String>,
+    pub rating: Option<f64>,
+    pub rating_count: Option<u32>,
+    pub price_range: Option<String>,
+    pub opening_hours: Vec<String>,
+    pub description: Option<String>,
+}
+
+// Fields counted by `LocalItem::completeness`: name, address, coordinates, and the six details
+const LOCAL_SCORED_FIELDS: usize = 9;
+
+impl LocalItem {
+    /// Fraction of the result's fields that are populated, from 0.0 to 1.0 (rounded to two
+    /// decimals), so richer POI records can be preferred over sparse ones
+    pub fn completeness(&self) -> f64 {
+        let details = self.details.as_ref();
+        let populated = [
+            self.name.is_some(),
+            self.address.is_some(),
+            self.coordinates.is_some(),
+            details.is_some_and(|d| d.phone.is_some()),
+            details.is_some_and(|d| d.rating.is_some()),
+            details.is_some_and(|d| d.rating_count.is_some()),
+            details.is_some_and(|d| d.price_range.is_some()),
+            details.is_some_and(|d| !d.opening_hours.is_empty()),
+            details.is_some_and(|d| d.description.is_some()),
+        ]
+        .into_iter()
+        .filter(|&populated| populated)
+        .count();
+        (populated as f64 / LOCAL_SCORED_FIELDS as f64 * 100.0).round() / 100.0
+    }
+}
+
+/// A local result as serialized by `JsonFormatter`, with its completeness score
+#[derive(Serialize)]
+struct ScoredLocalItem<'a> {
+    #[serde(flatten)]
+    item: &'a LocalItem,
+    completeness: f64,
+}
+
+/// Presentation settings applied when joining formatted results
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OutputOptions {
+    /// 1-based position of the first result when numbering is enabled
+    pub first_position: Option<usize>,
+    /// Byte budget for the joined results (the omitted-results note is not counted)
+    pub max_output_bytes: Option<usize>,
+    /// List news thumbnails in one trailing `Thumbnails:` block instead of inline per result
+    pub thumbnails_as_gallery: bool,
+    /// Indent JSON output across lines instead of serializing it on a single line
+    pub pretty_json: bool,
+}
+
+impl OutputOptions {
+    pub(crate) fn new(
+        numbered: Option<bool>,
+        offset: usize,
+        count: usize,
+        max_output_bytes: Option<usize>,
+    ) -> Self {
+        Self {
+            first_position: numbered.unwrap_or(false).then(|| offset * count + 1),
+            max_output_bytes,
+            thumbnails_as_gallery: false,
+            pretty_json: false,
+        }
+    }
+
+    pub(crate) fn with_pretty_json(mut self, pretty: Option<bool>) -> Self {
+        self.pretty_json = pretty.unwrap_or(false);
+        self
+    }
+
+    pub(crate) fn with_thumbnails_as_gallery(mut self, gallery: Option<bool>) -> Self {
+        self.thumbnails_as_gallery = gallery.unwrap_or(false);
+        self
+    }
+}
+
+/// Renders search results for a tool response
+pub trait ResultFormatter: Send + Sync {
+    fn web(&self, items: &[WebSearchItem], options: OutputOptions) -> String;
+
+    fn news(&self, items: &[NewsItem], options: OutputOptions) -> String;
+
+    fn local(&self, items: &[LocalItem], options: OutputOptions) -> String;
+
+    /// Attach an advisory note (e.g. about deep pagination) to formatted output
+    fn with_note(&self, output: String, note: Option<String>) -> String {
+        append_note(output, note)
+    }
+
+    /// Put an extracted direct answer above formatted output
+    fn with_answer(&self, output: String, answer: Option<String>) -> String {
+        match answer {
+            Some(answer) => format!("Answer: {}\n\n{}", answer, output),
+            None => output,
+        }
+    }
+
+    /// Follow formatted results with Brave's estimate of how many results the query has
+    fn with_total_estimate(&self, output: String, estimate: Option<u64>) -> String {
+        append_note(
+            output,
+            estimate.map(|estimate| format!("Estimated total results: {}", estimate)),
+        )
+    }
+
+    /// Follow formatted local results with web results for the same query, formatted by this
+    /// formatter, when there were too few locations
+    fn with_web_supplement(&self, local: String, web: String) -> String {
+        append_note(
+            local,
+            Some(format!("{}\n\n{}", WEB_SUPPLEMENT_HEADING, web)),
+        )
+    }
+}
+
+/// Heading above the web results that supplement sparse local results
+pub(crate) const WEB_SUPPLEMENT_HEADING: &str = "Supplemented with web results:";
+
+/// Output formats selectable per call or as the server default
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    Serialize,
+    Deserialize,
+    schemars::JsonSchema,
+    clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Labeled plain-text lines (default)
+    #[default]
+    Text,
+    /// A JSON array of result objects
+    Json,
+    /// Markdown with linked titles
+    Markdown,
+    /// One `title | host | url` line per result
+    Compact,
+}
+
+impl OutputFormat {
+    pub fn formatter(self) -> Arc<dyn ResultFormatter> {
+        match self {
+            OutputFormat::Text => Arc::new(TextFormatter),
+            OutputFormat::Json => Arc::new(JsonFormatter),
+            OutputFormat::Markdown => Arc::new(MarkdownFormatter),
+            OutputFormat::Compact => Arc::new(CompactFormatter::default()),
+        }
+    }
+}
+
+/// Join formatted results, numbering them and truncating to the byte budget as configured.
+///
+/// Truncation only happens between results, and the first result is always kept.
+pub(crate) fn join_results(
+    results: Vec<String>,
+    separator: &str,
+    options: OutputOptions,
+) -> String {
+    let results = match options.first_position {
+        Some(start) => results
+            .into_iter()
+            .enumerate()
+            .map(|(i, result)| format!("{}. {}", start + i, result))
+            .collect::<Vec<_>>(),
+        None => results,
+    };
+
+    let Some(max_bytes) = options.max_output_bytes else {
+        return results.join(separator);
+    };
+
+    let total = results.len();
+    let mut output = String::new();
+    let mut included = 0;
+    for result in &results {
+        let added = if included == 0 {
+            result.len()
+        } else {
+            separator.len() + result.len()
+        };
+        if included > 0 && output.len() + added > max_bytes {
+            break;
+        }
+        if included > 0 {
+            output.push_str(separator);
+        }
+        output.push_str(result);
+        included += 1;
+    }
+
+    if included < total {
+        output.push_str(&format!("\n\n({} more results omitted)", total - included));
+    }
+    output
+}
+
+/// Append a `Thumbnails:` block listing every item's thumbnails, one line per URL, when
+/// gallery mode is on and any item has one
+fn append_thumbnail_gallery(
+    output: String,
+    items: &[NewsItem],
+    options: OutputOptions,
+    line: impl Fn(&str) -> String,
+) -> String {
+    if !options.thumbnails_as_gallery {
+        return output;
+    }
+    let lines = items
+        .iter()
+        .flat_map(NewsItem::thumbnails)
+        .map(line)
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        return output;
+    }
+    format!("{}\n\nThumbnails:\n{}", output, lines.join("\n"))
+}
+
+/// Append an advisory note after the results, separated by a blank line
+pub(crate) fn append_note(output: String, note: Option<String>) -> String {
+    match note {
+        Some(note) if output.is_empty() => note,
+        Some(note) => format!("{}\n\n{}", output, note),
+        None => output,
+    }
+}
+
+// Punctuation that may separate a repeated title from the rest of a description
+const TITLE_SEPARATORS: &[char] = &[':', '-', '\u{2013}', '\u{2014}', '|', '.', '\u{b7}'];
+
+/// `description` without a leading copy of `title`, which Brave sometimes includes.
+///
+/// Deliberately conservative: the copy must be the whole title (ignoring ASCII case), followed
+/// by the end of the description or by separator punctuation and whitespace. So "Rust - A
+/// language" under "Rust" loses its prefix, but "Rust is a language" and "Rust-based tools"
+/// are kept. A description that only repeats the title becomes empty.
+pub(crate) fn strip_title_echo(title: &str, description: String) -> String {
+    let title = title.trim();
+    let text = description.trim_start();
+    let Some(head) = text.get(..title.len()) else {
+        return description;
+    };
+    if title.is_empty() || !head.eq_ignore_ascii_case(title) {
+        return description;
+    }
+
+    let rest = text[title.len()..].trim_start();
+    let separators = rest
+        .find(|c: char| !TITLE_SEPARATORS.contains(&c))
+        .unwrap_or(rest.len());
+    let after = &rest[separators..];
+    let separated = separators > 0 && (after.is_empty() || after.starts_with(char::is_whitespace));
+    if rest.is_empty() || separated {
+        after.trim_start().to_string()
+    } else {
+        description
+    }
+}
+
+/// The default labeled plain-text format
+pub struct TextFormatter;
+
+impl ResultFormatter for TextFormatter {
+    fn web(&self, items: &[WebSearchItem], options: OutputOptions) -> String {
+        let results = items
+            .iter()
+            .map(|item| {
+                let source = item
+                    .source
+                    .as_ref()
+                    .map(|source| format!("\nSource: {}", source))
+                    .unwrap_or_default();
+                let updated = item
+                    .updated
+                    .as_ref()
+                    .map(|updated| format!("\nUpdated: {}", updated))
+                    .unwrap_or_default();
+                format!(
+                    "Title: {}\nDescription: {}\nURL: {}{}{}",
+                    item.labeled_title(),
+                    item.description,
+                    item.url,
+                    source,
+                    updated
+                )
+            })
+            .collect();
+        join_results(results, "\n\n", options)
+    }
+
+    fn news(&self, items: &[NewsItem], options: OutputOptions) -> String {
+        if items.is_empty() {
+            return "No news results found (empty results array)".to_string();
+        }
+
+        let results = items
+            .iter()
+            .map(|item| {
+                let breaking = if item.breaking { "[BREAKING] " } else { "" };
+                let source = item
+                    .source
+                    .as_ref()
+                    .map(|source| format!("\nSource: {}", source))
+                    .unwrap_or_default();
+                let published = item
+                    .published_at
+                    .as_ref()
+                    .map(|published| format!("\nPublished: {}", published))
+                    .unwrap_or_default();
+                let thumbnail = item
+                    .thumbnails()
+                    .filter(|_| !options.thumbnails_as_gallery)
+                    .map(|src| format!("\nThumbnail: {}", src))
+                    .collect::<String>();
+
+                format!(
+                    "{}Title: {}\nDescription: {}\nURL: {}{}\nAge: {}{}{}",
+                    breaking,
+                    item.title,
+                    item.description,
+                    item.url,
+                    source,
+                    item.age.as_deref().unwrap_or("Unknown"),
+                    published,
+                    thumbnail
+                )
+            })
+            .collect();
+        let output = join_results(results, "\n\n", options);
+        append_thumbnail_gallery(output, items, options, |src| format!("- {}", src))
+    }
+
+    fn local(&self, items: &[LocalItem], options: OutputOptions) -> String {
+        if items.is_empty() {
+            return "No local results found".to_string();
+        }
+
+        let results = items
+            .iter()
+            .map(|item| match &item.details {
+                Some(details) => format_local_details(item, details),
+                None => format_local_summary(item),
+            })
+            .collect();
+        join_results(results, "\n---\n", options)
+    }
+}
+
+/// Summary lines for a location known only from the search response
+fn format_local_summary(item: &LocalItem) -> String {
+    let mut parts = Vec::new();
+    if let Some(name) = &item.name {
+        parts.push(format!("Name: {}", name));
+    }
+    if let Some(address) = &item.address {
+        parts.push(format!("Address: {}", address));
+    }
+    if let Some([latitude, longitude]) = item.coordinates {
+        parts.push(format!("Coordinates: {}, {}", latitude, longitude));
+    }
+    parts.push(format!("ID: {}", item.id));
+    parts.join("\n")
+}
+
+/// Full business listing for a location with POI details
+fn format_local_details(item: &LocalItem, details: &LocalDetails) -> String {
+    let hours = details.opening_hours.join(", ");
+    format!(
+        "Name: {}\nAddress: {}\nPhone: {}\nRating: {} ({} reviews)\nPrice Range: {}\nHours: {}\nDescription: {}",
+        item.name.as_deref().unwrap_or("N/A"),
+        item.address.as_deref().unwrap_or("N/A"),
+        details.phone.as_deref().unwrap_or("N/A"),
+        details
+            .rating
+            .map(|rating| rating.to_string())
+            .unwrap_or_else(|| "N/A".to_string()),
+        details.rating_count.unwrap_or(0),
+        details.price_range.as_deref().unwrap_or("N/A"),
+        if hours.is_empty() { "N/A" } else { &hours },
+        details
+            .description
+            .as_deref()
+            .unwrap_or("No description available")
+    )
+}
+
+/// A JSON array of result objects, on a single line unless `pretty_json` is set. Web and news
+/// arrays end up wrapped with their total estimate (`with_total_estimate`).
+///
+/// Numbering, `max_output_bytes`, and advisory notes only apply to the text-based formats,
+/// so the output always parses.
+pub struct JsonFormatter;
+
+impl JsonFormatter {
+    fn serialize<T: Serialize>(items: &[T], options: OutputOptions) -> String {
+        let json = if options.pretty_json {
+            serde_json::to_string_pretty(items)
+        } else {
+            serde_json::to_string(items)
+        };
+        json.unwrap_or_else(|e| format!("Error: failed to serialize results: {}", e))
+    }
+}
+
+impl ResultFormatter for JsonFormatter {
+    fn web(&self, items: &[WebSearchItem], options: OutputOptions) -> String {
+        Self::serialize(items, options)
+    }
+
+    fn news(&self, items: &[NewsItem], options: OutputOptions) -> String {
+        Self::serialize(items, options)
+    }
+
+    fn local(&self, items: &[LocalItem], options: OutputOptions) -> String {
+        let scored = items
+            .iter()
+            .map(|item| ScoredLocalItem {
+                item,
+                completeness: item.completeness(),
+            })
+            .collect::<Vec<_>>();
+        Self::serialize(&scored, options)
+    }
+
+    fn with_note(&self, output: String, _note: Option<String>) -> String {
+        output
+    }
+
+    fn with_answer(&self, output: String, _answer: Option<String>) -> String {
+        output
+    }
+
+    /// The results array and the estimate in one object, `{"results": [...], "total_estimate": N}`,
+    /// with a `null` estimate when Brave gave none, so the shape never depends on the response
+    fn with_total_estimate(&self, output: String, estimate: Option<u64>) -> String {
+        let estimate = estimate.map_or_else(|| "null".to_string(), |estimate| estimate.to_string());
+        format!(r#"{{"results":{},"total_estimate":{}}}"#, output, estimate)
+    }
+
+    /// The locations array and the web search output (a results object) in one object,
+    /// `{"locations": [...], "web_supplement": {"results": [...], "total_estimate": N}}`, so the
+    /// output still parses
+    fn with_web_supplement(&self, local: String, web: String) -> String {
+        format!(r#"{{"locations":{},"web_supplement":{}}}"#, local, web)
+    }
+}
+
+/// Markdown with each result's title linked to its URL
+pub struct MarkdownFormatter;
+
+impl ResultFormatter for MarkdownFormatter {
+    fn web(&self, items: &[WebSearchItem], options: OutputOptions) -> String {
+        let results = items
+            .iter()
+            .map(|item| {
+                let meta = [
+                    item.source.clone(),
+                    item.updated
+                        .as_ref()
+                        .map(|updated| format!("updated {}", updated)),
+                ]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(" · ");
+                let meta = if meta.is_empty() {
+                    meta
+                } else {
+                    format!("\n_{}_", meta)
+                };
+                format!(
+                    "**[{}]({})**\n{}{}",
+                    item.labeled_title(),
+                    item.url,
+                    item.description,
+                    meta
+                )
+            })
+            .collect();
+        join_results(results, "\n\n", options)
+    }
+
+    fn news(&self, items: &[NewsItem], options: OutputOptions) -> String {
+        if items.is_empty() {
+            return "_No news results found._".to_string();
+        }
+
+        let results = items
+            .iter()
+            .map(|item| {
+                let breaking = if item.breaking { "**BREAKING** " } else { "" };
+                let meta = [
+                    item.source.clone(),
+                    item.age.clone(),
+                    item.published_at.clone(),
+                ]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(" · ");
+
+                let mut result = format!(
+                    "{}**[{}]({})**\n{}",
+                    breaking, item.title, item.url, item.description
+                );
+                if !meta.is_empty() {
+                    result.push_str(&format!("\n_{}_", meta));
+                }
+                for src in item.thumbnails().filter(|_| !options.thumbnails_as_gallery) {
+                    result.push_str(&format!("\n![thumbnail]({})", src));
+                }
+                result
+            })
+            .collect();
+        let output = join_results(results, "\n\n", options);
+        append_thumbnail_gallery(output, items, options, |src| {
+            format!("- ![thumbnail]({})", src)
+        })
+    }
+
+    fn local(&self, items: &[LocalItem], options: OutputOptions) -> String {
+        if items.is_empty() {
+            return "_No local results found._".to_string();
+        }
+
+        let results = items
+            .iter()
+            .map(|item| {
+                let mut lines = vec![format!(
+                    "**{}**",
+                    item.name.as_deref().unwrap_or(item.id.as_str())
+                )];
+                if let Some(address) = &item.address {
+                    lines.push(format!("- Address: {}", address));
+                }
+                if let Some(details) = &item.details {
+                    if let Some(phone) = &details.phone {
+                        lines.push(format!("- Phone: {}", phone));
+                    }
+                    if let Some(rating) = details.rating {
+                        lines.push(format!(
+                            "- Rating: {} ({} reviews)",
+                            rating,
+                            details.rating_count.unwrap_or(0)
+                        ));
+                    }
+                    if let Some(price_range) = &details.price_range {
+                        lines.push(format!("- Price Range: {}", price_range));
+                    }
+                    if !details.opening_hours.is_empty() {
+                        lines.push(format!("- Hours: {}", details.opening_hours.join(", ")));
+                    }
+                    if let Some(description) = &details.description {
+                        lines.push(format!("\n{}", description));
+                    }
+                } else if let Some([latitude, longitude]) = item.coordinates {
+                    lines.push(format!("- Coordinates: {}, {}", latitude, longitude));
+                }
+                lines.join("\n")
+            })
+            .collect();
+        join_results(results, "\n\n", options)
+    }
+}
+
+/// Width titles are cut to in the compact format unless configured otherwise
+pub const DEFAULT_COMPACT_TITLE_WIDTH: usize = 60;
+
+/// One pipe-delimited line per result, `title | host | url`, for dense listings in terminals
+/// and for grep. Local results, which have no URL, are `name | address | id`.
+pub struct CompactFormatter {
+    title_width: usize,
+}
+
+impl Default for CompactFormatter {
+    fn default() -> Self {
+        Self::new(DEFAULT_COMPACT_TITLE_WIDTH)
+    }
+}
+
+impl CompactFormatter {
+    /// A compact formatter cutting titles to `title_width` characters (at least 1)
+    pub fn new(title_width: usize) -> Self {
+        Self {
+            title_width: title_width.max(1),
+        }
+    }
+
+    /// `title` cut to the configured width, ending in an ellipsis when shortened
+    fn truncate(&self, title: &str) -> String {
+        if title.chars().count() <= self.title_width {
+            return title.to_string();
+        }
+        let mut truncated = title.chars().take(self.title_width - 1).collect::<String>();
+        truncated.push('\u{2026}');
+        truncated
+    }
+
+    /// Join fields into one line, flattening whitespace and pipes that would break it apart
+    fn line(fields: &[&str]) -> String {
+        fields
+            .iter()
+            .map(|field| {
+                let field = field.split_whitespace().collect::<Vec<_>>().join(" ");
+                match field.replace('|', "/") {
+                    field if field.is_empty() => "-".to_string(),
+                    field => field,
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+}
+
+/// Hostname of `url`, or an empty string when it does not parse
+fn host(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+impl ResultFormatter for CompactFormatter {
+    fn web(&self, items: &[WebSearchItem], options: OutputOptions) -> String {
+        let results = items
+            .iter()
+            .map(|item| {
+                Self::line(&[
+                    &self.truncate(&item.labeled_title()),
+                    &host(&item.url),
+                    &item.url,
+                ])
+            })
+            .collect();
+        join_results(results, "\n", options)
+    }
+
+    fn news(&self, items: &[NewsItem], options: OutputOptions) -> String {
+        if items.is_empty() {
+            return "No news results found".to_string();
+        }
+
+        let results = items
+            .iter()
+            .map(|item| Self::line(&[&self.truncate(&item.title), &host(&item.url), &item.url]))
+            .collect();
+        join_results(results, "\n", options)
+    }
+
+    fn local(&self, items: &[LocalItem], options: OutputOptions) -> String {
+        if items.is_empty() {
+            return "No local results found".to_string();
+        }
+
+        let results = items
+            .iter()
+            .map(|item| {
+                let name = item.name.as_deref().unwrap_or_default();
+                let address = item.address.as_deref().unwrap_or_default();
+                Self::line(&[&self.truncate(name), address, &item.id])
+            })
+            .collect();
+        join_results(results, "\n", options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_title_echo_is_conservative() {
+        let strip =
+            |title: &str, description: &str| strip_title_echo(title, description.to_string());
+        assert_eq!(
+            strip(
+                "Rust Programming Language",
+                "Rust Programming Language - A language empowering everyone"
+            ),
+            "A language empowering everyone"
+        );
+        assert_eq!(
+            strip("Tokio", "tokio: An asynchronous runtime"),
+            "An asynchronous runtime"
+        );
+        assert_eq!(strip("Tokio", "  Tokio  "), "");
+        // Text that merely starts with the title's words is kept
+        assert_eq!(strip("Rust", "Rust is a language"), "Rust is a language");
+        assert_eq!(strip("Rust", "Rust-based tools"), "Rust-based tools");
+        assert_eq!(
+            strip("Node.js", "Node.js.org hosts the docs"),
+            "Node.js.org hosts the docs"
+        );
+        assert_eq!(strip("Caf\u{e9}", "Ca"), "Ca");
+        assert_eq!(strip("", "Anything"), "Anything");
+    }
+
+    fn web_items() -> Vec<WebSearchItem> {
+        vec![
+            WebSearchItem {
+                title: "Rust".to_string(),
+                description: "A language".to_string(),
+                url: "https://www.rust-lang.org/".to_string(),
+                source: None,
+                kind: None,
+                page_age: None,
+                updated: None,
+            },
+            WebSearchItem {
+                title: "Crates".to_string(),
+                description: "Packages".to_string(),
+                url: "https://crates.io/".to_string(),
+                source: Some("crates.io".to_string()),
+                kind: None,
+                page_age: None,
+                updated: None,
+            },
+        ]
+    }
+
+    fn news_item() -> NewsItem {
+        NewsItem {
+            title: "Rust 2.0".to_string(),
+            description: "Released".to_string(),
+            url: "https://news.example.com/rust".to_string(),
+            age: Some("2 hours ago".to_string()),
+            published_at: Some("2024-05-10T10:00:00Z".to_string()),
+            breaking: true,
+            thumbnail: Some("https://img.example.com/t.png".to_string()),
+            extra_thumbnails: Vec::new(),
+            source: Some("news.example.com".to_string()),
+        }
+    }
+
+    fn local_items() -> Vec<LocalItem> {
+        vec![
+            LocalItem {
+                id: "loc-1".to_string(),
+                name: Some("Cafe".to_string()),
+                address: Some("1 Main St, Seattle".to_string()),
+                coordinates: Some([47.6, -122.3]),
+                coordinates_note: None,
+                details: None,
+            },
+            LocalItem {
+                id: "loc-2".to_string(),
+                name: Some("Diner".to_string()),
+                address: None,
+                coordinates: None,
+                coordinates_note: None,
+                details: Some(LocalDetails {
+                    phone: Some("555-0100".to_string()),
+                    rating: Some(4.5),
+                    rating_count: Some(12),
+                    opening_hours: vec!["Mon 9-5".to_string()],
+                    ..Default::default()
+                }),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_text_formatter() {
+        let output = TextFormatter.web(&web_items(), OutputOptions::default());
+        assert_eq!(
+            output,
+            "Title: Rust\nDescription: A language\nURL: https://www.rust-lang.org/\n\nTitle: Crates\nDescription: Packages\nURL: https://crates.io/\nSource: crates.io"
+        );
+
+        let output = TextFormatter.news(&[news_item()], OutputOptions::default());
+        assert_eq!(
+            output,
+            "[BREAKING] Title: Rust 2.0\nDescription: Released\nURL: https://news.example.com/rust\nSource: news.example.com\nAge: 2 hours ago\nPublished: 2024-05-10T10:00:00Z\nThumbnail: https://img.example.com/t.png"
+        );
+        assert_eq!(
+            TextFormatter.news(&[], OutputOptions::default()),
+            "No news results found (empty results array)"
+        );
+
+        let output = TextFormatter.local(&local_items(), OutputOptions::default());
+        assert_eq!(
+            output,
+            "Name: Cafe\nAddress: 1 Main St, Seattle\nCoordinates: 47.6, -122.3\nID: loc-1\n---\nName: Diner\nAddress: N/A\nPhone: 555-0100\nRating: 4.5 (12 reviews)\nPrice Range: N/A\nHours: Mon 9-5\nDescription: No description available"
+        );
+    }
+
+    #[test]
+    fn test_json_formatter() {
+        let options = OutputOptions {
+            first_position: Some(1),
+            max_output_bytes: Some(1),
+            thumbnails_as_gallery: true,
+            pretty_json: false,
+        };
+
+        let output: serde_json::Value =
+            serde_json::from_str(&JsonFormatter.web(&web_items(), options)).unwrap();
+        assert_eq!(output.as_array().unwrap().len(), 2);
+        assert_eq!(output[1]["url"], "https://crates.io/");
+        assert_eq!(output[1]["source"], "crates.io");
+        assert!(output[0].get("source").is_none());
+
+        let output: serde_json::Value =
+            serde_json::from_str(&JsonFormatter.news(&[news_item()], options)).unwrap();
+        assert_eq!(output[0]["breaking"], true);
+        assert_eq!(output[0]["source"], "news.example.com");
+        // Gallery mode leaves thumbnails on their items
+        assert_eq!(output[0]["thumbnail"], "https://img.example.com/t.png");
+
+        let output: serde_json::Value =
+            serde_json::from_str(&JsonFormatter.local(&local_items(), options)).unwrap();
+        assert_eq!(output[0]["coordinates"], serde_json::json!([47.6, -122.3]));
+        assert!(output[0].get("phone").is_none());
+        assert_eq!(output[1]["phone"], "555-0100");
+        assert_eq!(output[1]["rating_count"], 12);
+        assert_eq!(output[0]["completeness"], 0.33);
+        // The score is ignored when parsing the output back
+        let parsed: Vec<LocalItem> =
+            serde_json::from_str(&JsonFormatter.local(&local_items(), options)).unwrap();
+        assert_eq!(parsed, local_items());
+
+        // Notes would break the JSON, so they are dropped
+        let output = JsonFormatter.with_note("[]".to_string(), Some("Note".to_string()));
+        assert_eq!(output, "[]");
+
+        // A web supplement is wrapped with the locations into one object
+        let output: serde_json::Value = serde_json::from_str(&JsonFormatter.with_web_supplement(
+            JsonFormatter.local(&local_items(), options),
+            JsonFormatter.with_total_estimate(JsonFormatter.web(&web_items(), options), None),
+        ))
+        .unwrap();
+        assert_eq!(output["locations"].as_array().unwrap().len(), 2);
+        assert_eq!(
+            output["web_supplement"]["results"][1]["url"],
+            "https://crates.io/"
+        );
+        assert!(output["web_supplement"]["total_estimate"].is_null());
+
+        // Web and news output is the same object whether or not there is an estimate
+        let output: serde_json::Value =
+            serde_json::from_str(&JsonFormatter.with_total_estimate("[]".to_string(), Some(7)))
+                .unwrap();
+        assert_eq!(
+            output,
+            serde_json::json!({"results": [], "total_estimate": 7})
+        );
+        let output: serde_json::Value =
+            serde_json::from_str(&JsonFormatter.with_total_estimate("[]".to_string(), None))
+                .unwrap();
+        assert_eq!(
+            output,
+            serde_json::json!({"results": [], "total_estimate": null})
+        );
+    }
+
+    #[test]
+    fn test_json_formatter_pretty_and_compact() {
+        let compact = JsonFormatter.web(&web_items(), OutputOptions::default());
+        let pretty = JsonFormatter.web(
+            &web_items(),
+            OutputOptions::default().with_pretty_json(Some(true)),
+        );
+
+        let compact_value: serde_json::Value = serde_json::from_str(&compact).unwrap();
+        let pretty_value: serde_json::Value = serde_json::from_str(&pretty).unwrap();
+        assert_eq!(compact_value, pretty_value);
+
+        // Compact output is a single line without padding around separators
+        assert!(!compact.contains('\n'));
+        assert!(!compact.contains(": "));
+        assert!(!compact.contains(", \""));
+        assert!(compact.starts_with("[{\""));
+        assert_eq!(compact, serde_json::to_string(&compact_value).unwrap());
+
+        assert!(pretty.contains("\n  {\n    \""));
+        assert!(!OutputOptions::default().with_pretty_json(None).pretty_json);
+    }
+
+    #[test]
+    fn test_completeness_prefers_richer_local_results() {
+        let name_only = LocalItem {
+            id: "loc-1".to_string(),
+            name: Some("Cafe".to_string()),
+            address: None,
+            coordinates: None,
+            coordinates_note: None,
+            details: None,
+        };
+        let full = LocalItem {
+            address: Some("1 Main St".to_string()),
+            coordinates: Some([47.6, -122.3]),
+            details: Some(LocalDetails {
+                phone: Some("555-0100".to_string()),
+                rating: Some(4.5),
+                rating_count: Some(12),
+                price_range: Some("$$".to_string()),
+                opening_hours: vec!["Mon 9-5".to_string()],
+                description: Some("Cozy".to_string()),
+            }),
+            ..name_only.clone()
+        };
+
+        assert_eq!(full.completeness(), 1.0);
+        assert_eq!(name_only.completeness(), 0.11);
+        assert!(full.completeness() > name_only.completeness());
+    }
+
+    #[test]
+    fn test_markdown_formatter() {
+        let output = MarkdownFormatter.web(&web_items(), OutputOptions::default());
+        assert_eq!(
+            output,
+            "**[Rust](https://www.rust-lang.org/)**\nA language\n\n**[Crates](https://crates.io/)**\nPackages\n_crates.io_"
+        );
+
+        let output = MarkdownFormatter.news(&[news_item()], OutputOptions::default());
+        assert_eq!(
+            output,
+            "**BREAKING** **[Rust 2.0](https://news.example.com/rust)**\nReleased\n_news.example.com · 2 hours ago · 2024-05-10T10:00:00Z_\n![thumbnail](https://img.example.com/t.png)"
+        );
+
+        let output = MarkdownFormatter.local(&local_items(), OutputOptions::default());
+        assert_eq!(
+            output,
+            "**Cafe**\n- Address: 1 Main St, Seattle\n- Coordinates: 47.6, -122.3\n\n**Diner**\n- Phone: 555-0100\n- Rating: 4.5 (12 reviews)\n- Hours: Mon 9-5"
+        );
+    }
+
+    #[test]
+    fn test_compact_formatter_writes_one_line_per_result() {
+        let mut items = web_items();
+        items[0].title = "The Rust Programming Language".to_string();
+        items[1].title = "Crates | the\nregistry".to_string();
+        let output = CompactFormatter::new(20).web(&items, OutputOptions::default());
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![
+                "The Rust Programmin\u{2026} | www.rust-lang.org | https://www.rust-lang.org/",
+                "Crates / the registry | crates.io | https://crates.io/",
+            ]
+        );
+        for line in lines {
+            assert_eq!(line.split(" | ").count(), 3, "{}", line);
+        }
+
+        let output = CompactFormatter::default().news(&[news_item()], OutputOptions::default());
+        assert_eq!(
+            output,
+            "Rust 2.0 | news.example.com | https://news.example.com/rust"
+        );
+
+        let output = CompactFormatter::default().local(
+            &local_items(),
+            OutputOptions {
+                first_position: Some(1),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            output,
+            "1. Cafe | 1 Main St, Seattle | loc-1\n2. Diner | - | loc-2"
+        );
+    }
+
+    #[test]
+    fn test_thumbnails_as_gallery_collects_thumbnails_in_one_block() {
+        let mut second = news_item();
+        second.title = "Rust 2.1".to_string();
+        second.thumbnail = Some("https://img.example.com/u.png".to_string());
+        let mut third = news_item();
+        third.thumbnail = None;
+        let items = [news_item(), second, third];
+        let options = OutputOptions::default().with_thumbnails_as_gallery(Some(true));
+
+        let output = TextFormatter.news(&items, options);
+        assert!(!output.contains("Thumbnail: "), "{}", output);
+        assert_eq!(output.matches("Thumbnails:").count(), 1);
+        assert!(output.ends_with(
+            "\n\nThumbnails:\n- https://img.example.com/t.png\n- https://img.example.com/u.png"
+        ));
+
+        let output = MarkdownFormatter.news(&items, options);
+        assert_eq!(output.matches("![thumbnail]").count(), 2);
+        assert!(output.ends_with(
+            "\n\nThumbnails:\n- ![thumbnail](https://img.example.com/t.png)\n- ![thumbnail](https://img.example.com/u.png)"
+        ));
+
+        // Without any thumbnails there is no block
+        let output = TextFormatter.news(&items[2..], options);
+        assert!(!output.contains("Thumbnails:"));
+    }
+
+    #[test]
+    fn test_max_output_bytes_truncates_between_results() {
+        let results = vec![
+            "a".repeat(40),
+            "b".repeat(40),
+            "c".repeat(40),
+            "d".repeat(40),
+        ];
+        let options = OutputOptions {
+            max_output_bytes: Some(90),
+            ..Default::default()
+        };
+
+        // Two results plus one separator fit in 82 bytes; the third would exceed 90
+        let output = join_results(results.clone(), "\n\n", options);
+        assert_eq!(
+            output,
+            format!(
+                "{}\n\n{}\n\n(2 more results omitted)",
+                "a".repeat(40),
+                "b".repeat(40)
+            )
+        );
+
+        // The first result is kept even when it alone exceeds the budget
+        let options = OutputOptions {
+            max_output_bytes: Some(10),
+            ..Default::default()
+        };
+        let output = join_results(results.clone(), "\n\n", options);
+        assert!(output.starts_with(&"a".repeat(40)));
+        assert!(output.ends_with("(3 more results omitted)"));
+
+        // A budget large enough for everything adds no note
+        let options = OutputOptions {
+            max_output_bytes: Some(1000),
+            ..Default::default()
+        };
+        assert!(!join_results(results, "\n\n", options).contains("omitted"));
+    }
+}
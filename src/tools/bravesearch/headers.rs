@@ -0,0 +1,116 @@
+use anyhow::{anyhow, Result};
+use reqwest::header::{HeaderName, HeaderValue};
+
+use super::signing::{SIGNATURE_HEADER, SIGNATURE_TIMESTAMP_HEADER};
+
+// Operator-configured headers sent on every Brave API request (`--header`).
+//
+// Gateways in front of Brave sometimes route or bill by a header such as a
+// tenant ID. These headers are added in the shared send path, before the
+// signer runs, so they cover every tool and every retry. Headers the server
+// sets itself and anything that looks like a credential are refused, so a
+// configured header can never replace or leak alongside the API key.
+
+/// Headers `with_custom_header` may not set: the ones this crate sends itself,
+/// the signer's, and hop-by-hop or credential headers
+const RESERVED_HEADERS: &[&str] = &[
+    "x-subscription-token",
+    "accept",
+    "accept-encoding",
+    SIGNATURE_HEADER,
+    SIGNATURE_TIMESTAMP_HEADER,
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "host",
+    "connection",
+    "content-length",
+    "transfer-encoding",
+];
+
+/// Validate a custom header, returning it ready to attach to requests
+pub(crate) fn custom_header(name: &str, value: &str) -> Result<(HeaderName, HeaderValue)> {
+    let header_name = HeaderName::from_bytes(name.trim().as_bytes())
+        .map_err(|_| anyhow!("'{}' is not a valid header name", name))?;
+    if RESERVED_HEADERS
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(header_name.as_str()))
+    {
+        return Err(anyhow!("Header '{}' cannot be overridden", name));
+    }
+    // Names are lowercase once parsed
+    let lowered = header_name.as_str();
+    if lowered.contains("key") || lowered.contains("token") || lowered.contains("auth") {
+        return Err(anyhow!(
+            "Header '{}' looks like a credential and is not allowed",
+            name
+        ));
+    }
+    let header_value = HeaderValue::from_str(value.trim())
+        .ok()
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| {
+            anyhow!(
+                "Value for header '{}' must be non-empty and free of control characters",
+                name
+            )
+        })?;
+    Ok((header_name, header_value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_fixtures::router_for;
+    use super::super::{BraveSearchRouter, WebSearchParams};
+
+    #[tokio::test]
+    async fn test_custom_headers_are_sent() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::Any)
+            .match_header("X-Tenant-Id", "acme")
+            .match_header("X-Subscription-Token", "test_key")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"type":"search","web":{"results":[]}}"#)
+            .create_async()
+            .await;
+
+        let router = router_for(&server)
+            .with_custom_header("X-Tenant-Id", "acme")
+            .unwrap();
+
+        let result = router
+            .brave_web_search(WebSearchParams {
+                query: "rust".to_string(),
+                ..Default::default()
+            })
+            .await;
+        assert!(!result.starts_with("Error"), "{}", result);
+        mock.assert_async().await;
+    }
+
+    #[test]
+    fn test_reserved_and_credential_headers_are_rejected() {
+        let router = || BraveSearchRouter::new("test_key".to_string());
+        for name in [
+            "X-Subscription-Token",
+            "x-subscription-token",
+            "Authorization",
+        ] {
+            let err = router().with_custom_header(name, "other").err().unwrap();
+            assert_eq!(
+                err.to_string(),
+                format!("Header '{}' cannot be overridden", name)
+            );
+        }
+        let err = router().with_custom_header("X-Api-Key", "k").err().unwrap();
+        assert!(
+            err.to_string().contains("looks like a credential"),
+            "{}",
+            err
+        );
+        assert!(router().with_custom_header("bad name", "v").is_err());
+        assert!(router().with_custom_header("X-Tenant-Id", " ").is_err());
+    }
+}
@@ -0,0 +1,82 @@
+// Highlighting of query terms in result titles and descriptions.
+//
+// Matching is per word, case-insensitive, and on whole words only, so "rust"
+// marks "Rust" but not "trust". Words already wrapped in the marker are left
+// alone, so highlighting never nests.
+
+/// Marker wrapped around query terms unless configured otherwise
+pub const DEFAULT_HIGHLIGHT_MARKER: &str = "**";
+
+/// Lowercased, deduplicated words of `query` worth highlighting (single characters are skipped)
+pub(crate) fn query_terms(query: &str) -> Vec<String> {
+    let mut terms = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() > 1)
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>();
+    terms.sort();
+    terms.dedup();
+    terms
+}
+
+/// Wrap each whole-word occurrence of `terms` (as returned by `query_terms`) in `marker`
+pub(crate) fn highlight(text: &str, terms: &[String], marker: &str) -> String {
+    if terms.is_empty() || marker.is_empty() {
+        return text.to_string();
+    }
+
+    let mut output = String::with_capacity(text.len());
+    let mut copied = 0;
+    let mut word_start = None;
+    // A trailing separator closes a word that runs to the end of the text
+    for (i, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        if c.is_alphanumeric() {
+            word_start.get_or_insert(i);
+            continue;
+        }
+        let Some(start) = word_start.take() else {
+            continue;
+        };
+
+        let word = &text[start..i];
+        let already_wrapped = text[..start].ends_with(marker) && text[i..].starts_with(marker);
+        if !already_wrapped && terms.binary_search(&word.to_lowercase()).is_ok() {
+            output.push_str(&text[copied..start]);
+            output.push_str(marker);
+            output.push_str(word);
+            output.push_str(marker);
+            copied = i;
+        }
+    }
+    output.push_str(&text[copied..]);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_wraps_whole_words_case_insensitively() {
+        let terms = query_terms("Rust async-runtime a");
+        assert_eq!(terms, vec!["async", "runtime", "rust"]);
+
+        assert_eq!(
+            highlight("Rust's async runtime, trusted by RUST users", &terms, "**"),
+            "**Rust**'s **async** **runtime**, trusted by **RUST** users"
+        );
+        // Non-matching text is untouched
+        assert_eq!(
+            highlight("A language for everyone", &terms, "**"),
+            "A language for everyone"
+        );
+    }
+
+    #[test]
+    fn test_highlight_does_not_double_wrap() {
+        let terms = query_terms("rust");
+        let once = highlight("Learn rust today", &terms, "==");
+        assert_eq!(once, "Learn ==rust== today");
+        assert_eq!(highlight(&once, &terms, "=="), once);
+    }
+}
@@ -0,0 +1,318 @@
+// Compact rendering of POI opening hours, and the open-now check.
+//
+// Brave lists one entry per day (e.g. "Monday: 9:00 AM - 5:00 PM"), which for
+// a full week makes a very long `Hours:` line. Consecutive days with identical
+// hours are collapsed into a range such as "Mon-Fri: 9:00 AM - 5:00 PM".
+//
+// For `open_now` the entries are parsed into a weekly schedule. Accepted forms
+// are 12-hour ("9:00 AM", "9 pm") and 24-hour ("17:30") times, several
+// intervals per day separated by commas, day ranges ("Mon-Fri"), "Closed",
+// and "Open 24 hours". Intervals that end at or before they start run past
+// midnight into the next day. Days a schedule does not list are closed.
+
+use chrono::{Datelike, NaiveDateTime, Timelike};
+
+const DAYS: [(&str, &str); 7] = [
+    ("monday", "Mon"),
+    ("tuesday", "Tue"),
+    ("wednesday", "Wed"),
+    ("thursday", "Thu"),
+    ("friday", "Fri"),
+    ("saturday", "Sat"),
+    ("sunday", "Sun"),
+];
+
+/// Index into `DAYS` for a full or abbreviated (at least 3 letters) day name
+fn day_index(name: &str) -> Option<usize> {
+    let name = name.trim().trim_end_matches('.').to_lowercase();
+    if name.len() < 3 {
+        return None;
+    }
+    DAYS.iter().position(|(day, _)| day.starts_with(&name))
+}
+
+/// Split "Monday: 9:00 AM - 5:00 PM" into the day index and the hours
+fn parse_entry(entry: &str) -> Option<(usize, &str)> {
+    let (day, hours) = entry.split_once(':')?;
+    let hours = hours.trim();
+    if hours.is_empty() {
+        return None;
+    }
+    Some((day_index(day)?, hours))
+}
+
+/// Collapse consecutive days with identical hours into day ranges.
+///
+/// Entries are returned unchanged if any of them is not in "<day>: <hours>" form.
+pub(crate) fn compact_hours(entries: &[String]) -> Vec<String> {
+    let Some(parsed) = entries
+        .iter()
+        .map(|entry| parse_entry(entry))
+        .collect::<Option<Vec<_>>>()
+    else {
+        return entries.to_vec();
+    };
+
+    // Runs of (first day, last day, hours)
+    let mut runs: Vec<(usize, usize, &str)> = Vec::new();
+    for (day, hours) in parsed {
+        match runs.last_mut() {
+            Some((_, last, run_hours)) if day == *last + 1 && hours == *run_hours => *last = day,
+            _ => runs.push((day, day, hours)),
+        }
+    }
+
+    runs.into_iter()
+        .map(|(first, last, hours)| {
+            if first == last {
+                format!("{}: {}", DAYS[first].1, hours)
+            } else {
+                format!("{}-{}: {}", DAYS[first].1, DAYS[last].1, hours)
+            }
+        })
+        .collect()
+}
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// Opening and closing time in minutes since midnight
+type Interval = (u32, u32);
+
+/// `text` with the dash and space variants found in hours replaced by ASCII, lowercased
+fn normalize(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\u{2013}' | '\u{2014}' => '-',
+            '\u{a0}' | '\u{202f}' | '\u{2009}' => ' ',
+            c => c.to_ascii_lowercase(),
+        })
+        .collect()
+}
+
+/// Whether a normalized time says AM (false) or PM (true)
+fn meridiem(time: &str) -> Option<bool> {
+    let time = time.trim();
+    if time.ends_with("am") {
+        Some(false)
+    } else if time.ends_with("pm") {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+/// Minutes since midnight for "9", "9:30", "9:30 am", or "17:30", reading a time without
+/// AM/PM as `default_meridiem` when given and as 24-hour otherwise
+fn parse_time(time: &str, default_meridiem: Option<bool>) -> Option<u32> {
+    let time = time.trim();
+    let pm = meridiem(time).or(default_meridiem);
+    let clock = time
+        .trim_end_matches("am")
+        .trim_end_matches("pm")
+        .trim_end();
+    let (hour, minute) = match clock.split_once(':') {
+        Some((hour, minute)) => (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?),
+        None => (clock.parse::<u32>().ok()?, 0),
+    };
+    if minute >= 60 {
+        return None;
+    }
+    let hour = match pm {
+        Some(pm) if (1..=12).contains(&hour) => hour % 12 + 12 * u32::from(pm),
+        Some(_) => return None,
+        None if hour <= 24 => hour,
+        None => return None,
+    };
+    let minutes = hour * 60 + minute;
+    (minutes <= MINUTES_PER_DAY).then_some(minutes)
+}
+
+/// Parse "9:00 AM - 5:00 PM". An opening time without AM/PM takes the closing time's
+/// ("9:00 - 11:00 AM"), unless that would open after closing ("11 - 2 PM" opens at 11 AM).
+fn parse_interval(interval: &str) -> Option<Interval> {
+    let (open, close) = interval.split_once('-')?;
+    let close_minutes = parse_time(close, None)?;
+    let open_minutes = match (meridiem(open), meridiem(close)) {
+        (None, Some(pm)) => {
+            let open_minutes = parse_time(open, Some(pm))?;
+            if pm && open_minutes > close_minutes {
+                parse_time(open, Some(false))?
+            } else {
+                open_minutes
+            }
+        }
+        _ => parse_time(open, None)?,
+    };
+    Some((open_minutes, close_minutes))
+}
+
+/// The intervals of one day's hours, such as "Closed" or "9 AM - 1 PM, 2 PM - 6 PM"
+fn parse_day_hours(hours: &str) -> Option<Vec<Interval>> {
+    match hours.trim() {
+        "closed" => Some(Vec::new()),
+        "open 24 hours" | "24 hours" | "open 24/7" => Some(vec![(0, MINUTES_PER_DAY)]),
+        hours => hours.split(',').map(parse_interval).collect(),
+    }
+}
+
+/// The days named by "monday" or a range such as "mon-fri" (which may wrap past Sunday)
+fn parse_days(days: &str) -> Option<Vec<usize>> {
+    match days.split_once('-') {
+        Some((first, last)) => {
+            let (first, last) = (day_index(first)?, day_index(last)?);
+            let len = (last + DAYS.len() - first) % DAYS.len() + 1;
+            Some(
+                (0..len)
+                    .map(|offset| (first + offset) % DAYS.len())
+                    .collect(),
+            )
+        }
+        None => Some(vec![day_index(days)?]),
+    }
+}
+
+/// The intervals of each day from Monday, or None if any entry cannot be parsed.
+///
+/// An entry without a day (e.g. "Open 24 hours") applies to every day.
+fn parse_schedule(entries: &[String]) -> Option<[Vec<Interval>; 7]> {
+    if entries.is_empty() {
+        return None;
+    }
+    let mut schedule: [Vec<Interval>; 7] = Default::default();
+    for entry in entries {
+        let entry = normalize(entry);
+        let dated = entry
+            .split_once(':')
+            .and_then(|(days, hours)| Some((parse_days(days)?, parse_day_hours(hours)?)));
+        let (days, intervals) = match dated {
+            Some(dated) => dated,
+            None => ((0..DAYS.len()).collect(), parse_day_hours(&entry)?),
+        };
+        for day in days {
+            schedule[day].extend(&intervals);
+        }
+    }
+    Some(schedule)
+}
+
+/// Whether a place with opening hours `entries` is open at local time `at`, or None when
+/// the hours cannot be parsed
+pub(crate) fn is_open_at(entries: &[String], at: NaiveDateTime) -> Option<bool> {
+    let schedule = parse_schedule(entries)?;
+    let today = at.weekday().num_days_from_monday() as usize;
+    let yesterday = (today + DAYS.len() - 1) % DAYS.len();
+    let minute = at.hour() * 60 + at.minute();
+
+    let open_today = schedule[today].iter().any(|&(open, close)| {
+        if close > open {
+            (open..close).contains(&minute)
+        } else {
+            minute >= open
+        }
+    });
+    // Yesterday's overnight intervals that have not closed yet
+    let open_from_yesterday = schedule[yesterday]
+        .iter()
+        .any(|&(open, close)| close <= open && minute < close);
+    Some(open_today || open_from_yesterday)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_compact_hours_collapses_consecutive_days() {
+        let hours = entries(&[
+            "Monday: 9:00 AM - 5:00 PM",
+            "Tuesday: 9:00 AM - 5:00 PM",
+            "Wednesday: 9:00 AM - 5:00 PM",
+            "Thursday: 9:00 AM - 8:00 PM",
+            "Fri: 9:00 AM - 8:00 PM",
+            "Saturday: 10:00 AM - 2:00 PM",
+            "Sunday: Closed",
+        ]);
+        assert_eq!(
+            compact_hours(&hours),
+            entries(&[
+                "Mon-Wed: 9:00 AM - 5:00 PM",
+                "Thu-Fri: 9:00 AM - 8:00 PM",
+                "Sat: 10:00 AM - 2:00 PM",
+                "Sun: Closed",
+            ])
+        );
+    }
+
+    #[test]
+    fn test_compact_hours_keeps_unrecognized_entries() {
+        // Identical hours on non-adjacent days stay separate
+        let hours = entries(&["Monday: 9-5", "Wednesday: 9-5"]);
+        assert_eq!(compact_hours(&hours), entries(&["Mon: 9-5", "Wed: 9-5"]));
+
+        let hours = entries(&["Open 24 hours"]);
+        assert_eq!(compact_hours(&hours), hours);
+        let hours = entries(&["Monday: 9-5", "Holidays: closed"]);
+        assert_eq!(compact_hours(&hours), hours);
+    }
+
+    fn at(date: &str, time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    // 2024-05-06 is a Monday
+    #[test]
+    fn test_is_open_at_weekly_schedule() {
+        let hours = entries(&[
+            "Monday: 9:00 AM - 5:00 PM",
+            "Tuesday: 9:00\u{202f}AM\u{2013}5:00\u{202f}PM",
+            "Wednesday: 9:00 AM - 1:00 PM, 2:00 PM - 6:00 PM",
+            "Thu-Fri: 9 am - 9 pm",
+            "Saturday: 10:00 - 14:00",
+            "Sunday: Closed",
+        ]);
+        assert_eq!(is_open_at(&hours, at("2024-05-06", "08:59")), Some(false));
+        assert_eq!(is_open_at(&hours, at("2024-05-06", "09:00")), Some(true));
+        assert_eq!(is_open_at(&hours, at("2024-05-06", "17:00")), Some(false));
+        assert_eq!(is_open_at(&hours, at("2024-05-07", "12:00")), Some(true));
+        // Closed over the lunch break
+        assert_eq!(is_open_at(&hours, at("2024-05-08", "13:30")), Some(false));
+        assert_eq!(is_open_at(&hours, at("2024-05-08", "14:30")), Some(true));
+        assert_eq!(is_open_at(&hours, at("2024-05-10", "20:59")), Some(true));
+        assert_eq!(is_open_at(&hours, at("2024-05-11", "13:00")), Some(true));
+        assert_eq!(is_open_at(&hours, at("2024-05-12", "12:00")), Some(false));
+    }
+
+    #[test]
+    fn test_is_open_at_overnight_and_all_day() {
+        let bar = entries(&["Friday: 6:00 PM - 2:00 AM", "Saturday: 6:00 PM - 2:00 AM"]);
+        assert_eq!(is_open_at(&bar, at("2024-05-10", "23:00")), Some(true));
+        // Friday's hours carry past midnight into Saturday
+        assert_eq!(is_open_at(&bar, at("2024-05-11", "01:30")), Some(true));
+        assert_eq!(is_open_at(&bar, at("2024-05-11", "02:00")), Some(false));
+        // Unlisted days are closed, including Thursday's late night
+        assert_eq!(is_open_at(&bar, at("2024-05-10", "01:00")), Some(false));
+        // Sunday early morning follows Saturday night
+        assert_eq!(is_open_at(&bar, at("2024-05-12", "01:00")), Some(true));
+
+        let always = entries(&["Open 24 hours"]);
+        assert_eq!(is_open_at(&always, at("2024-05-12", "03:00")), Some(true));
+        let late = entries(&["Mon-Sun: 11 - 2 PM"]);
+        assert_eq!(is_open_at(&late, at("2024-05-06", "11:30")), Some(true));
+        assert_eq!(is_open_at(&late, at("2024-05-06", "23:30")), Some(false));
+    }
+
+    #[test]
+    fn test_is_open_at_rejects_unparseable_hours() {
+        assert_eq!(is_open_at(&[], at("2024-05-06", "12:00")), None);
+        let hours = entries(&["Monday: 9:00 AM - 5:00 PM", "Holidays: closed"]);
+        assert_eq!(is_open_at(&hours, at("2024-05-06", "12:00")), None);
+        let hours = entries(&["Monday: by appointment"]);
+        assert_eq!(is_open_at(&hours, at("2024-05-06", "12:00")), None);
+        let hours = entries(&["Monday: 13 PM - 5 PM"]);
+        assert_eq!(is_open_at(&hours, at("2024-05-06", "12:00")), None);
+    }
+}
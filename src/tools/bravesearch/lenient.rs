@@ -0,0 +1,128 @@
+// Deserializers for response fields whose JSON type Brave has varied.
+//
+// Ratings have arrived both as numbers and as strings ("4.5"), and single
+// values sometimes arrive where a list is documented (e.g. one opening-hours
+// string instead of an array). Each helper accepts every form seen for its
+// field and reads anything else, including `null`, as missing, so one field
+// drifting does not fail the whole response. Fields using them also need
+// `#[serde(default)]` to tolerate being absent.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+
+/// A number sent as `4.5` or `"4.5"`
+pub(crate) fn number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+    let value = Value::deserialize(deserializer)?;
+    let number = match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().parse::<f64>().ok(),
+        _ => None,
+    };
+    Ok(number.filter(|number| number.is_finite()))
+}
+
+/// A count sent as `1234`, `1234.0`, `"1234"`, or `"1,234"`
+pub(crate) fn count<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u32>, D::Error> {
+    let value = Value::deserialize(deserializer)?;
+    let count = match value {
+        Value::Number(number) => number.as_u64().or_else(|| {
+            number
+                .as_f64()
+                .filter(|n| n.fract() == 0.0 && *n >= 0.0)
+                .map(|n| n as u64)
+        }),
+        Value::String(text) => text.trim().replace(',', "").parse::<u64>().ok(),
+        _ => None,
+    };
+    Ok(count.and_then(|count| u32::try_from(count).ok()))
+}
+
+/// A list of strings sent as an array or as one string; non-string array items are skipped
+pub(crate) fn strings<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Vec<String>>, D::Error> {
+    let value = Value::deserialize(deserializer)?;
+    Ok(match value {
+        Value::Array(items) => Some(
+            items
+                .into_iter()
+                .filter_map(|item| match item {
+                    Value::String(text) => Some(text),
+                    _ => None,
+                })
+                .collect(),
+        ),
+        Value::String(text) => Some(vec![text]),
+        _ => None,
+    })
+}
+
+/// Like `strings`, for fields where a missing list is simply empty
+pub(crate) fn string_list<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<String>, D::Error> {
+    Ok(strings(deserializer)?.unwrap_or_default())
+}
+
+/// A list sent as an array or as one value; items that do not parse are skipped
+pub(crate) fn one_or_many<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    let items = match Value::deserialize(deserializer)? {
+        Value::Array(items) => items,
+        Value::Null => Vec::new(),
+        value => vec![value],
+    };
+    Ok(items
+        .into_iter()
+        .filter_map(|item| serde_json::from_value(item).ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{BraveLocation, BraveLocationRef, BraveRating};
+
+    #[test]
+    fn test_rating_accepts_numbers_and_strings() {
+        let numeric: BraveRating =
+            serde_json::from_str(r#"{"rating_value":4.5,"rating_count":120}"#).unwrap();
+        assert_eq!(numeric.rating_value, Some(4.5));
+        assert_eq!(numeric.rating_count, Some(120));
+
+        let text: BraveRating =
+            serde_json::from_str(r#"{"ratingValue":"4.5","reviewCount":"1,204"}"#).unwrap();
+        assert_eq!(text.rating_value, Some(4.5));
+        assert_eq!(text.rating_count, Some(1204));
+
+        // Unreadable values are missing rather than an error
+        let odd: BraveRating =
+            serde_json::from_str(r#"{"rating_value":"n/a","rating_count":null}"#).unwrap();
+        assert_eq!(odd.rating_value, None);
+        assert_eq!(odd.rating_count, None);
+    }
+
+    #[test]
+    fn test_location_tolerates_shifted_types() {
+        let poi: BraveLocation = serde_json::from_str(
+            r#"{"id":"p1","name":"Cafe","opening_hours":"Open 24 hours",
+                "coordinates":{"latitude":"47.6","longitude":-122.3},
+                "rating":{"rating_value":"4.0","rating_count":12.0}}"#,
+        )
+        .unwrap();
+        assert_eq!(poi.opening_hours, Some(vec!["Open 24 hours".to_string()]));
+        let coordinates = poi.coordinates.unwrap();
+        assert_eq!(
+            (coordinates.latitude, coordinates.longitude),
+            (Some(47.6), Some(-122.3))
+        );
+        assert_eq!(poi.rating.unwrap().rating_count, Some(12));
+
+        let location: BraveLocationRef =
+            serde_json::from_str(r#"{"id":"loc1","categories":"cafe"}"#).unwrap();
+        assert_eq!(location.categories, vec!["cafe".to_string()]);
+    }
+}
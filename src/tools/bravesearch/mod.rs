@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -8,11 +9,93 @@ use std::fmt;
 use std::str::FromStr;
 use tokio::sync::Mutex;
 
-use rmcp::{model::*, schemars, tool, ServerHandler};
-
-// Rate limiting configuration
-const RATE_LIMIT_PER_SECOND: usize = 1;
-const RATE_LIMIT_PER_MONTH: usize = 15000;
+use rmcp::{model::*, service::RequestContext, tool, Peer, RoleServer, ServerHandler};
+
+mod account;
+mod answer;
+mod cache;
+mod category;
+mod client_log;
+mod clock;
+mod demo;
+mod dispatch;
+mod format;
+mod headers;
+mod highlight;
+mod hours;
+mod lenient;
+mod pacer;
+mod params;
+mod preprocess;
+mod progress;
+mod query;
+mod retry;
+mod selftest;
+mod server_config;
+mod signing;
+mod singleflight;
+mod streaming;
+mod suggest;
+#[cfg(test)]
+pub(crate) mod test_client;
+#[cfg(test)]
+mod test_fixtures;
+mod timestamp;
+mod tracking;
+
+use cache::{CacheKey, ResponseCache};
+pub use cache::{MemoryCache, SearchCache};
+use client_log::{ClientLogger, LogThrottle};
+use clock::{Clock, SystemClock};
+use dispatch::{LocalFilters, NewsFilters, SearchKind, SearchParams};
+use pacer::Pacer;
+pub use preprocess::{IdentityPreprocessor, QueryPreprocessor, SiteRestriction};
+use progress::{report_wait, ProgressReporter};
+use retry::RetryPolicy;
+pub use signing::{HmacSigner, NoopSigner, RequestSigner};
+use singleflight::SingleFlight;
+
+pub use format::{
+    CompactFormatter, JsonFormatter, LocalDetails, LocalItem, MarkdownFormatter, NewsItem,
+    OutputFormat, OutputOptions, ResultFormatter, TextFormatter, WebSearchItem,
+};
+pub use params::{
+    LocalSearchParams, NewsSearchParams, QueryOperators, SuggestParams, WebSearchBatchParams,
+    WebSearchParams,
+};
+pub use query::QueryBuilder;
+pub use selftest::{SelfTestCheck, SelfTestReport};
+
+// Defaults of the router settings, shared with the CLI
+pub use client_log::DEFAULT_QUOTA_LOG_INTERVAL;
+pub use format::DEFAULT_COMPACT_TITLE_WIDTH;
+pub use highlight::DEFAULT_HIGHLIGHT_MARKER;
+pub use retry::{DEFAULT_MAX_RETRIES, DEFAULT_RETRY_BASE_MS};
+
+/// Requests per second allowed by the free plan, the default `RateLimiterConfig::per_second`
+pub const RATE_LIMIT_PER_SECOND: usize = 1;
+/// Requests per month allowed by the free plan, the default `RateLimiterConfig::per_month`
+pub const RATE_LIMIT_PER_MONTH: usize = 15000;
+// Length of the monthly quota window; Brave also counts monthly quotas over 30 days
+const QUOTA_WINDOW_DAYS: i64 = 30;
+// Longest a request may wait in total for the per-second limit under `RateLimitBehavior::Wait`
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(5);
+
+// Brave Search API location
+const DEFAULT_BASE_URL: &str = "https://api.search.brave.com";
+
+// Maximum number of queries accepted by a single batch search
+const MAX_BATCH_QUERIES: usize = 5;
+
+// Longest POI or description lookup URL sent as one request; longer ID lists are split,
+// since Brave and proxies in front of it reject long URLs with 414
+const MAX_LOOKUP_URL_LENGTH: usize = 2048;
+
+/// Results requested beyond this depth (offset * count) get a deep-pagination note
+pub const DEFAULT_DEEP_PAGINATION_THRESHOLD: usize = 100;
+
+// Monthly usage percentages that trigger a warning to the client
+const QUOTA_WARNING_THRESHOLDS: [usize; 3] = [80, 90, 100];
 
 // Country codes for Brave Search API
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -254,67 +337,298 @@ impl FromStr for LanguageCode {
     }
 }
 
+/// Request limits for the Brave plan in use; the default matches the free plan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimiterConfig {
+    /// Requests allowed in each one-second window
+    pub per_second: usize,
+    /// Requests allowed in each 30-day quota window
+    pub per_month: usize,
+}
+
+impl Default for RateLimiterConfig {
+    fn default() -> Self {
+        Self {
+            per_second: RATE_LIMIT_PER_SECOND,
+            per_month: RATE_LIMIT_PER_MONTH,
+        }
+    }
+}
+
+/// What a request does when the per-second limit is reached
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitBehavior {
+    /// Fail with "Rate limit exceeded" (default)
+    #[default]
+    Error,
+    /// Sleep until the window clears, failing only after waiting too long in total
+    Wait,
+}
+
 // Rate limiter
 #[derive(Clone)]
 struct RateLimiter {
     request_count: Arc<Mutex<RequestCount>>,
+    limits: RateLimiterConfig,
+    // Optional monthly sub-budgets keyed by tool name
+    tool_budgets: HashMap<String, usize>,
+    clock: Arc<dyn Clock>,
 }
 
 struct RequestCount {
     second: usize,
     month: usize,
     last_reset: Instant,
+    // Start of the current monthly quota window
+    month_start: chrono::DateTime<chrono::Utc>,
+    tool_month: HashMap<String, usize>,
 }
 
-impl Default for RequestCount {
-    fn default() -> Self {
+impl RequestCount {
+    /// Empty counters whose windows start now on `clock`
+    fn new(clock: &dyn Clock) -> Self {
         Self {
             second: 0,
             month: 0,
-            last_reset: Instant::now(),
+            last_reset: clock.now(),
+            month_start: clock.utc_now(),
+            tool_month: HashMap::new(),
         }
     }
+
+    /// Start a new monthly window, clearing the monthly counters, once the current one has ended
+    fn roll_month(&mut self, now: chrono::DateTime<chrono::Utc>) {
+        if now >= self.month_reset() {
+            self.month = 0;
+            self.tool_month.clear();
+            self.month_start = now;
+        }
+    }
+
+    /// When the current monthly window ends
+    fn month_reset(&self) -> chrono::DateTime<chrono::Utc> {
+        self.month_start + chrono::Duration::days(QUOTA_WINDOW_DAYS)
+    }
+}
+
+/// Human-readable time until `reset`, rounded up to whole days
+fn resets_in(reset: chrono::DateTime<chrono::Utc>, now: chrono::DateTime<chrono::Utc>) -> String {
+    let seconds = (reset - now).num_seconds().max(0);
+    let days = ((seconds + 86399) / 86400).max(1);
+    if days == 1 {
+        "resets in 1 day".to_string()
+    } else {
+        format!("resets in {} days", days)
+    }
+}
+
+/// Error for a failed API response, keeping its status for callers that react to it
+#[derive(Debug)]
+struct ApiError {
+    status: reqwest::StatusCode,
+    body: String,
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Brave API error: {} {}\n{}",
+            self.status.as_u16(),
+            self.status.canonical_reason().unwrap_or(""),
+            self.body
+        )
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Whether `error` is the API rejecting a request URL as too long (414)
+fn is_uri_too_long(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<ApiError>()
+        .is_some_and(|e| e.status == reqwest::StatusCode::URI_TOO_LONG)
+}
+
+/// Error for a request rejected by the per-second limit, as opposed to the monthly quota
+#[derive(Debug)]
+struct PerSecondLimitExceeded;
+
+impl fmt::Display for PerSecondLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Rate limit exceeded")
+    }
+}
+
+impl std::error::Error for PerSecondLimitExceeded {}
+
+/// Error for a request rejected because the monthly quota is used up
+#[derive(Debug)]
+struct MonthlyQuotaExhausted {
+    resets_in: String,
+}
+
+impl fmt::Display for MonthlyQuotaExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "monthly quota exhausted, {}", self.resets_in)
+    }
 }
 
+impl std::error::Error for MonthlyQuotaExhausted {}
+
 impl RateLimiter {
     fn new() -> Self {
+        Self::with_config(RateLimiterConfig::default())
+    }
+
+    fn with_limits(per_second: usize, per_month: usize) -> Self {
+        Self::with_config(RateLimiterConfig {
+            per_second,
+            per_month,
+        })
+    }
+
+    fn with_config(limits: RateLimiterConfig) -> Self {
+        let clock: Arc<dyn Clock> = Arc::new(SystemClock);
         Self {
-            request_count: Arc::new(Mutex::new(RequestCount {
-                second: 0,
-                month: 0,
-                last_reset: Instant::now(),
-            })),
+            request_count: Arc::new(Mutex::new(RequestCount::new(clock.as_ref()))),
+            limits,
+            tool_budgets: HashMap::new(),
+            clock,
+        }
+    }
+
+    /// Measure the limiter's windows on `clock`, starting them afresh
+    #[cfg(test)]
+    fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.request_count = Arc::new(Mutex::new(RequestCount::new(clock.as_ref())));
+        self.clock = clock;
+        self
+    }
+
+    /// Start the monthly count at `count`, in a fresh window, instead of 0
+    fn with_initial_month(mut self, count: usize) -> Self {
+        let mut request_count = RequestCount::new(self.clock.as_ref());
+        request_count.month = count;
+        self.request_count = Arc::new(Mutex::new(request_count));
+        self
+    }
+
+    /// Count one invocation of `tool` against its monthly sub-budget, if it has one
+    async fn check_tool_budget(&self, tool: &str) -> Result<()> {
+        let Some(&budget) = self.tool_budgets.get(tool) else {
+            return Ok(());
+        };
+
+        let mut req_count = self.request_count.lock().await;
+        req_count.roll_month(self.clock.utc_now());
+        let used = req_count.tool_month.entry(tool.to_string()).or_insert(0);
+        if *used >= budget {
+            return Err(anyhow!(
+                "Monthly budget for {} exhausted ({} searches)",
+                tool,
+                budget
+            ));
         }
+        *used += 1;
+
+        Ok(())
+    }
+
+    /// Configured limits and the number of requests used this month
+    async fn usage(&self) -> (usize, usize, usize) {
+        let req_count = self.request_count.lock().await;
+        (
+            self.limits.per_second,
+            self.limits.per_month,
+            req_count.month,
+        )
+    }
+
+    /// Time left until the current per-second window ends and its count resets
+    async fn until_next_second(&self) -> Duration {
+        let req_count = self.request_count.lock().await;
+        // The window resets once strictly more than a second has passed
+        (req_count.last_reset + Duration::from_millis(1001))
+            .saturating_duration_since(self.clock.now())
     }
 
-    async fn check_rate_limit(&self) -> Result<()> {
+    /// Admit one request, returning the number of requests used this month including it
+    async fn check_rate_limit(&self) -> Result<usize> {
         let mut req_count = self.request_count.lock().await;
-        let now = Instant::now();
+        let now = self.clock.now();
 
         if now.duration_since(req_count.last_reset) > Duration::from_secs(1) {
             req_count.second = 0;
             req_count.last_reset = now;
         }
 
-        if req_count.second >= RATE_LIMIT_PER_SECOND || req_count.month >= RATE_LIMIT_PER_MONTH {
-            return Err(anyhow!("Rate limit exceeded"));
+        let today = self.clock.utc_now();
+        req_count.roll_month(today);
+        if req_count.month >= self.limits.per_month {
+            return Err(MonthlyQuotaExhausted {
+                resets_in: resets_in(req_count.month_reset(), today),
+            }
+            .into());
+        }
+        if req_count.second >= self.limits.per_second {
+            return Err(PerSecondLimitExceeded.into());
         }
 
         req_count.second += 1;
         req_count.month += 1;
 
-        Ok(())
+        Ok(req_count.month)
     }
 }
 
 // Brave Search API Response Types
 #[derive(Debug, Deserialize)]
-struct BraveWebResult {
+pub(crate) struct BraveWebResult {
     title: String,
+    #[serde(default)]
     description: String,
     url: String,
+    #[serde(default)]
+    age: Option<String>,
+    #[serde(default)]
+    page_age: Option<String>,
+    #[serde(default)]
+    profile: Option<BraveWebProfile>,
+    // Set for news and video results placed by the mixed layout
+    #[serde(skip)]
+    kind: Option<&'static str>,
+}
+
+/// The site a web result comes from, as Brave identifies it
+#[derive(Debug, Deserialize)]
+struct BraveWebProfile {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    #[allow(dead_code)]
+    url: Option<String>,
+    #[serde(default)]
+    long_name: Option<String>,
+}
+
+impl BraveWebResult {
+    /// Display name of the source site from `profile`, preferring the short name
+    fn source_name(&self) -> Option<String> {
+        let profile = self.profile.as_ref()?;
+        [&profile.name, &profile.long_name]
+            .into_iter()
+            .flatten()
+            .map(|name| name.trim())
+            .find(|name| !name.is_empty())
+            .map(str::to_string)
+    }
 }
 
+// Each endpoint gets its own response type so only the fields it owns are read;
+// anything else in the payload (e.g. a stray top-level `results` on a web
+// response) is ignored rather than parsed
 #[derive(Debug, Deserialize)]
 struct BraveSearchResponse {
     #[serde(rename = "type")]
@@ -324,11 +638,73 @@ struct BraveSearchResponse {
     web: Option<BraveWebResults>,
     #[serde(default)]
     locations: Option<BraveLocationsResults>,
+    // News and videos Brave mixes into a web search; same shape as web results
+    #[serde(default)]
+    news: Option<BraveWebResults>,
+    #[serde(default)]
+    videos: Option<BraveWebResults>,
+    #[serde(default)]
+    mixed: Option<BraveMixed>,
+}
+
+/// Brave's intended page layout across result types
+#[derive(Debug, Deserialize, Default)]
+struct BraveMixed {
+    #[serde(default)]
+    main: Vec<BraveMixedRef>,
+}
+
+/// One slot of the layout: result `index` of type `kind`, or all of them when `all` is set
+#[derive(Debug, Deserialize)]
+struct BraveMixedRef {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    index: Option<usize>,
+    #[serde(default)]
+    all: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct BraveNewsResponse {
+    #[serde(rename = "type")]
+    #[allow(dead_code)]
+    response_type: String,
     // News search API returns results directly at top level
     #[serde(default)]
     results: Vec<BraveNewsResult>,
 }
 
+// The `query` object both endpoints echo back, read on its own for the spell correction
+#[derive(Debug, Deserialize)]
+struct BraveQueryEcho {
+    #[serde(default)]
+    query: Option<BraveQuery>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BraveQuery {
+    /// Brave's spell-corrected query, when it corrected one
+    #[serde(default)]
+    altered: Option<String>,
+}
+
+// The estimated total result count Brave sometimes includes: inside `web` for web
+// searches, at the top level for news
+#[derive(Debug, Deserialize)]
+struct BraveTotalEcho {
+    #[serde(default)]
+    web: Option<BraveTotal>,
+    #[serde(default)]
+    total_count: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BraveTotal {
+    #[serde(default)]
+    total_count: Option<u64>,
+}
+
 #[derive(Debug, Deserialize, Default)]
 struct BraveWebResults {
     #[serde(default)]
@@ -352,6 +728,7 @@ struct BraveNewsResults {
 #[derive(Debug, Deserialize)]
 struct BraveNewsResult {
     title: String,
+    #[serde(default)]
     description: String,
     url: String,
     #[serde(default)]
@@ -359,34 +736,120 @@ struct BraveNewsResult {
     #[serde(default)]
     breaking: Option<bool>,
     #[serde(rename = "page_age", default)]
-    #[allow(dead_code)]
     page_age: Option<String>,
     #[serde(rename = "page_fetched", default)]
     #[allow(dead_code)]
     page_fetched: Option<String>,
-    #[serde(default)]
-    thumbnail: Option<BraveNewsThumbnail>,
+    // Usually one object, but some articles come with an array of them
+    #[serde(default, deserialize_with = "lenient::one_or_many")]
+    thumbnail: Vec<BraveNewsThumbnail>,
     #[serde(rename = "meta_url", default)]
-    #[allow(dead_code)]
     meta_url: Option<BraveNewsMetaUrl>,
 }
 
+impl BraveNewsResult {
+    /// Hostname of the outlet, from `meta_url` or else parsed from the article URL
+    fn source(&self) -> Option<String> {
+        self.meta_url
+            .as_ref()
+            .and_then(|meta| meta.hostname.clone())
+            .filter(|hostname| !hostname.is_empty())
+            .or_else(|| {
+                reqwest::Url::parse(&self.url)
+                    .ok()
+                    .and_then(|url| url.host_str().map(str::to_string))
+            })
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct BraveNewsThumbnail {
     #[serde(default)]
     src: Option<String>,
     #[serde(default)]
-    #[allow(dead_code)]
     original: Option<String>,
 }
 
+impl BraveNewsThumbnail {
+    /// Thumbnail URL at the preferred size; `original` falls back to `src` when absent
+    fn url(self, size: ThumbnailSize) -> Option<String> {
+        match size {
+            ThumbnailSize::Small => self.src,
+            ThumbnailSize::Original => self.original.or(self.src),
+        }
+    }
+}
+
+/// Brave's adult-content filtering levels
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SafeSearchLevel {
+    /// No filtering
+    Off,
+    /// Filter explicit content (Brave's default)
+    #[default]
+    Moderate,
+    /// Filter explicit and suggestive content
+    Strict,
+}
+
+impl fmt::Display for SafeSearchLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self {
+            SafeSearchLevel::Off => "off",
+            SafeSearchLevel::Moderate => "moderate",
+            SafeSearchLevel::Strict => "strict",
+        };
+        write!(f, "{}", level)
+    }
+}
+
+impl FromStr for SafeSearchLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "off" => Ok(SafeSearchLevel::Off),
+            "moderate" => Ok(SafeSearchLevel::Moderate),
+            "strict" => Ok(SafeSearchLevel::Strict),
+            _ => Err(format!(
+                "Unknown safesearch level: {} (expected off, moderate, or strict)",
+                s
+            )),
+        }
+    }
+}
+
+/// Which of Brave's news thumbnail URLs to show
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum ThumbnailSize {
+    /// Brave's resized `src` thumbnail
+    #[default]
+    Small,
+    /// The full-resolution `original` image
+    Original,
+}
+
+impl FromStr for ThumbnailSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "small" => Ok(ThumbnailSize::Small),
+            "original" => Ok(ThumbnailSize::Original),
+            _ => Err(format!(
+                "Unknown thumbnail size: {} (expected small or original)",
+                s
+            )),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct BraveNewsMetaUrl {
     #[serde(default)]
     #[allow(dead_code)]
     scheme: Option<String>,
     #[serde(default)]
-    #[allow(dead_code)]
     hostname: Option<String>,
     #[serde(default)]
     #[allow(dead_code)]
@@ -405,10 +868,15 @@ struct BraveLocationRef {
     coordinates: Option<Vec<f64>>,
     #[serde(default)]
     postal_address: Option<BravePostalAddress>,
+    #[serde(default, deserialize_with = "lenient::string_list")]
+    categories: Vec<String>,
+    #[serde(default)]
+    rating: Option<BraveRating>,
 }
 
 #[derive(Debug, Deserialize)]
 struct BravePoiResponse {
+    #[serde(default)]
     results: Vec<BraveLocation>,
 }
 
@@ -419,13 +887,12 @@ struct BraveLocation {
     #[serde(default)]
     address: BraveAddress,
     #[serde(default)]
-    #[allow(dead_code)]
     coordinates: Option<BraveCoordinates>,
     #[serde(default)]
     phone: Option<String>,
     #[serde(default)]
     rating: Option<BraveRating>,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "lenient::strings")]
     opening_hours: Option<Vec<String>>,
     #[serde(default)]
     price_range: Option<String>,
@@ -459,30 +926,300 @@ struct BravePostalAddress {
 
 #[derive(Debug, Deserialize)]
 struct BraveCoordinates {
-    #[allow(dead_code)]
-    latitude: f64,
-    #[allow(dead_code)]
-    longitude: f64,
+    #[serde(default, deserialize_with = "lenient::number")]
+    latitude: Option<f64>,
+    #[serde(default, deserialize_with = "lenient::number")]
+    longitude: Option<f64>,
 }
 
 #[derive(Debug, Deserialize)]
 struct BraveRating {
-    #[serde(default)]
+    // Location results in web search responses use Brave's camelCase names, and either
+    // may come as a string
+    #[serde(default, alias = "ratingValue", deserialize_with = "lenient::number")]
     rating_value: Option<f64>,
-    #[serde(default)]
+    #[serde(default, alias = "reviewCount", deserialize_with = "lenient::count")]
     rating_count: Option<u32>,
 }
 
 #[derive(Debug, Deserialize)]
 struct BraveDescription {
+    #[serde(default)]
     descriptions: std::collections::HashMap<String, String>,
 }
 
+/// Content-Encoding left on a response after reqwest has had a chance to decode it.
+///
+/// reqwest removes the header once it decompresses a body, so any remaining value
+/// (other than `identity`) means the body is still compressed.
+fn undecoded_content_encoding(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    headers
+        .get(reqwest::header::CONTENT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim().to_lowercase())
+        .filter(|value| !value.is_empty() && value != "identity")
+}
+
+/// Name of the reqwest feature that decodes the given Content-Encoding
+fn decompression_feature(encoding: &str) -> &str {
+    match encoding {
+        "br" => "brotli",
+        "zstd" => "zstd",
+        "deflate" => "deflate",
+        _ => "gzip",
+    }
+}
+
+/// Decode a response body as UTF-8, replacing invalid byte sequences with U+FFFD instead of
+/// failing the whole call over a stray byte
+fn decode_body(bytes: &[u8]) -> String {
+    match String::from_utf8_lossy(bytes) {
+        std::borrow::Cow::Borrowed(body) => body.to_string(),
+        std::borrow::Cow::Owned(body) => {
+            tracing::warn!("Brave API response was not valid UTF-8; invalid bytes were replaced");
+            body
+        }
+    }
+}
+
+/// Return the quota threshold (in percent) crossed by the request that brought usage to `used`
+fn crossed_quota_threshold(used: usize, per_month: usize) -> Option<usize> {
+    if used == 0 || per_month == 0 {
+        return None;
+    }
+    QUOTA_WARNING_THRESHOLDS.into_iter().rev().find(|&percent| {
+        used * 100 >= percent * per_month && (used - 1) * 100 < percent * per_month
+    })
+}
+
+/// Check that a Goggle reference is a well-formed HTTPS URL before sending it to Brave
+fn validate_goggles_url(value: &str) -> Result<reqwest::Url, String> {
+    let url = reqwest::Url::parse(value.trim())
+        .map_err(|e| format!("'{}' is not a valid URL: {}", value, e))?;
+    if url.scheme() != "https" {
+        return Err(format!("Goggle URL must use https, got '{}'", url.scheme()));
+    }
+    if url.host_str().is_none() {
+        return Err(format!("Goggle URL '{}' has no host", value));
+    }
+    Ok(url)
+}
+
+/// Latitude and longitude from a location reference's `coordinates` array, or a note
+/// explaining why a present but malformed array was dropped
+fn parse_coordinates(id: &str, coordinates: Option<&[f64]>) -> (Option<[f64; 2]>, Option<String>) {
+    match coordinates {
+        None => (None, None),
+        Some([latitude, longitude, ..]) => (Some([*latitude, *longitude]), None),
+        Some(malformed) => {
+            tracing::debug!("Location {} has malformed coordinates: {:?}", id, malformed);
+            (
+                None,
+                Some(format!(
+                    "Brave returned malformed coordinates (expected [latitude, longitude], got {} value{})",
+                    malformed.len(),
+                    if malformed.len() == 1 { "" } else { "s" }
+                )),
+            )
+        }
+    }
+}
+
+/// Query parameters `extra_params` may not set: the ones this crate sends itself,
+/// and `safesearch`, which operators may rely on being left alone
+const RESERVED_QUERY_PARAMS: &[&str] = &[
+    "q",
+    "count",
+    "offset",
+    "country",
+    "search_lang",
+    "freshness",
+    "spellcheck",
+    "result_filter",
+    "goggles",
+    "ids",
+    "safesearch",
+];
+
+/// Validate passthrough query parameters, returning them sorted by key so the
+/// request URL (and its cache key) does not depend on map iteration order
+fn validate_extra_params(
+    extra: Option<&HashMap<String, String>>,
+) -> Result<Vec<(String, String)>, String> {
+    let Some(extra) = extra else {
+        return Ok(Vec::new());
+    };
+
+    let mut pairs = Vec::with_capacity(extra.len());
+    for (key, value) in extra {
+        if key.is_empty()
+            || !key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        {
+            return Err(format!(
+                "'{}' is not a valid parameter name (use letters, digits, '_', '-', '.')",
+                key
+            ));
+        }
+        let lowered = key.to_ascii_lowercase();
+        if RESERVED_QUERY_PARAMS.contains(&lowered.as_str()) {
+            return Err(format!(
+                "'{}' cannot be overridden; use the tool's own parameter instead",
+                key
+            ));
+        }
+        if lowered.contains("key") || lowered.contains("token") || lowered.contains("auth") {
+            return Err(format!(
+                "'{}' looks like a credential and is not allowed",
+                key
+            ));
+        }
+        if value.trim().is_empty() || value.chars().any(char::is_control) {
+            return Err(format!(
+                "Value for '{}' must be non-empty and free of control characters",
+                key
+            ));
+        }
+        pairs.push((key.clone(), value.clone()));
+    }
+    pairs.sort();
+    Ok(pairs)
+}
+
+/// Join the non-empty address parts with commas, or None if there are none
+fn join_address(parts: &[&Option<String>]) -> Option<String> {
+    let address = parts
+        .iter()
+        .filter_map(|part| part.as_deref())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ");
+    (!address.is_empty()).then_some(address)
+}
+
+/// Whether a result's rating satisfies the `min_rating` and `min_reviews` filters.
+///
+/// Unrated results fail any rating minimum, and a missing review count counts as 0.
+fn meets_rating_filters(rating: Option<&BraveRating>, params: &LocalSearchParams) -> bool {
+    let value = rating.and_then(|rating| rating.rating_value);
+    let reviews = rating.and_then(|rating| rating.rating_count).unwrap_or(0);
+
+    params
+        .min_rating
+        .is_none_or(|min_rating| value.is_some_and(|value| value >= min_rating))
+        && params
+            .min_reviews
+            .is_none_or(|min_reviews| reviews >= min_reviews)
+}
+
+/// Whether a POI is open at local time `at`; POIs without readable hours are not
+fn is_open(poi: &BraveLocation, at: chrono::NaiveDateTime) -> bool {
+    poi.opening_hours
+        .as_deref()
+        .and_then(|entries| hours::is_open_at(entries, at))
+        .unwrap_or(false)
+}
+
+/// Web, news, and video results in the order of Brave's `mixed.main` layout. Slots of other
+/// types (e.g. locations, which have no URL) and indexes out of range are skipped, and each
+/// result appears at most once.
+fn mixed_results(mixed: BraveMixed, data: BraveSearchResponse) -> Vec<BraveWebResult> {
+    let mut web = data
+        .web
+        .unwrap_or_default()
+        .results
+        .into_iter()
+        .map(Some)
+        .collect::<Vec<_>>();
+    let mut news = data
+        .news
+        .unwrap_or_default()
+        .results
+        .into_iter()
+        .map(Some)
+        .collect::<Vec<_>>();
+    let mut videos = data
+        .videos
+        .unwrap_or_default()
+        .results
+        .into_iter()
+        .map(Some)
+        .collect::<Vec<_>>();
+
+    let mut ordered = Vec::new();
+    for slot in mixed.main {
+        let (results, kind) = match slot.kind.as_str() {
+            "web" => (&mut web, None),
+            "news" => (&mut news, Some("news")),
+            "videos" => (&mut videos, Some("video")),
+            _ => continue,
+        };
+        let taken = if slot.all {
+            results
+                .iter_mut()
+                .filter_map(Option::take)
+                .collect::<Vec<_>>()
+        } else {
+            slot.index
+                .and_then(|index| results.get_mut(index))
+                .and_then(Option::take)
+                .into_iter()
+                .collect()
+        };
+        ordered.extend(taken.into_iter().map(|mut result| {
+            result.kind = kind;
+            result
+        }));
+    }
+    ordered
+}
+
+/// Sort results by a deterministic key when `stable_sort` is set; equal keys keep Brave's order
+fn apply_stable_sort<T>(results: &mut [T], stable_sort: Option<bool>, key: impl Fn(&T) -> &str) {
+    if stable_sort.unwrap_or(false) {
+        results.sort_by(|a, b| key(a).cmp(key(b)));
+    }
+}
+
+/// MCP server exposing the Brave Search tools.
+///
+/// Clones are cheap and share their runtime state: the HTTP connection pool
+/// (`reqwest::Client` is reference-counted), the rate limiter and tool budget
+/// counters, and the response cache all live behind `Arc`s. The SSE transport
+/// clones the router per connection, so every session draws from the same
+/// quota. Only the MCP peer and its logging level are per session (replaced in
+/// `set_peer`). Shared state added to the router must follow the same pattern.
 #[derive(Clone)]
 pub struct BraveSearchRouter {
     pub client: Client,
     rate_limiter: RateLimiter,
+    rate_limit_behavior: RateLimitBehavior,
     api_key: String,
+    base_url: String,
+    default_news_freshness: Option<String>,
+    deep_pagination_threshold: Option<usize>,
+    client_log: ClientLogger,
+    // Limits repeated "monthly quota exhausted" logs while the quota stays used up
+    quota_log: LogThrottle,
+    retry_policy: RetryPolicy,
+    cache: Option<ResponseCache>,
+    cache_backend: Arc<dyn SearchCache>,
+    output_format: OutputFormat,
+    // Custom default formatter; takes the place of `output_format` when set
+    formatter: Option<Arc<dyn ResultFormatter>>,
+    compact_title_width: usize,
+    signer: Arc<dyn RequestSigner>,
+    custom_headers: reqwest::header::HeaderMap,
+    preprocessor: Arc<dyn QueryPreprocessor>,
+    highlight_marker: String,
+    pacer: Option<Pacer>,
+    forced_safesearch: Option<SafeSearchLevel>,
+    admin_tools: bool,
+    serve_stale_on_limit: bool,
+    empty_retry_delay: Duration,
+    in_flight: SingleFlight,
+    demo: bool,
 }
 
 impl BraveSearchRouter {
@@ -490,748 +1227,4514 @@ impl BraveSearchRouter {
     pub fn new(api_key: String) -> Self {
         // Create a client with default settings
         // The reqwest client automatically handles gzip responses by default
-        // as long as the appropriate feature is enabled in Cargo.toml
+        // as long as the appropriate feature is enabled in Cargo.toml;
+        // send_request reports any response it could not decompress
         Self {
             client: Client::new(),
             rate_limiter: RateLimiter::new(),
+            rate_limit_behavior: RateLimitBehavior::default(),
             api_key,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            default_news_freshness: None,
+            deep_pagination_threshold: Some(DEFAULT_DEEP_PAGINATION_THRESHOLD),
+            client_log: ClientLogger::default(),
+            quota_log: LogThrottle::new(client_log::DEFAULT_QUOTA_LOG_INTERVAL),
+            retry_policy: RetryPolicy::default(),
+            cache: None,
+            cache_backend: Arc::new(MemoryCache::default()),
+            output_format: OutputFormat::Text,
+            formatter: None,
+            compact_title_width: format::DEFAULT_COMPACT_TITLE_WIDTH,
+            signer: Arc::new(NoopSigner),
+            custom_headers: reqwest::header::HeaderMap::new(),
+            preprocessor: Arc::new(IdentityPreprocessor),
+            highlight_marker: highlight::DEFAULT_HIGHLIGHT_MARKER.to_string(),
+            pacer: None,
+            forced_safesearch: None,
+            admin_tools: false,
+            serve_stale_on_limit: false,
+            empty_retry_delay: Duration::from_millis(retry::DEFAULT_EMPTY_RETRY_DELAY_MS),
+            in_flight: SingleFlight::default(),
+            demo: false,
         }
     }
 
-    async fn perform_news_search(
-        &self,
-        query: &str,
-        count: usize,
-        offset: usize,
-        country: Option<CountryCode>,
-        search_lang: Option<LanguageCode>,
-        freshness: Option<&str>,
-    ) -> Result<String> {
-        self.rate_limiter.check_rate_limit().await?;
-
-        // Build URL with query parameters
-        let country_code = country.unwrap_or_default().to_string();
-        let language_code = search_lang.unwrap_or_default().to_string();
+    /// Cap how many times `tool` may be invoked per month, independently of the other tools
+    pub fn with_tool_budget(mut self, tool: &str, monthly_limit: usize) -> Result<Self> {
+        if !Self::tool_box().map.contains_key(tool) {
+            return Err(anyhow!("Unknown tool for budget: {}", tool));
+        }
+        self.rate_limiter
+            .tool_budgets
+            .insert(tool.to_string(), monthly_limit);
+        Ok(self)
+    }
 
-        let mut params = vec![
-            ("q", query.to_string()),
-            ("count", count.to_string()),
-            ("offset", offset.to_string()),
-            ("country", country_code),
-            ("search_lang", language_code),
-            ("spellcheck", "1".to_string()),
-        ];
+    /// Create a router enforcing `config` instead of the free plan's limits, e.g. for a paid
+    /// plan; the same as `new(api_key).with_rate_limit_config(config)`
+    pub fn with_rate_limits(api_key: String, config: RateLimiterConfig) -> Result<Self> {
+        Self::new(api_key).with_rate_limit_config(config)
+    }
 
-        // Add optional parameters
-        if let Some(freshness_val) = freshness {
-            params.push(("freshness", freshness_val.to_string()));
+    /// Enforce `config` instead of the free plan's limits (1 request per second, 15000 per
+    /// month), e.g. for a paid plan. Both limits must be at least 1; the month's count so far
+    /// and any tool budgets are kept
+    pub fn with_rate_limit_config(mut self, config: RateLimiterConfig) -> Result<Self> {
+        if config.per_second == 0 || config.per_month == 0 {
+            return Err(anyhow!(
+                "Rate limits must be at least 1, got {} per second and {} per month",
+                config.per_second,
+                config.per_month
+            ));
         }
+        self.rate_limiter.limits = config;
+        Ok(self)
+    }
 
-        let url = reqwest::Url::parse_with_params(
-            "https://api.search.brave.com/res/v1/news/search",
-            &params,
-        )?;
+    /// Choose whether a request over the per-second limit fails (the default) or waits for the
+    /// next window. Waiting is capped at a few seconds per request; the monthly quota always
+    /// fails at once, since waiting would not help
+    pub fn with_rate_limit_behavior(mut self, behavior: RateLimitBehavior) -> Self {
+        self.rate_limit_behavior = behavior;
+        self
+    }
 
-        let response = self
-            .client
-            .get(url)
-            .header("Accept", "application/json")
-            .header("Accept-Encoding", "gzip")
-            .header("X-Subscription-Token", &self.api_key)
-            .send()
-            .await?;
+    /// Log an exhausted monthly quota at most once per `interval` (default 60 seconds) instead
+    /// of on every rejected call; each caller still gets the error. Zero logs every rejection
+    pub fn with_quota_log_interval(mut self, interval: Duration) -> Self {
+        self.quota_log = LogThrottle::new(interval);
+        self
+    }
 
-        if !response.status().is_success() {
-            let status_code = response.status().as_u16();
-            let reason = response.status().canonical_reason().unwrap_or("");
-            let error_text = response.text().await?;
+    /// Start the monthly request count at `count` rather than 0, for a key whose quota another
+    /// process has already partly used this month. `count` must be below the monthly limit
+    pub fn with_initial_monthly_count(mut self, count: usize) -> Result<Self> {
+        if count >= self.rate_limiter.limits.per_month {
             return Err(anyhow!(
-                "Brave API error: {} {}\n{}",
-                status_code,
-                reason,
-                error_text
+                "Initial monthly count {} must be below the monthly limit of {}",
+                count,
+                self.rate_limiter.limits.per_month
             ));
         }
+        self.rate_limiter = self.rate_limiter.with_initial_month(count);
+        Ok(self)
+    }
 
-        // Get response body as text
-        let response_text = response.text().await?;
+    /// Point the router at a different Brave API host (e.g. a gateway or a mock server)
+    pub fn with_base_url(mut self, base_url: String) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
 
-        // Parse the JSON
-        let data = match serde_json::from_str::<BraveSearchResponse>(&response_text) {
-            Ok(parsed) => parsed,
-            Err(e) => {
-                return Ok(format!("Failed to parse API response: {}", e));
-            }
+    /// Set the freshness filter applied to news searches that omit one (e.g. "pw" for past week)
+    pub fn with_default_news_freshness(mut self, freshness: Option<String>) -> Self {
+        self.default_news_freshness = freshness;
+        self
+    }
+
+    /// Retry transient API failures (connection errors, 429, and 5xx) up to `max_retries` times,
+    /// backing off exponentially from `base_delay` with ±25% jitter
+    pub fn with_retries(mut self, max_retries: u32, base_delay: Duration) -> Self {
+        self.retry_policy = RetryPolicy {
+            max_retries,
+            base_delay,
         };
+        self
+    }
 
-        if data.results.is_empty() {
-            return Ok("No news results found (empty results array)".to_string());
-        }
+    /// Wait `delay` before re-sending a search that returned no results, for calls that pass
+    /// `retry_on_empty` (default just over one second, clearing the per-second limit)
+    pub fn with_empty_retry_delay(mut self, delay: Duration) -> Self {
+        self.empty_retry_delay = delay;
+        self
+    }
 
-        let results = data
-            .results
-            .iter() // Use iter() instead of into_iter() for shared references
-            .map(|result| {
-                let breaking = if result.breaking.unwrap_or(false) {
-                    "[BREAKING] "
-                } else {
-                    ""
-                };
+    /// Space outbound API requests at least `min_interval` apart, delaying bursts instead of
+    /// sending them at once; None (the default) disables pacing
+    pub fn with_request_pacing(mut self, min_interval: Option<Duration>) -> Self {
+        self.pacer = min_interval.map(Pacer::new);
+        self
+    }
 
-                let age = result.age.as_deref().unwrap_or("Unknown");
+    /// Send `level` as `safesearch` on every search request, overriding any value a client
+    /// asked for; None (the default) leaves safesearch to each request
+    pub fn with_forced_safesearch(mut self, level: Option<SafeSearchLevel>) -> Self {
+        self.forced_safesearch = level;
+        self
+    }
 
-                let thumbnail = match &result.thumbnail {
-                    Some(thumb) => match &thumb.src {
-                        Some(src) => format!("\nThumbnail: {}", src),
-                        None => "".to_string(),
-                    },
-                    None => "".to_string(),
-                };
+    /// Answer every API request from canned demo fixtures instead of calling Brave, for trying
+    /// a client without an API key; off by default. Demo requests use no quota
+    pub fn with_demo_mode(mut self, enabled: bool) -> Self {
+        self.demo = enabled;
+        self
+    }
 
-                format!(
-                    "{}Title: {}\nDescription: {}\nURL: {}\nAge: {}{}",
-                    breaking, result.title, result.description, result.url, age, thumbnail
-                )
-            })
-            .collect::<Vec<_>>()
-            .join("\n\n");
+    /// Allow the operator tools (`brave_cache_clear`); off by default, since every client
+    /// of a shared server could otherwise call them
+    pub fn with_admin_tools(mut self, enabled: bool) -> Self {
+        self.admin_tools = enabled;
+        self
+    }
 
-        Ok(results)
+    /// Answer requests rejected by the per-second limit with the cached response for the same
+    /// URL, even an expired one, instead of failing; only has an effect with caching enabled
+    pub fn with_serve_stale_on_limit(mut self, enabled: bool) -> Self {
+        self.serve_stale_on_limit = enabled;
+        self
     }
 
-    async fn perform_web_search(&self, query: &str, count: usize, offset: usize) -> Result<String> {
-        self.rate_limiter.check_rate_limit().await?;
+    /// Cache successful API responses for `ttl`; None (the default) disables caching.
+    ///
+    /// Entries are partitioned by a hash of the API key, and cache hits do not count against
+    /// the rate limiter.
+    pub fn with_cache_ttl(mut self, ttl: Option<Duration>) -> Self {
+        let negative_ttl = self.cache.as_ref().and_then(ResponseCache::negative_ttl);
+        self.cache = ResponseCache::new(ttl, negative_ttl, self.cache_backend.clone());
+        self
+    }
 
-        let url = reqwest::Url::parse_with_params(
-            "https://api.search.brave.com/res/v1/web/search",
-            &[
-                ("q", query),
-                ("count", &count.to_string()),
-                ("offset", &offset.to_string()),
-            ],
-        )?;
+    /// Cache responses without any results for `ttl`; None (the default) disables this.
+    ///
+    /// Kept separate from `with_cache_ttl` and meant to be shorter, so a retried dud query
+    /// does not spend quota while a query that starts returning results is seen soon.
+    pub fn with_negative_cache_ttl(mut self, ttl: Option<Duration>) -> Self {
+        let positive_ttl = self.cache.as_ref().and_then(ResponseCache::ttl);
+        self.cache = ResponseCache::new(positive_ttl, ttl, self.cache_backend.clone());
+        self
+    }
 
-        let response = self
-            .client
-            .get(url)
-            .header("Accept", "application/json")
-            .header("Accept-Encoding", "gzip")
-            .header("X-Subscription-Token", &self.api_key)
-            .send()
-            .await?;
+    /// Store cached responses in `backend` instead of the default in-memory `MemoryCache`,
+    /// e.g. to share one cache across several servers. Caching still has to be enabled with
+    /// `with_cache_ttl` or `with_negative_cache_ttl`.
+    pub fn with_cache_backend(mut self, backend: Arc<dyn SearchCache>) -> Self {
+        self.cache = self.cache.as_ref().and_then(|cache| {
+            ResponseCache::new(cache.ttl(), cache.negative_ttl(), backend.clone())
+        });
+        self.cache_backend = backend;
+        self
+    }
 
-        if !response.status().is_success() {
-            return Err(anyhow!(
-                "Brave API error: {} {}\n{}",
-                response.status().as_u16(),
-                response.status().canonical_reason().unwrap_or(""),
-                response.text().await?
-            ));
-        }
+    /// Set the output format used when a call does not pass `output_format`
+    pub fn with_output_format(mut self, format: OutputFormat) -> Self {
+        self.output_format = format;
+        self.formatter = None;
+        self
+    }
 
-        // With the gzip feature enabled, reqwest will automatically handle decompression
-        let data: BraveSearchResponse = response.json().await?;
-        let results = data
-            .web
-            .unwrap_or_default()
-            .results
-            .into_iter()
-            .map(|result| {
-                format!(
-                    "Title: {}\nDescription: {}\nURL: {}",
-                    result.title, result.description, result.url
-                )
-            })
-            .collect::<Vec<_>>()
-            .join("\n\n");
+    /// Use a custom formatter for calls that do not pass `output_format`
+    pub fn with_formatter(mut self, formatter: Arc<dyn ResultFormatter>) -> Self {
+        self.formatter = Some(formatter);
+        self
+    }
 
-        Ok(results)
+    /// Cut titles in the compact output format to `width` characters (default 60)
+    pub fn with_compact_title_width(mut self, width: usize) -> Self {
+        self.compact_title_width = width;
+        self
     }
 
-    async fn perform_local_search(&self, query: &str, count: usize) -> Result<String> {
-        self.rate_limiter.check_rate_limit().await?;
+    /// Sign every outgoing API request (including retries) with `signer`, e.g. for a gateway
+    /// that requires an HMAC header
+    pub fn with_signer(mut self, signer: Arc<dyn RequestSigner>) -> Self {
+        self.signer = signer;
+        self
+    }
 
-        // Use appropriate Local Search API endpoint and params
-        let url = reqwest::Url::parse_with_params(
-            "https://api.search.brave.com/res/v1/web/search",
-            &[
-                ("q", query),
-                ("search_lang", "en"),
-                ("result_filter", "locations"),
-                ("count", &count.to_string()),
-            ],
-        )?;
+    /// Send `name: value` on every API request (including retries), e.g. a tenant ID a gateway
+    /// routes by. Headers the server sets itself, such as `X-Subscription-Token`, and
+    /// credential-like names are rejected; setting a name again replaces its value
+    pub fn with_custom_header(mut self, name: &str, value: &str) -> Result<Self> {
+        let (name, value) = headers::custom_header(name, value)?;
+        self.custom_headers.insert(name, value);
+        Ok(self)
+    }
 
-        let response = self
-            .client
-            .get(url)
-            .header("Accept", "application/json")
-            .header("Accept-Encoding", "gzip")
-            .header("X-Subscription-Token", &self.api_key)
-            .send()
-            .await?;
+    /// Rewrite every web, news, and local query with `preprocessor` before it is sent, e.g. to
+    /// exclude a site for the whole deployment; the default leaves queries untouched
+    pub fn with_query_preprocessor(mut self, preprocessor: Arc<dyn QueryPreprocessor>) -> Self {
+        self.preprocessor = preprocessor;
+        self
+    }
 
-        if !response.status().is_success() {
-            return Err(anyhow!(
-                "Brave API error: {} {}\n{}",
-                response.status().as_u16(),
-                response.status().canonical_reason().unwrap_or(""),
-                response.text().await?
-            ));
+    /// Set the marker wrapped around query terms when a call passes `highlight` (default `**`)
+    pub fn with_highlight_marker(mut self, marker: String) -> Self {
+        self.highlight_marker = marker;
+        self
+    }
+
+    /// Highlighter for a call's titles and descriptions; the identity unless `highlight` is set
+    fn highlighter(&self, query: &str, highlight: Option<bool>) -> impl Fn(String) -> String + '_ {
+        let terms = highlight
+            .unwrap_or(false)
+            .then(|| highlight::query_terms(query));
+        move |text| match &terms {
+            Some(terms) => highlight::highlight(&text, terms, &self.highlight_marker),
+            None => text,
         }
+    }
 
-        // Parse the response using the new BraveSearchResponse structure
-        let search_data: BraveSearchResponse = response.json().await?;
+    /// Formatter for a call, preferring its explicit `output_format` over the router default
+    fn formatter(&self, format: Option<OutputFormat>) -> Arc<dyn ResultFormatter> {
+        match (format, &self.formatter) {
+            (None, Some(formatter)) => formatter.clone(),
+            (format, _) => match format.unwrap_or(self.output_format) {
+                OutputFormat::Compact => Arc::new(CompactFormatter::new(self.compact_title_width)),
+                format => format.formatter(),
+            },
+        }
+    }
 
-        // Extract location references from the search response
-        let location_refs = match &search_data.locations {
-            Some(locations) => &locations.results,
-            None => {
-                // Fall back to web search if no local results
-                return self.perform_web_search(query, count, 0).await;
-            }
-        };
+    /// Set the depth (offset * count) beyond which results get a deep-pagination note; None disables it
+    pub fn with_deep_pagination_threshold(mut self, threshold: Option<usize>) -> Self {
+        self.deep_pagination_threshold = threshold;
+        self
+    }
 
-        if location_refs.is_empty() {
-            // Fall back to web search if no local results
-            return self.perform_web_search(query, count, 0).await;
+    /// Advisory note for pages deep enough that Brave may return fewer or no results
+    fn deep_pagination_note(&self, offset: usize, count: usize) -> Option<String> {
+        let threshold = self.deep_pagination_threshold?;
+        if offset * count <= threshold {
+            return None;
         }
 
-        // Extract only the IDs for the POI data lookup
-        let location_ids: Vec<String> = location_refs.iter().map(|loc| loc.id.clone()).collect();
+        tracing::warn!(
+            "Deep pagination requested (offset {} x count {}); results may be sparse",
+            offset,
+            count
+        );
+        Some(format!(
+            "Note: this page starts {} results deep (offset {} x count {}). Brave's index often runs out at this depth, so fewer or no results may be returned; refine the query instead of paging further.",
+            offset * count,
+            offset,
+            count
+        ))
+    }
 
-        // Format results directly from location references if possible
-        let mut results = Vec::new();
+    /// Resolve the freshness for a news search, preferring an explicit value over the default
+    fn resolve_news_freshness(&self, freshness: Option<String>) -> Option<String> {
+        freshness.or_else(|| self.default_news_freshness.clone())
+    }
 
-        for loc_ref in location_refs {
-            let mut result_parts = Vec::new();
+    /// Count one invocation of `tool` against its monthly sub-budget, telling the client when it runs out
+    async fn check_tool_budget(&self, tool: &str) -> Result<()> {
+        let result = self.rate_limiter.check_tool_budget(tool).await;
+        if let Err(e) = &result {
+            self.client_log
+                .log(LoggingLevel::Warning, e.to_string())
+                .await;
+        }
+        result
+    }
 
-            // Try to use data directly from the search results first
-            if let Some(title) = &loc_ref.title {
-                result_parts.push(format!("Name: {}", title));
+    /// Admit one API request through the rate limiter, telling the client about limit events.
+    ///
+    /// A `retry` always waits out the per-second limit: it is already delayed, and rejecting it
+    /// would replace the failure being retried with a rate limit error.
+    async fn acquire_request_slot(&self, retry: bool) -> Result<()> {
+        let wait = retry || self.rate_limit_behavior == RateLimitBehavior::Wait;
+        let mut waited = Duration::ZERO;
+        let result = loop {
+            match self.rate_limiter.check_rate_limit().await {
+                Err(e) if wait && e.is::<PerSecondLimitExceeded>() => {
+                    let delay = self.rate_limiter.until_next_second().await;
+                    if waited + delay > MAX_RATE_LIMIT_WAIT {
+                        break Err(e);
+                    }
+                    report_wait(delay).await;
+                    tokio::time::sleep(delay).await;
+                    waited += delay;
+                }
+                result => break result,
             }
-
-            // Format address if available
-            if let Some(address) = &loc_ref.postal_address {
-                let address_parts = vec![
-                    address.street_address.as_deref().unwrap_or(""),
-                    address.address_locality.as_deref().unwrap_or(""),
-                    address.address_region.as_deref().unwrap_or(""),
-                    address.postal_code.as_deref().unwrap_or(""),
-                    address.country.as_deref().unwrap_or(""),
-                ];
-
-                let address_str = address_parts
-                    .into_iter()
-                    .filter(|part| !part.is_empty())
-                    .collect::<Vec<_>>()
-                    .join(", ");
-
-                if !address_str.is_empty() {
-                    result_parts.push(format!("Address: {}", address_str));
+        };
+        match result {
+            Ok(used) => {
+                let per_month = self.rate_limiter.limits.per_month;
+                if let Some(percent) = crossed_quota_threshold(used, per_month) {
+                    self.client_log
+                        .log(
+                            LoggingLevel::Warning,
+                            format!(
+                                "Monthly quota {}% used ({}/{} requests)",
+                                percent, used, per_month
+                            ),
+                        )
+                        .await;
                 }
+                Ok(())
             }
-
-            // Add coordinates if available
-            if let Some(coords) = &loc_ref.coordinates {
-                if coords.len() >= 2 {
-                    result_parts.push(format!("Coordinates: {}, {}", coords[0], coords[1]));
+            Err(e) if e.is::<MonthlyQuotaExhausted>() => {
+                // Every call fails until the window resets, so logging each one would drown
+                // out everything else
+                if self.quota_log.allow() {
+                    tracing::warn!("{}", e);
+                    self.client_log
+                        .log(LoggingLevel::Warning, e.to_string())
+                        .await;
                 }
+                Err(e)
+            }
+            Err(e) => {
+                self.client_log
+                    .log(LoggingLevel::Warning, e.to_string())
+                    .await;
+                Err(e)
             }
-
-            // Add the ID for reference
-            result_parts.push(format!("ID: {}", loc_ref.id));
-
-            results.push(result_parts.join("\n"));
         }
+    }
 
-        // If we have basic information, return it
-        if !results.is_empty() {
-            return Ok(results.join("\n---\n"));
+    /// Send a GET request to the Brave API and return the response body, using the cache if enabled
+    async fn send_request(&self, url: reqwest::Url) -> Result<String> {
+        let key = CacheKey::new(&self.api_key, &url);
+        if let Some(cache) = &self.cache {
+            if let Some(body) = cache.get(&key).await {
+                return Ok(body);
+            }
         }
 
-        // Fall back to the old method of getting detailed POI data
-        let pois_data = self.get_pois_data(&location_ids).await?;
-        let desc_data = self.get_descriptions_data(&location_ids).await?;
-
-        Ok(self.format_local_results(pois_data, desc_data))
+        // Identical concurrent requests share one API call and one unit of quota
+        self.in_flight
+            .run(key.clone(), || async {
+                let body = match self.send_request_with_headers(url).await {
+                    Ok((_, body)) => body,
+                    Err(e) => return self.serve_stale(&key, e).await,
+                };
+                if let Some(cache) = &self.cache {
+                    cache.insert(key, body.clone()).await;
+                }
+                Ok(body)
+            })
+            .await
     }
 
-    async fn get_pois_data(&self, ids: &[String]) -> Result<BravePoiResponse> {
-        self.rate_limiter.check_rate_limit().await?;
+    /// Re-send `url` once after the empty-retry delay when `body` has no results, replacing the
+    /// cached body when the retry finds some. The retry goes through the rate limiter; if it is
+    /// rejected or fails, the empty `body` stands.
+    async fn retry_if_empty(&self, url: reqwest::Url, body: String) -> String {
+        if !cache::has_no_results(&body) {
+            return body;
+        }
 
-        let mut url = reqwest::Url::parse("https://api.search.brave.com/res/v1/local/pois")?;
+        tracing::info!(
+            "Brave returned no results; retrying once in {:?}",
+            self.empty_retry_delay
+        );
+        tokio::time::sleep(self.empty_retry_delay).await;
+        let key = CacheKey::new(&self.api_key, &url);
+        match self.send_request_with_headers(url).await {
+            Ok((_, retried)) => {
+                if let Some(cache) = &self.cache {
+                    cache.insert(key, retried.clone()).await;
+                }
+                retried
+            }
+            Err(e) => {
+                tracing::warn!("Retry after empty results failed: {}", e);
+                body
+            }
+        }
+    }
 
-        // Add all IDs as query parameters
-        for id in ids {
-            url.query_pairs_mut().append_pair("ids", id);
+    /// Fall back to a stale cached body for `key` when `error` is a per-second limit hit and
+    /// serving stale content is enabled; otherwise return `error`
+    async fn serve_stale(&self, key: &CacheKey, error: anyhow::Error) -> Result<String> {
+        if !self.serve_stale_on_limit || !error.is::<PerSecondLimitExceeded>() {
+            return Err(error);
+        }
+        let Some(cache) = &self.cache else {
+            return Err(error);
+        };
+        match cache.get_stale(key).await {
+            Some(body) => {
+                tracing::info!("Rate limit exceeded; serving a cached response instead");
+                Ok(body)
+            }
+            None => Err(error),
         }
+    }
 
-        let response = self
-            .client
-            .get(url)
-            .header("Accept", "application/json")
-            .header("Accept-Encoding", "gzip")
-            .header("X-Subscription-Token", &self.api_key)
-            .send()
-            .await?;
+    /// Apply the forced safesearch level to a search endpoint URL, replacing any client value
+    fn enforce_safesearch(&self, mut url: reqwest::Url) -> reqwest::Url {
+        let Some(level) = self.forced_safesearch else {
+            return url;
+        };
+        // Only the search endpoints filter content; POI and description lookups take ids
+        if !url.path().ends_with("/search") {
+            return url;
+        }
 
-        if !response.status().is_success() {
-            return Err(anyhow!(
-                "Brave API error: {} {}\n{}",
-                response.status().as_u16(),
-                response.status().canonical_reason().unwrap_or(""),
-                response.text().await?
-            ));
+        let level = level.to_string();
+        let mut pairs = Vec::new();
+        for (name, value) in url.query_pairs() {
+            if name == "safesearch" {
+                if value != level {
+                    tracing::info!(
+                        "Overriding client safesearch={} with forced safesearch={}",
+                        value,
+                        level
+                    );
+                }
+            } else {
+                pairs.push((name.into_owned(), value.into_owned()));
+            }
         }
+        url.query_pairs_mut()
+            .clear()
+            .extend_pairs(pairs)
+            .append_pair("safesearch", &level);
+        url
+    }
 
-        let pois_response: BravePoiResponse = response.json().await?;
-        Ok(pois_response)
+    /// Error for a failed API response, telling the client about it
+    async fn api_error(&self, status: reqwest::StatusCode, error_text: String) -> anyhow::Error {
+        let reason = status.canonical_reason().unwrap_or("");
+        self.client_log
+            .log(
+                LoggingLevel::Error,
+                format!("Brave API error: {} {}", status.as_u16(), reason),
+            )
+            .await;
+        ApiError {
+            status,
+            body: error_text,
+        }
+        .into()
     }
 
-    async fn get_descriptions_data(&self, ids: &[String]) -> Result<BraveDescription> {
-        self.rate_limiter.check_rate_limit().await?;
+    /// Send a GET request to the Brave API through the rate limiter and return the response
+    /// headers and body
+    async fn send_request_with_headers(
+        &self,
+        url: reqwest::Url,
+    ) -> Result<(reqwest::header::HeaderMap, String)> {
+        self.send_api_request(reqwest::Method::GET, url).await
+    }
 
-        let mut url =
-            reqwest::Url::parse("https://api.search.brave.com/res/v1/local/descriptions")?;
+    /// Send a request to the Brave API through the rate limiter and return the response
+    /// headers and body, retrying transient failures only when `method` is idempotent
+    async fn send_api_request(
+        &self,
+        method: reqwest::Method,
+        url: reqwest::Url,
+    ) -> Result<(reqwest::header::HeaderMap, String)> {
+        let response = self.send_api_response(method, url).await?;
+        let headers = response.headers().clone();
+        Ok((headers, decode_body(&response.bytes().await?)))
+    }
 
-        // Add all IDs as query parameters
-        for id in ids {
-            url.query_pairs_mut().append_pair("ids", id);
+    /// Send a request like `send_api_request`, returning the successful response before its
+    /// body has been read
+    async fn send_api_response(
+        &self,
+        method: reqwest::Method,
+        url: reqwest::Url,
+    ) -> Result<reqwest::Response> {
+        let url = self.enforce_safesearch(url);
+        if self.demo {
+            return demo::response(&url);
         }
-
-        let response = self
-            .client
-            .get(url)
-            .header("Accept", "application/json")
-            .header("Accept-Encoding", "gzip")
-            .header("X-Subscription-Token", &self.api_key)
-            .send()
-            .await?;
+        let mut attempt = 0;
+        let response = loop {
+            // Every attempt is a billed API call, so retries are paced and counted too
+            if let Some(pacer) = &self.pacer {
+                let slot = pacer.reserve().await;
+                report_wait(slot.saturating_duration_since(tokio::time::Instant::now())).await;
+                tokio::time::sleep_until(slot).await;
+            }
+            self.acquire_request_slot(attempt > 0).await?;
+
+            let mut request = self
+                .client
+                .request(method.clone(), url.clone())
+                .header("Accept", "application/json")
+                .header("Accept-Encoding", "gzip")
+                .header("X-Subscription-Token", &self.api_key)
+                .headers(self.custom_headers.clone())
+                .build()?;
+            // Signed per attempt, so each retry carries a fresh timestamp
+            self.signer.sign(&mut request);
+            let result = self.client.execute(request).await;
+            let retries_left =
+                retry::is_idempotent(&method) && attempt < self.retry_policy.max_retries;
+
+            let maintenance = match result {
+                // Maintenance is only recognisable from the body, so 503 bodies are read here
+                Ok(response) if response.status() == reqwest::StatusCode::SERVICE_UNAVAILABLE => {
+                    let status = response.status();
+                    let error_text = response.text().await?;
+                    let maintenance = retry::is_maintenance_body(&error_text);
+                    if !retries_left {
+                        if maintenance {
+                            self.client_log
+                                .log(LoggingLevel::Error, retry::MAINTENANCE_MESSAGE)
+                                .await;
+                            return Err(anyhow!(retry::MAINTENANCE_MESSAGE));
+                        }
+                        return Err(self.api_error(status, error_text).await);
+                    }
+                    maintenance
+                }
+                result => {
+                    let retryable = match &result {
+                        Ok(response) => retry::is_retryable_status(response.status()),
+                        Err(e) => retry::is_retryable_error(e),
+                    };
+                    if !retryable || !retries_left {
+                        break result?;
+                    }
+                    false
+                }
+            };
+
+            let delay = if maintenance {
+                self.retry_policy
+                    .maintenance_delay(attempt, &mut rand::thread_rng())
+            } else {
+                self.retry_policy.delay(attempt, &mut rand::thread_rng())
+            };
+            tracing::warn!(
+                "Retrying Brave API request in {:?} (retry {}/{})",
+                delay,
+                attempt + 1,
+                self.retry_policy.max_retries
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        };
 
         if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await?;
+            return Err(self.api_error(status, error_text).await);
+        }
+
+        if let Some(encoding) = undecoded_content_encoding(response.headers()) {
             return Err(anyhow!(
-                "Brave API error: {} {}\n{}",
-                response.status().as_u16(),
-                response.status().canonical_reason().unwrap_or(""),
-                response.text().await?
+                "Brave API response is still {}-encoded; enable the reqwest `{}` feature in Cargo.toml to decompress it",
+                encoding,
+                decompression_feature(&encoding)
             ));
         }
 
-        let descriptions_data: BraveDescription = response.json().await?;
-        Ok(descriptions_data)
+        Ok(response)
     }
 
-    fn format_local_results(
-        &self,
-        pois_data: BravePoiResponse,
-        desc_data: BraveDescription,
-    ) -> String {
-        let results = pois_data.results.into_iter().map(|poi| {
-            let address = [
-                poi.address.street_address.unwrap_or_default(),
-                poi.address.address_locality.unwrap_or_default(),
-                poi.address.address_region.unwrap_or_default(),
-                poi.address.postal_code.unwrap_or_default(),
-            ]
-            .into_iter()
-            .filter(|part| !part.is_empty())
-            .collect::<Vec<_>>()
-            .join(", ");
-
-            let address_display = if address.is_empty() { "N/A" } else { &address };
-
-            let rating = poi.rating.as_ref().and_then(|r| r.rating_value)
-                .map(|val| val.to_string())
-                .unwrap_or_else(|| "N/A".to_string());
-
-            let rating_count = poi.rating.as_ref().and_then(|r| r.rating_count)
-                .map(|val| val.to_string())
-                .unwrap_or_else(|| "0".to_string());
-
-            let hours = poi.opening_hours.unwrap_or_default().join(", ");
-            let hours_display = if hours.is_empty() { "N/A" } else { &hours };
+    /// Brave's spell-corrected form of the query behind `body`, when it differs from `query`
+    fn altered_query(body: &str, query: &str) -> Option<String> {
+        let echo: BraveQueryEcho = serde_json::from_str(body).ok()?;
+        echo.query?
+            .altered
+            .filter(|altered| !altered.trim().is_empty() && altered != query)
+    }
 
-            let description = desc_data.descriptions.get(&poi.id)
-                .cloned()
-                .unwrap_or_else(|| "No description available".to_string());
+    /// Brave's estimate of the total number of results for the search behind `body`, if given
+    fn total_estimate(body: &str) -> Option<u64> {
+        let echo: BraveTotalEcho = serde_json::from_str(body).ok()?;
+        echo.web
+            .and_then(|web| web.total_count)
+            .or(echo.total_count)
+    }
 
-            format!(
-                "Name: {}\nAddress: {}\nPhone: {}\nRating: {} ({} reviews)\nPrice Range: {}\nHours: {}\nDescription: {}",
-                poi.name,
-                address_display,
-                poi.phone.unwrap_or_else(|| "N/A".to_string()),
-                rating,
-                rating_count,
-                poi.price_range.unwrap_or_else(|| "N/A".to_string()),
-                hours_display,
-                description
-            )
-        })
-        .collect::<Vec<_>>()
-        .join("\n---\n");
+    /// Re-send the search at `url` with `corrected` in place of its query, for calls that pass
+    /// `use_corrected_query`. The request goes through the rate limiter like any other; when
+    /// the per-second limit rejects it, it is tried once more after `empty_retry_delay`.
+    async fn search_corrected(&self, url: &reqwest::Url, corrected: &str) -> Result<String> {
+        let mut url = url.clone();
+        let pairs = url
+            .query_pairs()
+            .map(|(name, value)| {
+                let value = if name == "q" {
+                    corrected.to_string()
+                } else {
+                    value.into_owned()
+                };
+                (name.into_owned(), value)
+            })
+            .collect::<Vec<_>>();
+        url.query_pairs_mut().clear().extend_pairs(pairs);
 
-        if results.is_empty() {
-            "No local results found".to_string()
-        } else {
-            results
+        match self.send_request(url.clone()).await {
+            Err(e) if e.downcast_ref::<PerSecondLimitExceeded>().is_some() => {
+                tokio::time::sleep(self.empty_retry_delay).await;
+                self.send_request(url).await
+            }
+            result => result,
         }
     }
-}
 
-#[tool(tool_box)]
-impl BraveSearchRouter {
-    #[tool(
-        description = "Performs a web search using the Brave Search API, ideal for general queries, articles, and online content. This tool provides access to Brave's comprehensive web search index to find relevant websites, articles, and information across the internet. Results include title, description, and URL for each match to help answer factual questions and provide high-quality reference information."
-    )]
-    pub async fn brave_web_search(
+    /// Swap `body` for the results of Brave's corrected query when it has one, returning the
+    /// corrected query (if it was searched), the body to use, and a note about the outcome.
+    /// A failed corrected search keeps the original results.
+    async fn apply_corrected_query(
         &self,
-        #[tool(param)]
-        #[schemars(
-            description = "Search query to find relevant web results. Limited to maximum 400 characters or 50 words. Use specific, concise queries for best results."
-        )]
-        query: String,
-
-        #[tool(param)]
-        #[schemars(
-            description = "Number of results to return, between 1-20 (default 10). Higher values provide more comprehensive results but may include less relevant items."
-        )]
-        count: Option<usize>,
-
-        #[tool(param)]
-        #[schemars(
-            description = "Pagination offset for viewing additional results, maximum value 9 (default 0). Use incremental values to see more results beyond the initial set."
-        )]
-        offset: Option<usize>,
-    ) -> String {
-        let count = count.unwrap_or(10).min(20);
-        let offset = offset.unwrap_or(0).min(9);
-
-        match self.perform_web_search(&query, count, offset).await {
-            Ok(result) => result,
-            Err(e) => format!("Error: {}", e),
+        url: &reqwest::Url,
+        body: String,
+    ) -> (Option<String>, String, Option<String>) {
+        let sent = url
+            .query_pairs()
+            .find(|(name, _)| name == "q")
+            .map(|(_, value)| value.into_owned())
+            .unwrap_or_default();
+        let Some(altered) = Self::altered_query(&body, &sent) else {
+            return (None, body, None);
+        };
+        tracing::info!(
+            "Searching Brave's corrected query {:?} instead of {:?}",
+            altered,
+            sent
+        );
+        match self.search_corrected(url, &altered).await {
+            Ok(corrected) => {
+                let note = format!(
+                    "Showing results for \"{}\" instead of \"{}\" (Brave's spelling correction)",
+                    altered, sent
+                );
+                (Some(altered), corrected, Some(note))
+            }
+            Err(e) => {
+                tracing::warn!("Search for corrected query failed: {}", e);
+                let note = format!(
+                    "Brave suggested \"{}\", but searching it failed ({}); showing results for \"{}\"",
+                    altered, e, sent
+                );
+                (None, body, Some(note))
+            }
         }
     }
 
-    #[tool(
-        description = "Searches for news articles using the Brave News Search API, ideal for current events, breaking news, and time-sensitive topics. This tool retrieves the latest news articles from a wide range of global news sources, providing timely information on current events, breaking news, and trending topics. Results include titles, descriptions, URLs, publication age, and often thumbnail images to provide comprehensive news coverage with real-time updates."
-    )]
-    pub async fn brave_news_search(
+    async fn perform_news_search(
         &self,
-        #[tool(param)]
-        #[schemars(
-            description = "News search query specifying the news topic or keywords to search for. Limited to maximum 400 characters or 50 words. Use clear, specific terms for more targeted news results."
-        )]
-        query: String,
-
-        #[tool(param)]
-        #[schemars(
-            description = "Number of news articles to return, between 1-50 (default 20). Higher values provide more comprehensive coverage of a news topic."
-        )]
-        count: Option<usize>,
-
-        #[tool(param)]
-        #[schemars(
-            description = "Pagination offset for viewing additional news results, maximum value 9 (default 0). Use with subsequent requests to see more news beyond the initial set."
-        )]
-        offset: Option<usize>,
-
-        #[tool(param)]
-        #[schemars(
-            description = "Country code to filter news by geographic region. Options: ALL (worldwide), AR, AU, AT, BE, BR, CA, CL, DK, FI, FR, DE, HK, IN, ID, IT, JP, KR, MY, MX, NL, NZ, NO, CN, PL, PT, PH, RU, SA, ZA, ES, SE, CH, TW, TR, GB, US (default US). Use to get region-specific news coverage."
-        )]
-        country: Option<String>,
-
-        #[tool(param)]
-        #[schemars(
-            description = "Search language for news articles. Options: ar, eu, bn, bg, ca, zh-hans, zh-hant, hr, cs, da, nl, en, en-gb, et, fi, fr, gl, de, gu, he, hi, hu, is, it, ja, kn, ko, lv, lt, ms, ml, mr, nb, pl, pt, pt-br, pa, ro, ru, sr, sk, sl, es, sv, ta, te, th, tr, uk, vi (default en). Determines the language of retrieved news articles."
-        )]
-        search_lang: Option<String>,
-
-        #[tool(param)]
-        #[schemars(
-            description = "Timeframe filter to specify how recent the news should be. Use h (hour), d (day), w (week), m (month), or y (year) to control recency. Omit for all time periods. Most useful for filtering out older news when researching time-sensitive topics."
-        )]
-        freshness: Option<String>,
-    ) -> String {
-        let count = count.unwrap_or(20).min(50);
-        let offset = offset.unwrap_or(0).min(9);
+        params: &NewsSearchParams,
+        filters: &NewsFilters,
+    ) -> Result<String> {
+        let count = params.count.unwrap_or(20).min(50);
+        let offset = params.offset.unwrap_or(0).min(9);
 
-        // Parse country code if provided
-        let country_code = match country {
-            Some(c) => match CountryCode::from_str(&c) {
-                Ok(code) => Some(code),
-                Err(e) => return format!("Error parsing country code: {}", e),
-            },
-            None => None,
+        // Build URL with query parameters
+        let country_code = filters.country.unwrap_or_default().to_string();
+        let language_code = filters.lang.unwrap_or_default().to_string();
+
+        let mut query_params = vec![
+            ("q", self.preprocessor.process(&params.query)),
+            ("count", count.to_string()),
+            ("offset", offset.to_string()),
+            ("country", country_code),
+            ("search_lang", language_code),
+            ("spellcheck", "1".to_string()),
+        ];
+
+        // Add optional parameters
+        if let Some(freshness_val) = self.resolve_news_freshness(params.freshness.clone()) {
+            query_params.push(("freshness", freshness_val));
+        }
+
+        let mut url = reqwest::Url::parse_with_params(
+            &format!("{}/res/v1/news/search", self.base_url),
+            &query_params,
+        )?;
+        url.query_pairs_mut().extend_pairs(
+            validate_extra_params(params.extra_params.as_ref()).map_err(|e| anyhow!(e))?,
+        );
+
+        let mut response_text = self.send_request(url.clone()).await?;
+        if params.retry_on_empty.unwrap_or(false) {
+            response_text = self.retry_if_empty(url.clone(), response_text).await;
+        }
+        let (corrected, response_text, correction) = match params.use_corrected_query {
+            Some(true) => self.apply_corrected_query(&url, response_text).await,
+            _ => (None, response_text, None),
         };
+        let query = corrected.unwrap_or_else(|| params.query.clone());
+        let total_estimate = Self::total_estimate(&response_text);
 
-        // Parse language code if provided
-        let lang_code = match search_lang {
-            Some(l) => match LanguageCode::from_str(&l) {
-                Ok(code) => Some(code),
-                Err(e) => return format!("Error parsing language code: {}", e),
-            },
-            None => None,
+        // Parse the JSON
+        let data = match serde_json::from_str::<BraveNewsResponse>(&response_text) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                return Ok(format!("Failed to parse API response: {}", e));
+            }
         };
 
-        let freshness_param = freshness.as_deref();
+        let mut news_results = data.results;
+        apply_stable_sort(&mut news_results, params.stable_sort, |result| &result.url);
 
-        match self
-            .perform_news_search(
-                &query,
-                count,
-                offset,
-                country_code,
-                lang_code,
-                freshness_param,
-            )
-            .await
-        {
-            Ok(result) => result,
-            Err(e) => format!("Error: {}", e),
-        }
+        let include_source = params.include_source.unwrap_or(true);
+        let clean_urls = params.clean_urls.unwrap_or(false);
+        let mark = self.highlighter(&query, params.highlight);
+        let max_thumbnails = params.max_thumbnails.unwrap_or(1);
+        let now = chrono::Utc::now();
+        let items = news_results
+            .into_iter()
+            .map(|result| {
+                let published_at =
+                    timestamp::published_at(result.page_age.as_deref(), result.age.as_deref(), now)
+                        .map(|published| match filters.tz {
+                            Some(tz) => timestamp::to_zoned(published, tz),
+                            None => timestamp::to_iso8601(published),
+                        });
+                let source = result.source().filter(|_| include_source);
+                let description = format::strip_title_echo(&result.title, result.description);
+                let mut thumbnails = result
+                    .thumbnail
+                    .into_iter()
+                    .filter_map(|thumb| thumb.url(filters.thumbnail_size))
+                    .take(max_thumbnails);
+
+                NewsItem {
+                    breaking: result.breaking.unwrap_or(false),
+                    thumbnail: thumbnails.next(),
+                    extra_thumbnails: thumbnails.collect(),
+                    title: mark(result.title),
+                    description: mark(description),
+                    url: if clean_urls {
+                        tracking::strip_tracking_params(&result.url)
+                    } else {
+                        result.url
+                    },
+                    age: result.age,
+                    published_at,
+                    source,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let formatter = self.formatter(params.output_format);
+        let output = formatter.news(
+            &items,
+            OutputOptions::new(params.numbered, offset, count, params.max_output_bytes)
+                .with_thumbnails_as_gallery(params.thumbnails_as_gallery)
+                .with_pretty_json(params.pretty),
+        );
+        let output = formatter.with_total_estimate(output, total_estimate);
+        let output = formatter.with_note(output, correction);
+        Ok(formatter.with_note(output, self.deep_pagination_note(offset, count)))
     }
 
-    #[tool(
-        description = "Searches for local businesses and places using Brave's Local Search API. This specialized search tool finds physical locations, businesses, landmarks, and points of interest based on geographic queries. It provides detailed information about each location including names, addresses, phone numbers, ratings, hours of operation, and descriptions, making it ideal for finding local services, restaurants, attractions, and other location-based information."
-    )]
-    pub async fn brave_local_search(
+    /// Request URL for a web search showing `count` results from page `offset`
+    fn web_search_url(
         &self,
-        #[tool(param)]
-        #[schemars(
-            description = "Local search query specifying what and where to search. Format should include both the category/business type and location (e.g., 'pizza near Central Park', 'coffee shops in Seattle', 'gas stations near me'). More specific queries yield better results."
-        )]
-        query: String,
-
-        #[tool(param)]
-        #[schemars(
-            description = "Number of location results to return, between 1-20 (default 5). Higher values provide more options but may include less relevant locations. For popular searches in dense areas, higher values are recommended."
-        )]
-        count: Option<usize>,
-    ) -> String {
-        let count = count.unwrap_or(5).min(20);
+        params: &WebSearchParams,
+        count: usize,
+        offset: usize,
+    ) -> Result<reqwest::Url> {
+        let query = match &params.operators {
+            Some(operators) => operators.apply(&params.query)?,
+            None => params.query.clone(),
+        };
+        let mut query_params = vec![
+            ("q", self.preprocessor.process(&query)),
+            ("count", count.to_string()),
+            ("offset", offset.to_string()),
+        ];
+        if let Some(goggles) = &params.goggles {
+            query_params.push(("goggles", goggles.clone()));
+        }
+        if let Some(country) = params.country.as_deref() {
+            let country = CountryCode::from_str(country).map_err(|e| anyhow!(e))?;
+            query_params.push(("country", country.to_string()));
+        }
+        if let Some(lang) = params.search_lang.as_deref() {
+            let lang = LanguageCode::from_str(lang).map_err(|e| anyhow!(e))?;
+            query_params.push(("search_lang", lang.to_string()));
+        }
+        if let Some(safesearch) = params.safesearch.as_deref() {
+            let safesearch = SafeSearchLevel::from_str(safesearch).map_err(|e| anyhow!(e))?;
+            query_params.push(("safesearch", safesearch.to_string()));
+        }
 
-        match self.perform_local_search(&query, count).await {
-            Ok(result) => result,
-            Err(e) => format!("Error: {}", e),
+        let mut url = reqwest::Url::parse_with_params(
+            &format!("{}/res/v1/web/search", self.base_url),
+            &query_params,
+        )?;
+        url.query_pairs_mut().extend_pairs(
+            validate_extra_params(params.extra_params.as_ref()).map_err(|e| anyhow!(e))?,
+        );
+        Ok(url)
+    }
+
+    /// Web results of a response body, in the order the call asked for
+    fn parse_web_results(params: &WebSearchParams, body: &str) -> Result<Vec<BraveWebResult>> {
+        let mut data: BraveSearchResponse = serde_json::from_str(body)?;
+        let mixed = match params.mixed_order {
+            Some(true) => data.mixed.take(),
+            _ => None,
+        };
+        let mut web_results = match mixed {
+            Some(mixed) => {
+                let mut ordered = mixed_results(mixed, data);
+                // News and videos in Brave's layout come on top of the requested web results
+                ordered.truncate(params.count.unwrap_or(10).min(20));
+                ordered
+            }
+            None => data.web.unwrap_or_default().results,
+        };
+        apply_stable_sort(&mut web_results, params.stable_sort, |result| &result.url);
+        if params.prefer_recent.unwrap_or(false) {
+            let now = chrono::Utc::now();
+            // Newest first; undated results go last, and ties keep the current order
+            web_results.sort_by_cached_key(|result| {
+                std::cmp::Reverse(timestamp::published_at(
+                    result.page_age.as_deref(),
+                    result.age.as_deref(),
+                    now,
+                ))
+            });
         }
+        Ok(web_results)
     }
-}
 
-#[tool(tool_box)]
-impl ServerHandler for BraveSearchRouter {
-    fn get_info(&self) -> ServerInfo {
-        ServerInfo {
-            protocol_version: ProtocolVersion::V_2024_11_05,
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
-            server_info: Implementation::from_build_env(),
-            instructions: Some(r#"Brave Search MCP Server providing access to Brave's web, news, and local search APIs.
+    /// Display item for a web result, with its source when asked for and terms marked by `mark`
+    fn web_item(
+        result: BraveWebResult,
+        include_source: bool,
+        clean_urls: bool,
+        mark: &impl Fn(String) -> String,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> WebSearchItem {
+        let source = include_source.then(|| result.source_name()).flatten();
+        let updated = result
+            .page_age
+            .as_deref()
+            .and_then(timestamp::parse_absolute)
+            .map(|page_age| timestamp::time_ago(page_age, now));
+        let description = format::strip_title_echo(&result.title, result.description);
+        let url = if clean_urls {
+            tracking::strip_tracking_params(&result.url)
+        } else {
+            result.url
+        };
+        WebSearchItem {
+            title: mark(result.title),
+            description: mark(description),
+            url,
+            source,
+            kind: result.kind.map(str::to_string),
+            page_age: result.page_age,
+            updated,
+        }
+    }
+
+    async fn perform_web_search(&self, params: &WebSearchParams) -> Result<String> {
+        let count = params.count.unwrap_or(10).min(20);
+        let progress = ProgressReporter::new(
+            self.client_log.peer(),
+            params.progress_token.clone(),
+            count as u32,
+        );
+        progress
+            .scope(self.web_search_with_progress(params, count, &progress))
+            .await
+    }
+
+    /// `perform_web_search` with `progress` reporting each result as it arrives
+    async fn web_search_with_progress(
+        &self,
+        params: &WebSearchParams,
+        count: usize,
+        progress: &ProgressReporter,
+    ) -> Result<String> {
+        let offset = params.offset.unwrap_or(0).min(9);
+        let url = self.web_search_url(params, count, offset)?;
+
+        let formatter = self.formatter(params.output_format);
+        let mark = self.highlighter(&params.query, params.highlight);
+        let include_source = params.include_source.unwrap_or(false);
+        let clean_urls = params.clean_urls.unwrap_or(false);
+        let now = chrono::Utc::now();
+        let mut response_text = if params.progress_token.is_some() {
+            self.send_request_streaming(url.clone(), progress, |result| {
+                let item = Self::web_item(result, include_source, clean_urls, &mark, now);
+                formatter.web(&[item], OutputOptions::new(None, 0, 1, None))
+            })
+            .await?
+        } else {
+            self.send_request(url.clone()).await?
+        };
+        if params.retry_on_empty.unwrap_or(false) {
+            response_text = self.retry_if_empty(url.clone(), response_text).await;
+        }
+        let (corrected, response_text, correction) = match params.use_corrected_query {
+            Some(true) => self.apply_corrected_query(&url, response_text).await,
+            _ => (None, response_text, None),
+        };
+        let query = corrected.unwrap_or_else(|| params.query.clone());
+
+        let web_results = Self::parse_web_results(params, &response_text)?;
+        let total_estimate = Self::total_estimate(&response_text);
+
+        let answer = params
+            .extract_answer
+            .unwrap_or(false)
+            .then(|| {
+                answer::extract_answer(
+                    &query,
+                    web_results.iter().map(|result| result.description.as_str()),
+                )
+            })
+            .flatten();
+        let mark = self.highlighter(&query, params.highlight);
+
+        let items = web_results
+            .into_iter()
+            .map(|result| Self::web_item(result, include_source, clean_urls, &mark, now))
+            .collect::<Vec<_>>();
+
+        let output = formatter.web(
+            &items,
+            OutputOptions::new(params.numbered, offset, count, params.max_output_bytes)
+                .with_pretty_json(params.pretty),
+        );
+        let output = formatter.with_total_estimate(output, total_estimate);
+        let output = formatter.with_note(output, correction);
+        let output = formatter.with_note(output, self.deep_pagination_note(offset, count));
+        Ok(formatter.with_answer(output, answer))
+    }
+
+    /// Run a web search and yield its results as parsed items, for library consumers that
+    /// render incrementally without going through MCP.
+    ///
+    /// Items come in the order `brave_web_search` would show them, with the same sorting,
+    /// highlighting, and sources. The stream is currently fed from the fully parsed response.
+    /// Tool budgets are not charged, since no tool is invoked.
+    pub async fn search_web_stream(
+        &self,
+        params: WebSearchParams,
+    ) -> Result<impl futures::Stream<Item = WebSearchItem>> {
+        if let Some(goggles) = params.goggles.as_deref() {
+            validate_goggles_url(goggles).map_err(|e| anyhow!(e))?;
+        }
+        let count = params.count.unwrap_or(10).min(20);
+        let offset = params.offset.unwrap_or(0).min(9);
+        let url = self.web_search_url(&params, count, offset)?;
+
+        let mut response_text = self.send_request(url.clone()).await?;
+        if params.retry_on_empty.unwrap_or(false) {
+            response_text = self.retry_if_empty(url, response_text).await;
+        }
+
+        let include_source = params.include_source.unwrap_or(false);
+        let clean_urls = params.clean_urls.unwrap_or(false);
+        let mark = self.highlighter(&params.query, params.highlight);
+        let now = chrono::Utc::now();
+        let items = Self::parse_web_results(&params, &response_text)?
+            .into_iter()
+            .map(|result| Self::web_item(result, include_source, clean_urls, &mark, now))
+            .collect::<Vec<_>>();
+        Ok(futures::stream::iter(items))
+    }
+
+    async fn perform_web_search_batch(&self, queries: &[String], count: usize) -> String {
+        // Run as many queries at once as the per-second limit admits, waiting for the next
+        // window between rounds; join_all preserves the input order
+        let per_round = self.rate_limiter.limits.per_second.max(1);
+        let mut results = Vec::with_capacity(queries.len());
+        for (round, chunk) in queries.chunks(per_round).enumerate() {
+            if round > 0 {
+                let delay = self.rate_limiter.until_next_second().await;
+                report_wait(delay).await;
+                tokio::time::sleep(delay).await;
+            }
+            let searches = chunk.iter().map(|query| {
+                let params = WebSearchParams {
+                    query: query.clone(),
+                    count: Some(count),
+                    ..Default::default()
+                };
+                self.perform(SearchKind::Web, SearchParams::Web(params))
+            });
+            results.extend(futures::future::join_all(searches).await);
+        }
+
+        queries
+            .iter()
+            .zip(results)
+            .enumerate()
+            .map(|(i, (query, result))| {
+                let body = match result {
+                    Ok(result) if result.is_empty() => "No web results found".to_string(),
+                    Ok(result) => result,
+                    Err(e) => format!("Error: {}", e),
+                };
+                format!("## Query {}: {}\n{}", i + 1, query, body)
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    async fn perform_local_search(
+        &self,
+        params: &LocalSearchParams,
+        filters: &LocalFilters,
+    ) -> Result<String> {
+        let progress =
+            ProgressReporter::new(self.client_log.peer(), params.progress_token.clone(), 3);
+        progress
+            .scope(self.local_search_with_progress(params, filters, &progress))
+            .await
+    }
+
+    /// `perform_local_search` with `progress` reporting each stage as it starts
+    async fn local_search_with_progress(
+        &self,
+        params: &LocalSearchParams,
+        filters: &LocalFilters,
+        progress: &ProgressReporter,
+    ) -> Result<String> {
+        let category = filters.category;
+        let count = params.count.unwrap_or(5).min(20);
+        let offset = params.offset.unwrap_or(0).min(9);
+        let output = OutputOptions::new(params.numbered, offset, count, params.max_output_bytes)
+            .with_pretty_json(params.pretty);
+
+        // Steer the query toward the category unless it already names it
+        let query = match category {
+            Some(category) if !category.mentioned_in(&params.query) => {
+                format!("{} {}", params.query, category.query_context())
+            }
+            _ => params.query.clone(),
+        };
+
+        // Local results fall back to a web search for the same query
+        let web_params = WebSearchParams {
+            query: query.clone(),
+            count: Some(count),
+            offset: Some(offset),
+            numbered: params.numbered,
+            max_output_bytes: params.max_output_bytes,
+            stable_sort: params.stable_sort,
+            output_format: params.output_format,
+            pretty: params.pretty,
+            extra_params: params.extra_params.clone(),
+            ..Default::default()
+        };
+        let formatter = self.formatter(params.output_format);
+
+        // Use appropriate Local Search API endpoint and params
+        let mut url = reqwest::Url::parse_with_params(
+            &format!("{}/res/v1/web/search", self.base_url),
+            &[
+                ("q", self.preprocessor.process(&query).as_str()),
+                ("search_lang", "en"),
+                ("result_filter", "locations"),
+                ("count", &count.to_string()),
+                ("offset", &offset.to_string()),
+            ],
+        )?;
+        url.query_pairs_mut().extend_pairs(
+            validate_extra_params(params.extra_params.as_ref()).map_err(|e| anyhow!(e))?,
+        );
+
+        progress.stage(1, "searching locations").await;
+        let response_text = self.send_request(url).await?;
+
+        // Parse the response using the new BraveSearchResponse structure
+        let search_data: BraveSearchResponse = serde_json::from_str(&response_text)?;
+
+        // Extract location references from the search response
+        let mut location_refs = match &search_data.locations {
+            Some(locations) => locations.results.iter().collect::<Vec<_>>(),
+            None => {
+                // Fall back to web search if no local results
+                return self.perform_web_search(&web_params).await;
+            }
+        };
+
+        if location_refs.is_empty() {
+            // Fall back to web search if no local results
+            return self.perform_web_search(&web_params).await;
+        }
+
+        if let Some(category) = category {
+            location_refs.retain(|loc| category.matches(&loc.categories));
+            if location_refs.is_empty() {
+                let local = formatter.local(&[], output);
+                return Ok(self
+                    .supplement_local(local, 0, params, &web_params, formatter.as_ref())
+                    .await);
+            }
+        }
+
+        apply_stable_sort(&mut location_refs, params.stable_sort, |loc| &loc.id);
+
+        // Extract only the IDs for the POI data lookup
+        let location_ids: Vec<String> = location_refs.iter().map(|loc| loc.id.clone()).collect();
+        let rated = location_refs
+            .iter()
+            .map(|loc| meets_rating_filters(loc.rating.as_ref(), params))
+            .collect::<Vec<_>>();
+
+        // Format results directly from location references if possible
+        let items = location_refs
+            .into_iter()
+            .map(|loc_ref| {
+                let address = loc_ref.postal_address.as_ref().and_then(|address| {
+                    join_address(&[
+                        &address.street_address,
+                        &address.address_locality,
+                        &address.address_region,
+                        &address.postal_code,
+                        &address.country,
+                    ])
+                });
+                let (coordinates, coordinates_note) =
+                    parse_coordinates(&loc_ref.id, loc_ref.coordinates.as_deref());
+
+                LocalItem {
+                    id: loc_ref.id.clone(),
+                    name: loc_ref.title.clone(),
+                    address,
+                    coordinates,
+                    coordinates_note,
+                    details: None,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // Opening hours are only in the POI details
+        let open_now = params.open_now.unwrap_or(false);
+        let local_time = open_now.then(|| {
+            let now = chrono::Utc::now();
+            match filters.tz {
+                Some(tz) => now.with_timezone(&tz).naive_local(),
+                None => now.naive_utc(),
+            }
+        });
+
+        // If we have basic information, return it; open_now needs the hours from the POI data
+        let (local, found) = if !open_now && !items.is_empty() {
+            let items = items
+                .into_iter()
+                .zip(rated)
+                .filter_map(|(item, rated)| rated.then_some(item))
+                .collect::<Vec<_>>();
+            (formatter.local(&items, output), items.len())
+        } else {
+            // Otherwise fetch the detailed POI data
+            progress.stage(2, "fetching details").await;
+            let mut pois_data = self.get_pois_data(&location_ids).await?;
+            pois_data.results.retain(|poi| {
+                meets_rating_filters(poi.rating.as_ref(), params)
+                    && local_time.is_none_or(|at| is_open(poi, at))
+            });
+            apply_stable_sort(&mut pois_data.results, params.stable_sort, |poi| &poi.id);
+            progress.stage(3, "fetching descriptions").await;
+            let desc_data = self.get_descriptions_data(&location_ids).await?;
+
+            let found = pois_data.results.len();
+            let local = self.format_local_results(
+                pois_data,
+                desc_data,
+                formatter.as_ref(),
+                output,
+                params.raw_hours.unwrap_or(false),
+            );
+            (local, found)
+        };
+
+        Ok(self
+            .supplement_local(local, found, params, &web_params, formatter.as_ref())
+            .await)
+    }
+
+    /// Append web results for the same query to local output with fewer than
+    /// `min_local_results` locations. A failed web search leaves the local results as they
+    /// are, with a note saying why they were not supplemented.
+    async fn supplement_local(
+        &self,
+        local: String,
+        found: usize,
+        params: &LocalSearchParams,
+        web_params: &WebSearchParams,
+        formatter: &dyn ResultFormatter,
+    ) -> String {
+        let Some(min) = params.min_local_results.filter(|&min| found < min) else {
+            return local;
+        };
+        tracing::debug!(
+            "Found {} of at least {} locations; supplementing with web results",
+            found,
+            min
+        );
+        match self.perform_web_search(web_params).await {
+            Ok(web) => formatter.with_web_supplement(local, web),
+            Err(e) => formatter.with_note(
+                local,
+                Some(format!("Could not supplement with web results: {}", e)),
+            ),
+        }
+    }
+
+    /// `ids` split into runs whose lookup URL (`base` plus one `ids` param per ID) stays within
+    /// `MAX_LOOKUP_URL_LENGTH`; an ID too long to fit with others gets a request of its own
+    fn id_batches<'a>(base: &reqwest::Url, ids: &'a [String]) -> Vec<&'a [String]> {
+        let mut batches = Vec::new();
+        let mut start = 0;
+        let mut length = base.as_str().len();
+        for (i, id) in ids.iter().enumerate() {
+            let param =
+                Self::ids_url(base, std::slice::from_ref(id)).as_str().len() - base.as_str().len();
+            if i > start && length + param > MAX_LOOKUP_URL_LENGTH {
+                batches.push(&ids[start..i]);
+                start = i;
+                length = base.as_str().len();
+            }
+            length += param;
+        }
+        batches.push(&ids[start..]);
+        batches
+    }
+
+    /// `base` with each of `ids` appended as an `ids` query param
+    fn ids_url(base: &reqwest::Url, ids: &[String]) -> reqwest::Url {
+        let mut url = base.clone();
+        for id in ids {
+            url.query_pairs_mut().append_pair("ids", id);
+        }
+        url
+    }
+
+    /// Response bodies for looking up `ids` at the local `endpoint`, one per request.
+    ///
+    /// IDs are sent in as few requests as fit within `MAX_LOOKUP_URL_LENGTH`. A batch still
+    /// rejected with 414 URI Too Long is split in half and retried, down to single IDs.
+    async fn lookup_ids(&self, endpoint: &str, ids: &[String]) -> Result<Vec<String>> {
+        let base = reqwest::Url::parse(&format!("{}/res/v1/local/{}", self.base_url, endpoint))?;
+        let mut pending = Self::id_batches(&base, ids)
+            .into_iter()
+            .collect::<std::collections::VecDeque<_>>();
+        if pending.len() > 1 {
+            tracing::debug!(
+                "Looking up {} IDs at {} in {} requests",
+                ids.len(),
+                endpoint,
+                pending.len()
+            );
+        }
+
+        let mut bodies = Vec::new();
+        while let Some(batch) = pending.pop_front() {
+            match self.send_request(Self::ids_url(&base, batch)).await {
+                Ok(body) => bodies.push(body),
+                Err(e) if batch.len() > 1 && is_uri_too_long(&e) => {
+                    tracing::debug!("Lookup of {} IDs was too long; splitting it", batch.len());
+                    let (first, second) = batch.split_at(batch.len() / 2);
+                    pending.push_front(second);
+                    pending.push_front(first);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(bodies)
+    }
+
+    async fn get_pois_data(&self, ids: &[String]) -> Result<BravePoiResponse> {
+        let mut pois_response = BravePoiResponse {
+            results: Vec::new(),
+        };
+        for body in self.lookup_ids("pois", ids).await? {
+            let batch: BravePoiResponse = serde_json::from_str(&body)?;
+            pois_response.results.extend(batch.results);
+        }
+        Ok(pois_response)
+    }
+
+    async fn get_descriptions_data(&self, ids: &[String]) -> Result<BraveDescription> {
+        let mut descriptions_data = BraveDescription {
+            descriptions: HashMap::new(),
+        };
+        for body in self.lookup_ids("descriptions", ids).await? {
+            let batch: BraveDescription = serde_json::from_str(&body)?;
+            descriptions_data.descriptions.extend(batch.descriptions);
+        }
+        Ok(descriptions_data)
+    }
+
+    fn format_local_results(
+        &self,
+        pois_data: BravePoiResponse,
+        mut desc_data: BraveDescription,
+        formatter: &dyn ResultFormatter,
+        output: OutputOptions,
+        raw_hours: bool,
+    ) -> String {
+        let items = pois_data
+            .results
+            .into_iter()
+            .map(|poi| LocalItem {
+                address: join_address(&[
+                    &poi.address.street_address,
+                    &poi.address.address_locality,
+                    &poi.address.address_region,
+                    &poi.address.postal_code,
+                ]),
+                coordinates: poi
+                    .coordinates
+                    .and_then(|coords| Some([coords.latitude?, coords.longitude?])),
+                coordinates_note: None,
+                details: Some(LocalDetails {
+                    phone: poi.phone,
+                    rating: poi.rating.as_ref().and_then(|r| r.rating_value),
+                    rating_count: poi.rating.as_ref().and_then(|r| r.rating_count),
+                    price_range: poi.price_range,
+                    opening_hours: match poi.opening_hours {
+                        Some(entries) if !raw_hours => hours::compact_hours(&entries),
+                        entries => entries.unwrap_or_default(),
+                    },
+                    description: desc_data.descriptions.remove(&poi.id),
+                }),
+                name: Some(poi.name),
+                id: poi.id,
+            })
+            .collect::<Vec<_>>();
+
+        formatter.local(&items, output)
+    }
+}
+
+#[tool(tool_box)]
+impl BraveSearchRouter {
+    #[tool(
+        description = "Performs a web search using the Brave Search API, ideal for general queries, articles, and online content. This tool provides access to Brave's comprehensive web search index to find relevant websites, articles, and information across the internet. Results include title, description, and URL for each match to help answer factual questions and provide high-quality reference information."
+    )]
+    pub async fn brave_web_search(&self, #[tool(aggr)] params: WebSearchParams) -> String {
+        // Validate the Goggle URL if provided
+        if let Some(goggles) = params.goggles.as_deref() {
+            if let Err(e) = validate_goggles_url(goggles) {
+                return format!("Error parsing goggles: {}", e);
+            }
+        }
+
+        if let Some(operators) = &params.operators {
+            if let Err(e) = operators.apply(&params.query) {
+                return format!("Error parsing operators: {}", e);
+            }
+        }
+
+        // Parse country code if provided
+        if let Some(country) = params.country.as_deref() {
+            if let Err(e) = CountryCode::from_str(country) {
+                return format!("Error parsing country code: {}", e);
+            }
+        }
+
+        if let Some(lang) = params.search_lang.as_deref() {
+            if let Err(e) = LanguageCode::from_str(lang) {
+                return format!("Error parsing language code: {}", e);
+            }
+        }
+
+        if let Some(safesearch) = params.safesearch.as_deref() {
+            if let Err(e) = SafeSearchLevel::from_str(safesearch) {
+                return format!("Error parsing safesearch: {}", e);
+            }
+        }
+
+        if let Err(e) = validate_extra_params(params.extra_params.as_ref()) {
+            return format!("Error parsing extra_params: {}", e);
+        }
+
+        if let Err(e) = self.check_tool_budget("brave_web_search").await {
+            return format!("Error: {}", e);
+        }
+
+        match self
+            .perform(SearchKind::Web, SearchParams::Web(params))
+            .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "Runs several web searches in one call using the Brave Search API and returns the results grouped per query, in the order the queries were given. Use this instead of repeated brave_web_search calls when you have a handful of related queries (up to 5). Each group starts with a `## Query N: <query>` heading; a failing query reports its error inline without affecting the others. Example usage: `{\"name\": \"brave_web_search_batch\", \"arguments\": {\"queries\": [\"rust async runtime\", \"tokio vs async-std\"]}}`. With count: `{\"name\": \"brave_web_search_batch\", \"arguments\": {\"queries\": [\"serde json\", \"simd-json\"], \"count\": 3}}`"
+    )]
+    pub async fn brave_web_search_batch(
+        &self,
+        #[tool(aggr)] params: WebSearchBatchParams,
+    ) -> String {
+        if params.queries.is_empty() {
+            return "Error: at least one query is required".to_string();
+        }
+        if params.queries.len() > MAX_BATCH_QUERIES {
+            return format!(
+                "Error: at most {} queries are allowed per batch, got {}",
+                MAX_BATCH_QUERIES,
+                params.queries.len()
+            );
+        }
+
+        if let Err(e) = self.check_tool_budget("brave_web_search_batch").await {
+            return format!("Error: {}", e);
+        }
+
+        let count = params.count.unwrap_or(10).min(20);
+        self.perform_web_search_batch(&params.queries, count).await
+    }
+
+    #[tool(
+        description = "Searches for news articles using the Brave News Search API, ideal for current events, breaking news, and time-sensitive topics. This tool retrieves the latest news articles from a wide range of global news sources, providing timely information on current events, breaking news, and trending topics. Results include titles, descriptions, URLs, publication age, and often thumbnail images to provide comprehensive news coverage with real-time updates."
+    )]
+    pub async fn brave_news_search(&self, #[tool(aggr)] params: NewsSearchParams) -> String {
+        // Validate the country, language, and timezone before charging the budget
+        if let Err(e) = NewsFilters::parse(&params) {
+            return e;
+        }
+
+        if let Err(e) = validate_extra_params(params.extra_params.as_ref()) {
+            return format!("Error parsing extra_params: {}", e);
+        }
+
+        if let Err(e) = self.check_tool_budget("brave_news_search").await {
+            return format!("Error: {}", e);
+        }
+
+        match self
+            .perform(SearchKind::News, SearchParams::News(params))
+            .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "Searches for local businesses and places using Brave's Local Search API. This specialized search tool finds physical locations, businesses, landmarks, and points of interest based on geographic queries. It provides detailed information about each location including names, addresses, phone numbers, ratings, hours of operation, and descriptions, making it ideal for finding local services, restaurants, attractions, and other location-based information."
+    )]
+    pub async fn brave_local_search(&self, #[tool(aggr)] params: LocalSearchParams) -> String {
+        if let Some(min_rating) = params.min_rating {
+            if !(0.0..=5.0).contains(&min_rating) {
+                return format!(
+                    "Error: min_rating must be between 0 and 5, got {}",
+                    min_rating
+                );
+            }
+        }
+
+        if let Err(e) = LocalFilters::parse(&params) {
+            return e;
+        }
+
+        if let Err(e) = validate_extra_params(params.extra_params.as_ref()) {
+            return format!("Error parsing extra_params: {}", e);
+        }
+
+        if let Err(e) = self.check_tool_budget("brave_local_search").await {
+            return format!("Error: {}", e);
+        }
+
+        match self
+            .perform(SearchKind::Local, SearchParams::Local(params))
+            .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "Suggests completions for a partial query using Brave's autocomplete, one per line, so a short or half-typed query can be expanded before searching. Optional country and search_lang tailor the suggestions. Returns 'No suggestions found' when Brave has none. Uses one request of quota. Example usage: `{\"name\": \"brave_suggest\", \"arguments\": {\"query\": \"rust asy\"}}`. With count and country: `{\"name\": \"brave_suggest\", \"arguments\": {\"query\": \"weather\", \"count\": 3, \"country\": \"JP\"}}`. With language: `{\"name\": \"brave_suggest\", \"arguments\": {\"query\": \"recette cr\", \"country\": \"FR\", \"search_lang\": \"fr\"}}`"
+    )]
+    pub async fn brave_suggest(&self, #[tool(aggr)] params: SuggestParams) -> String {
+        if let Some(country) = params.country.as_deref() {
+            if let Err(e) = CountryCode::from_str(country) {
+                return format!("Error parsing country code: {}", e);
+            }
+        }
+
+        if let Some(lang) = params.search_lang.as_deref() {
+            if let Err(e) = LanguageCode::from_str(lang) {
+                return format!("Error parsing language code: {}", e);
+            }
+        }
+
+        if let Err(e) = self.check_tool_budget("brave_suggest").await {
+            return format!("Error: {}", e);
+        }
+
+        match self.perform_suggest(&params).await {
+            Ok(result) => result,
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "Reports the Brave Search API quota for the configured API key: per-second and monthly limits, remaining requests, and time until reset. Brave does not expose plan details, so this is a best-effort report built from the rate-limit headers of a minimal 1-result web search (which uses one request of quota). Use it to check whether larger or more frequent requests will be accepted. Takes no arguments. Returns plain text lines: a 'Plan:' note, one line per quota window reported by Brave (e.g. 'Per second: limit 1, remaining 0, resets in 1s' and 'Per month: limit 15000, remaining 14321, resets in 1419704s', or 'API quota: unknown' when Brave sends no rate-limit headers), and a 'Server limiter:' line with this server's own per-second and monthly limits and requests used this month. Example usage: `{\"name\": \"brave_account_info\", \"arguments\": {}}`. Arguments may also be omitted: `{\"name\": \"brave_account_info\"}`"
+    )]
+    pub async fn brave_account_info(&self) -> String {
+        if let Err(e) = self.check_tool_budget("brave_account_info").await {
+            return format!("Error: {}", e);
+        }
+
+        match self.perform_account_info().await {
+            Ok(result) => result,
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "Returns the server's effective runtime configuration as JSON: base URL, enabled tools, rate limits and tool budgets, request timeout, retries, request pacing, cache TTLs, and search defaults. The API key is always redacted. Makes no API request and uses no quota. Use it to confirm how the server is configured before relying on a limit or setting. Takes no arguments. Example usage: `{\"name\": \"brave_server_config\", \"arguments\": {}}`. Arguments may also be omitted: `{\"name\": \"brave_server_config\"}`"
+    )]
+    pub async fn brave_server_config(&self) -> String {
+        match serde_json::to_string_pretty(&self.server_config().await) {
+            Ok(config) => config,
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "Admin tool: empties the server's response cache and reports how many cached responses were dropped, so the next searches fetch fresh results (e.g. after a breaking news event). Only available when the server runs with --admin-tools; otherwise it returns an error. Makes no API request and uses no quota. Takes no arguments and returns e.g. 'Cleared 12 cached responses'. Example usage: `{\"name\": \"brave_cache_clear\", \"arguments\": {}}`. Arguments may also be omitted: `{\"name\": \"brave_cache_clear\"}`"
+    )]
+    pub async fn brave_cache_clear(&self) -> String {
+        if !self.admin_tools {
+            return "Error: brave_cache_clear is an admin tool; start the server with --admin-tools to enable it".to_string();
+        }
+        let Some(cache) = &self.cache else {
+            return "Caching is disabled; nothing to clear".to_string();
+        };
+        let Some(cleared) = cache.clear().await else {
+            return "Error: the configured cache backend does not support clearing".to_string();
+        };
+        tracing::info!("Cleared {} cached responses", cleared);
+        format!(
+            "Cleared {} cached response{}",
+            cleared,
+            if cleared == 1 { "" } else { "s" }
+        )
+    }
+}
+
+#[tool(tool_box)]
+impl ServerHandler for BraveSearchRouter {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            protocol_version: ProtocolVersion::V_2024_11_05,
+            capabilities: ServerCapabilities::builder()
+                .enable_logging()
+                .enable_tools()
+                .build(),
+            server_info: Implementation::from_build_env(),
+            instructions: Some(r#"Brave Search MCP Server providing access to Brave's web, news, and local search APIs.
 
 TOOL USAGE EXAMPLES:
 
-1. Web Search - For general information queries:
-   ```
-   brave_web_search(
-     query: "rust programming language benefits",
-     count: 5,  // Optional: Get 5 results (default: 10, max: 20)
-     offset: 0  // Optional: Start from first result (default: 0, max: 9)
-   )
-   ```
+1. Web Search - For general information queries:
+   ```
+   brave_web_search(
+     query: "rust programming language benefits",
+     count: 5,  // Optional: Get 5 results (default: 10, max: 20)
+     offset: 0  // Optional: Start from first result (default: 0, max: 9)
+   )
+   ```
+
+2. Batch Web Search - For several related queries at once:
+   ```
+   brave_web_search_batch(
+     queries: ["rust async runtime", "tokio vs async-std"],  // 1-5 queries
+     count: 3  // Optional: Results per query (default: 10, max: 20)
+   )
+   ```
+
+3. News Search - For current events and breaking news:
+   ```
+   brave_news_search(
+     query: "artificial intelligence developments",
+     count: 10,            // Optional: Number of results (default: 20, max: 50)
+     offset: 0,            // Optional: Pagination offset (default: 0, max: 9)
+     country: "US",        // Optional: Country code (default: US)
+     search_lang: "en",    // Optional: Language code (default: en)
+     freshness: "d"        // Optional: Timeframe - d=day, w=week, m=month
+   )
+   ```
+
+4. Local Search - For businesses and physical locations:
+   ```
+   brave_local_search(
+     query: "pizza restaurants near Times Square",
+     count: 5  // Optional: Number of results (default: 5, max: 20)
+   )
+   ```
+
+5. Suggest - For completing a partial query before searching:
+   ```
+   brave_suggest(
+     query: "rust asy",
+     country: "US",  // Optional: Country code
+     search_lang: "en"  // Optional: Language code
+   )
+   ```
+
+6. Account Info - For checking API quota limits and remaining requests:
+   ```
+   brave_account_info()  // Uses one request of quota
+   ```
+
+7. Server Config - For checking the server's effective configuration (API key redacted):
+   ```
+   brave_server_config()  // Uses no quota
+   ```
+
+8. Cache Clear - For dropping cached results (only when the server runs with --admin-tools):
+   ```
+   brave_cache_clear()  // Uses no quota
+   ```
+
+All searches respect rate limits and provide formatted, readable results. Choose the appropriate tool based on the type of information needed."#.to_string()),
+        }
+    }
+
+    async fn set_level(
+        &self,
+        request: SetLevelRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), rmcp::Error> {
+        self.client_log.set_level(request.level).await;
+        Ok(())
+    }
+
+    fn get_peer(&self) -> Option<Peer<RoleServer>> {
+        self.client_log.peer()
+    }
+
+    fn set_peer(&mut self, peer: Peer<RoleServer>) {
+        // Each session gets its own peer and log level
+        self.client_log = ClientLogger::for_peer(peer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_fixtures::{json_mock, router_for};
+    use super::*;
+    use clock::MockClock;
+
+    /// The results array of JSON web or news output
+    fn json_results<T: serde::de::DeserializeOwned>(output: &str) -> T {
+        let mut output: serde_json::Value = serde_json::from_str(output).unwrap();
+        serde_json::from_value(output["results"].take()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_brave_search_apis() {
+        // Skip the test if API key is not set in environment
+        let api_key = std::env::var("BRAVE_API_KEY").unwrap_or_else(|_| {
+            eprintln!("BRAVE_API_KEY environment variable not set, skipping test");
+            String::from("dummy_key")
+        });
+
+        // Only run this test if we have a real API key
+        if api_key == "dummy_key" {
+            // Skip the test
+            return;
+        }
+
+        // Create a BraveSearchRouter with the API key
+        let router = BraveSearchRouter::new(api_key);
+
+        // Test 1: Web Search
+        let web_result = router
+            .brave_web_search(WebSearchParams {
+                query: "Rust programming language".to_string(),
+                count: Some(3),
+                ..Default::default()
+            })
+            .await;
+
+        println!("Web search result: {}", web_result);
+        assert!(!web_result.is_empty());
+        assert!(web_result.contains("Rust"));
+
+        // Test 2: News Search with country and language
+        let news_result = router
+            .brave_news_search(NewsSearchParams {
+                query: "technology".to_string(),
+                count: Some(3),
+                country: Some("JP".to_string()),
+                search_lang: Some("en".to_string()),
+                freshness: Some("w".to_string()),
+                ..Default::default()
+            })
+            .await;
+
+        println!("News search result (JP, en): {}", news_result);
+        assert!(!news_result.is_empty());
+        assert!(news_result != "No news results found");
+        assert!(!news_result.starts_with("Error parsing"));
+
+        // Test 3: Local Search
+        let local_result = router
+            .brave_local_search(LocalSearchParams {
+                query: "coffee shop".to_string(),
+                count: Some(2),
+                ..Default::default()
+            })
+            .await;
+
+        println!("Local search result: {}", local_result);
+        assert!(!local_result.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_news_search_with_query() {
+        // Skip the test if API key is not set in environment
+        let api_key = std::env::var("BRAVE_API_KEY").unwrap_or_else(|_| {
+            eprintln!("BRAVE_API_KEY environment variable not set, skipping test");
+            String::from("dummy_key")
+        });
+
+        // Only run this test if we have a real API key
+        if api_key == "dummy_key" {
+            // Skip the test
+            return;
+        }
+
+        // Create a BraveSearchRouter with the API key
+        let router = BraveSearchRouter::new(api_key);
+
+        // Search for current news with US country code and English language
+        // Use "news" as a generic query that should always return results
+        let news_result = router
+            .brave_news_search(NewsSearchParams {
+                query: "news".to_string(),
+                count: Some(3),
+                country: Some("US".to_string()),
+                search_lang: Some("en".to_string()),
+                ..Default::default()
+            })
+            .await;
+
+        println!("News search result: {}", news_result);
+
+        // Verify we got results
+        assert!(!news_result.is_empty());
+        assert!(news_result != "No news results found");
+        assert!(!news_result.starts_with("Error parsing"));
+
+        // Print the API response details
+        println!("\nNews search API response received successfully!");
+    }
+
+    // New unit tests
+    #[test]
+    fn test_country_code_from_str() {
+        // Test valid country codes
+        assert_eq!(CountryCode::from_str("US").unwrap(), CountryCode::US);
+        assert_eq!(CountryCode::from_str("us").unwrap(), CountryCode::US);
+        assert_eq!(CountryCode::from_str("JP").unwrap(), CountryCode::JP);
+        assert_eq!(CountryCode::from_str("all").unwrap(), CountryCode::ALL);
+
+        // Test invalid country code
+        let invalid = CountryCode::from_str("ZZ");
+        assert!(invalid.is_err());
+        assert_eq!(invalid.unwrap_err(), "Unknown country code: ZZ");
+    }
+
+    #[test]
+    fn test_language_code_from_str() {
+        // Test valid language codes
+        assert_eq!(LanguageCode::from_str("en").unwrap(), LanguageCode::EN);
+        assert_eq!(LanguageCode::from_str("EN").unwrap(), LanguageCode::EN);
+        assert_eq!(LanguageCode::from_str("en-gb").unwrap(), LanguageCode::EnGb);
+        assert_eq!(
+            LanguageCode::from_str("zh-hans").unwrap(),
+            LanguageCode::ZhHans
+        );
+
+        // Test invalid language code
+        let invalid = LanguageCode::from_str("xx");
+        assert!(invalid.is_err());
+        assert_eq!(invalid.unwrap_err(), "Unknown language code: xx");
+    }
+
+    // Every valid locale code, in canonical (Display) form. These double as the
+    // corpus for the property tests below; the exhaustive matches in
+    // `test_locale_corpus_is_complete` fail to compile when a variant is added
+    // without updating the corpus.
+    const COUNTRY_CODE_CORPUS: [&str; 37] = [
+        "all", "ar", "au", "at", "be", "br", "ca", "cl", "dk", "fi", "fr", "de", "hk", "in", "id",
+        "it", "jp", "kr", "my", "mx", "nl", "nz", "no", "cn", "pl", "pt", "ph", "ru", "sa", "za",
+        "es", "se", "ch", "tw", "tr", "gb", "us",
+    ];
+    const LANGUAGE_CODE_CORPUS: [&str; 50] = [
+        "ar", "eu", "bn", "bg", "ca", "zh-hans", "zh-hant", "hr", "cs", "da", "nl", "en", "en-gb",
+        "et", "fi", "fr", "gl", "de", "gu", "he", "hi", "hu", "is", "it", "ja", "kn", "ko", "lv",
+        "lt", "ms", "ml", "mr", "nb", "pl", "pt", "pt-br", "pa", "ro", "ru", "sr", "sk", "sl",
+        "es", "sv", "ta", "te", "th", "tr", "uk", "vi",
+    ];
+
+    #[test]
+    fn test_locale_corpus_is_complete() {
+        for code in COUNTRY_CODE_CORPUS {
+            match CountryCode::from_str(code).unwrap() {
+                CountryCode::ALL
+                | CountryCode::AR
+                | CountryCode::AU
+                | CountryCode::AT
+                | CountryCode::BE
+                | CountryCode::BR
+                | CountryCode::CA
+                | CountryCode::CL
+                | CountryCode::DK
+                | CountryCode::FI
+                | CountryCode::FR
+                | CountryCode::DE
+                | CountryCode::HK
+                | CountryCode::IN
+                | CountryCode::ID
+                | CountryCode::IT
+                | CountryCode::JP
+                | CountryCode::KR
+                | CountryCode::MY
+                | CountryCode::MX
+                | CountryCode::NL
+                | CountryCode::NZ
+                | CountryCode::NO
+                | CountryCode::CN
+                | CountryCode::PL
+                | CountryCode::PT
+                | CountryCode::PH
+                | CountryCode::RU
+                | CountryCode::SA
+                | CountryCode::ZA
+                | CountryCode::ES
+                | CountryCode::SE
+                | CountryCode::CH
+                | CountryCode::TW
+                | CountryCode::TR
+                | CountryCode::GB
+                | CountryCode::US => {}
+            }
+        }
+        for code in LANGUAGE_CODE_CORPUS {
+            match LanguageCode::from_str(code).unwrap() {
+                LanguageCode::AR
+                | LanguageCode::EU
+                | LanguageCode::BN
+                | LanguageCode::BG
+                | LanguageCode::CA
+                | LanguageCode::ZhHans
+                | LanguageCode::ZhHant
+                | LanguageCode::HR
+                | LanguageCode::CS
+                | LanguageCode::DA
+                | LanguageCode::NL
+                | LanguageCode::EN
+                | LanguageCode::EnGb
+                | LanguageCode::ET
+                | LanguageCode::FI
+                | LanguageCode::FR
+                | LanguageCode::GL
+                | LanguageCode::DE
+                | LanguageCode::GU
+                | LanguageCode::HE
+                | LanguageCode::HI
+                | LanguageCode::HU
+                | LanguageCode::IS
+                | LanguageCode::IT
+                | LanguageCode::JA
+                | LanguageCode::KN
+                | LanguageCode::KO
+                | LanguageCode::LV
+                | LanguageCode::LT
+                | LanguageCode::MS
+                | LanguageCode::ML
+                | LanguageCode::MR
+                | LanguageCode::NB
+                | LanguageCode::PL
+                | LanguageCode::PT
+                | LanguageCode::PtBr
+                | LanguageCode::PA
+                | LanguageCode::RO
+                | LanguageCode::RU
+                | LanguageCode::SR
+                | LanguageCode::SK
+                | LanguageCode::SL
+                | LanguageCode::ES
+                | LanguageCode::SV
+                | LanguageCode::TA
+                | LanguageCode::TE
+                | LanguageCode::TH
+                | LanguageCode::TR
+                | LanguageCode::UK
+                | LanguageCode::VI => {}
+            }
+        }
+    }
+
+    /// Strings equal to a valid code up to ASCII case
+    fn is_known_locale(value: &str) -> bool {
+        let lowered = value.to_lowercase();
+        COUNTRY_CODE_CORPUS.contains(&lowered.as_str())
+            || LANGUAGE_CODE_CORPUS.contains(&lowered.as_str())
+    }
+
+    /// A corpus code with the case of each character flipped according to `mask`
+    fn with_case_mask(code: &str, mask: u32) -> String {
+        code.chars()
+            .enumerate()
+            .map(|(i, c)| {
+                if mask & (1 << i) != 0 {
+                    c.to_ascii_uppercase()
+                } else {
+                    c
+                }
+            })
+            .collect()
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn prop_country_code_round_trips(index in 0..COUNTRY_CODE_CORPUS.len(), mask in proptest::prelude::any::<u32>()) {
+            let input = with_case_mask(COUNTRY_CODE_CORPUS[index], mask);
+            let parsed = CountryCode::from_str(&input).unwrap();
+            proptest::prop_assert_eq!(parsed.to_string(), COUNTRY_CODE_CORPUS[index]);
+            proptest::prop_assert_eq!(CountryCode::from_str(&parsed.to_string()), Ok(parsed));
+        }
+
+        #[test]
+        fn prop_language_code_round_trips(index in 0..LANGUAGE_CODE_CORPUS.len(), mask in proptest::prelude::any::<u32>()) {
+            let input = with_case_mask(LANGUAGE_CODE_CORPUS[index], mask);
+            let parsed = LanguageCode::from_str(&input).unwrap();
+            proptest::prop_assert_eq!(parsed.to_string(), LANGUAGE_CODE_CORPUS[index]);
+            proptest::prop_assert_eq!(LanguageCode::from_str(&parsed.to_string()), Ok(parsed));
+        }
+
+        #[test]
+        fn prop_arbitrary_strings_are_rejected(input in "\\PC{0,12}") {
+            proptest::prop_assume!(!is_known_locale(&input));
+            proptest::prop_assert_eq!(
+                CountryCode::from_str(&input),
+                Err(format!("Unknown country code: {}", input))
+            );
+            proptest::prop_assert_eq!(
+                LanguageCode::from_str(&input),
+                Err(format!("Unknown language code: {}", input))
+            );
+        }
+
+        // Near misses: short ASCII strings shaped like real codes
+        #[test]
+        fn prop_code_shaped_strings_are_rejected(input in "[a-zA-Z]{1,3}(-[a-zA-Z]{2,4})?") {
+            proptest::prop_assume!(!is_known_locale(&input));
+            proptest::prop_assert!(CountryCode::from_str(&input).is_err());
+            proptest::prop_assert!(LanguageCode::from_str(&input).is_err());
+        }
+    }
+
+    #[test]
+    fn test_country_code_display() {
+        assert_eq!(CountryCode::US.to_string(), "us");
+        assert_eq!(CountryCode::ALL.to_string(), "all");
+        assert_eq!(CountryCode::JP.to_string(), "jp");
+    }
+
+    #[test]
+    fn test_language_code_display() {
+        assert_eq!(LanguageCode::EN.to_string(), "en");
+        assert_eq!(LanguageCode::EnGb.to_string(), "en-gb");
+        assert_eq!(LanguageCode::ZhHans.to_string(), "zh-hans");
+    }
+
+    fn mock_limiter(per_second: usize, per_month: usize) -> (RateLimiter, Arc<MockClock>) {
+        let clock = Arc::new(MockClock::new());
+        let limiter = RateLimiter::with_limits(per_second, per_month).with_clock(clock.clone());
+        (limiter, clock)
+    }
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    #[tokio::test]
+    async fn test_rate_limiter() {
+        let (limiter, clock) = mock_limiter(RATE_LIMIT_PER_SECOND, 2);
+
+        // First request should succeed
+        assert_eq!(limiter.check_rate_limit().await.unwrap(), 1);
+
+        // Next request in the same second should fail due to rate limit
+        let err = limiter.check_rate_limit().await.unwrap_err();
+        assert_eq!(err.to_string(), "Rate limit exceeded");
+
+        // A second later the request is admitted, using up the monthly limit
+        clock.advance(Duration::from_millis(1100));
+        assert_eq!(limiter.check_rate_limit().await.unwrap(), 2);
+
+        // Request should fail due to monthly limit, whatever the second
+        clock.advance(Duration::from_millis(1100));
+        let err = limiter.check_rate_limit().await.unwrap_err();
+        assert!(err.to_string().starts_with("monthly quota exhausted"));
+    }
+
+    #[tokio::test]
+    async fn test_per_second_window_resets_after_one_second() {
+        let (limiter, clock) = mock_limiter(2, RATE_LIMIT_PER_MONTH);
+        assert!(limiter.check_rate_limit().await.is_ok());
+        assert!(limiter.check_rate_limit().await.is_ok());
+        assert!(limiter.check_rate_limit().await.is_err());
+
+        // Still inside the window
+        clock.advance(Duration::from_millis(1000));
+        assert!(limiter.check_rate_limit().await.is_err());
+
+        // Rejected requests do not count, so the new window admits two again
+        clock.advance(Duration::from_millis(1));
+        assert!(limiter.check_rate_limit().await.is_ok());
+        assert!(limiter.check_rate_limit().await.is_ok());
+        assert!(limiter.check_rate_limit().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_monthly_limit_error_estimates_reset() {
+        let (limiter, clock) = mock_limiter(10, 1);
+        assert!(limiter.check_rate_limit().await.is_ok());
+
+        clock.advance(25 * DAY);
+        let err = limiter.check_rate_limit().await.unwrap_err();
+        assert_eq!(err.to_string(), "monthly quota exhausted, resets in 5 days");
+
+        clock.advance(4 * DAY + Duration::from_secs(1));
+        let err = limiter.check_rate_limit().await.unwrap_err();
+        assert_eq!(err.to_string(), "monthly quota exhausted, resets in 1 day");
+
+        // Once the window has ended the monthly count starts over
+        clock.advance(DAY);
+        assert_eq!(limiter.check_rate_limit().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_initial_monthly_count_is_respected() {
+        let mut router = BraveSearchRouter::new("test_key".to_string());
+        router.rate_limiter = RateLimiter::with_limits(10, 5);
+        let router = router.with_initial_monthly_count(3).unwrap();
+
+        // Counting continues from the seeded value
+        assert_eq!(router.rate_limiter.check_rate_limit().await.unwrap(), 4);
+        assert_eq!(router.rate_limiter.check_rate_limit().await.unwrap(), 5);
+        let err = router.rate_limiter.check_rate_limit().await.unwrap_err();
+        assert!(err.to_string().starts_with("monthly quota exhausted"));
+
+        let mut router = BraveSearchRouter::new("test_key".to_string());
+        router.rate_limiter = RateLimiter::with_limits(10, 5);
+        let err = router.with_initial_monthly_count(5).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Initial monthly count 5 must be below the monthly limit of 5"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_configured_rate_limits_replace_the_defaults() {
+        let router = BraveSearchRouter::new("test_key".to_string());
+        assert_eq!(router.rate_limiter.usage().await, (1, 15000, 0));
+
+        let router = router
+            .with_rate_limit_config(RateLimiterConfig {
+                per_second: 3,
+                per_month: 4,
+            })
+            .unwrap();
+        for used in 1..=3 {
+            assert_eq!(router.rate_limiter.check_rate_limit().await.unwrap(), used);
+        }
+        let err = router.rate_limiter.check_rate_limit().await.unwrap_err();
+        assert!(err.is::<PerSecondLimitExceeded>(), "{}", err);
+        assert_eq!(router.rate_limiter.usage().await, (3, 4, 3));
+
+        // The constructor takes the same config, and rejects the same zero limits
+        let router = BraveSearchRouter::with_rate_limits(
+            "test_key".to_string(),
+            RateLimiterConfig {
+                per_second: 5,
+                per_month: 6,
+            },
+        )
+        .unwrap();
+        assert_eq!(router.rate_limiter.usage().await, (5, 6, 0));
+        let err = BraveSearchRouter::with_rate_limits(
+            "test_key".to_string(),
+            RateLimiterConfig {
+                per_second: 0,
+                per_month: 4,
+            },
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Rate limits must be at least 1, got 0 per second and 4 per month"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wait_behavior_sleeps_through_the_per_second_limit() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","web":{"results":[]}}"#,
+        )
+        .expect(2)
+        .create_async()
+        .await;
+
+        let router = router_for(&server)
+            .with_rate_limit_config(RateLimiterConfig {
+                per_second: 1,
+                per_month: 2,
+            })
+            .unwrap()
+            .with_rate_limit_behavior(RateLimitBehavior::Wait);
+        let search = |query: &str| {
+            router.brave_web_search(WebSearchParams {
+                query: query.to_string(),
+                ..Default::default()
+            })
+        };
+
+        assert!(!search("first").await.starts_with("Error"));
+        // The second request waits for the next window instead of failing
+        let started = Instant::now();
+        let result = search("second").await;
+        assert!(!result.starts_with("Error"), "{}", result);
+        assert!(started.elapsed() >= Duration::from_millis(500));
+
+        // Waiting cannot help with the monthly quota, so it fails at once
+        let started = Instant::now();
+        let result = search("third").await;
+        assert!(
+            result.starts_with("Error: monthly quota exhausted"),
+            "{}",
+            result
+        );
+        assert!(started.elapsed() < Duration::from_millis(500));
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_tool_budgets_reset_with_monthly_window() {
+        let (mut limiter, clock) = mock_limiter(10, RATE_LIMIT_PER_MONTH);
+        limiter
+            .tool_budgets
+            .insert("brave_local_search".to_string(), 1);
+        assert!(limiter
+            .check_tool_budget("brave_local_search")
+            .await
+            .is_ok());
+        assert!(limiter
+            .check_tool_budget("brave_local_search")
+            .await
+            .is_err());
+
+        clock.advance(DAY * QUOTA_WINDOW_DAYS as u32);
+        assert!(limiter
+            .check_tool_budget("brave_local_search")
+            .await
+            .is_ok());
+    }
+
+    #[test]
+    fn test_default_news_freshness_applied_only_when_omitted() {
+        let router = BraveSearchRouter::new("test_key".to_string())
+            .with_default_news_freshness(Some("pw".to_string()));
+
+        // Omitted freshness falls back to the configured default
+        assert_eq!(router.resolve_news_freshness(None), Some("pw".to_string()));
+
+        // Explicit freshness overrides the default
+        assert_eq!(
+            router.resolve_news_freshness(Some("pd".to_string())),
+            Some("pd".to_string())
+        );
+
+        // Without a configured default, omitted freshness stays unset
+        let router = BraveSearchRouter::new("test_key".to_string());
+        assert_eq!(router.resolve_news_freshness(None), None);
+    }
+
+    #[tokio::test]
+    async fn test_web_search_batch_groups_results_in_order() {
+        let mut server = mockito::Server::new_async().await;
+        for (query, title) in [("rust", "Rust Lang"), ("tokio", "Tokio Runtime")] {
+            server
+                .mock("GET", "/res/v1/web/search")
+                .match_query(mockito::Matcher::UrlEncoded("q".into(), query.into()))
+                .with_header("content-type", "application/json")
+                .with_body(format!(
+                    r#"{{"type":"search","web":{{"results":[{{"title":"{}","description":"d","url":"https://example.com/{}"}}]}}}}"#,
+                    title, query
+                ))
+                .create_async()
+                .await;
+        }
+
+        let router = router_for(&server);
+
+        let result = router
+            .brave_web_search_batch(WebSearchBatchParams {
+                queries: vec!["rust".to_string(), "tokio".to_string()],
+                count: Some(1),
+            })
+            .await;
+
+        let first = result
+            .find("## Query 1: rust")
+            .expect("first group missing");
+        let second = result
+            .find("## Query 2: tokio")
+            .expect("second group missing");
+        assert!(first < second);
+        assert!(result[first..second].contains("Title: Rust Lang"));
+        assert!(result[second..].contains("Title: Tokio Runtime"));
+    }
+
+    #[tokio::test]
+    async fn test_web_search_batch_respects_default_rate_limit() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","web":{"results":[{"title":"T","description":"d","url":"https://example.com/"}]}}"#,
+        )
+        .expect(3)
+        .create_async()
+        .await;
+
+        // Default limits: one request per second
+        let router = BraveSearchRouter::new("test_key".to_string()).with_base_url(server.url());
+        let result = router
+            .brave_web_search_batch(WebSearchBatchParams {
+                queries: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+                count: Some(1),
+            })
+            .await;
+
+        assert!(!result.contains("Error"), "{}", result);
+        assert_eq!(result.matches("Title: T").count(), 3, "{}", result);
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_web_search_batch_rejects_too_many_queries() {
+        let router = BraveSearchRouter::new("test_key".to_string());
+        let queries = (0..=MAX_BATCH_QUERIES).map(|i| i.to_string()).collect();
+
+        let result = router
+            .brave_web_search_batch(WebSearchBatchParams {
+                queries,
+                count: None,
+            })
+            .await;
+        assert!(result.starts_with("Error: at most 5 queries"));
+    }
+
+    #[tokio::test]
+    async fn test_numbered_results_account_for_offset() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::UrlEncoded("offset".into(), "1".into()))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"type":"search","web":{"results":[
+                    {"title":"First","description":"d","url":"https://example.com/1"},
+                    {"title":"Second","description":"d","url":"https://example.com/2"}
+                ]}}"#,
+            )
+            .expect(2)
+            .create_async()
+            .await;
+
+        let router = router_for(&server);
+
+        let params = WebSearchParams {
+            query: "rust".to_string(),
+            count: Some(2),
+            offset: Some(1),
+            numbered: Some(true),
+            ..Default::default()
+        };
+        let result = router.brave_web_search(params.clone()).await;
+        assert!(result.starts_with("3. Title: First"));
+        assert!(result.contains("\n\n4. Title: Second"));
+
+        // Numbering is off by default
+        let result = router
+            .brave_web_search(WebSearchParams {
+                numbered: None,
+                ..params
+            })
+            .await;
+        assert!(result.starts_with("Title: First"));
+    }
+
+    fn gzip(body: &str) -> Vec<u8> {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(body.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_gzip_response_is_decoded() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_header("content-encoding", "gzip")
+            .with_body(gzip(
+                r#"{"type":"search","web":{"results":[{"title":"Zipped","description":"d","url":"https://example.com"}]}}"#,
+            ))
+            .create_async()
+            .await;
+
+        let router = router_for(&server);
+        let params = WebSearchParams {
+            query: "rust".to_string(),
+            ..Default::default()
+        };
+
+        assert!(router
+            .perform_web_search(&params)
+            .await
+            .unwrap()
+            .contains("Title: Zipped"));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_utf8_is_replaced_instead_of_failing() {
+        let mut server = mockito::Server::new_async().await;
+        let mut body = br#"{"type":"search","web":{"results":[{"title":"Caf"#.to_vec();
+        body.push(0xE9); // Latin-1 'e' with acute, invalid on its own in UTF-8
+        body.extend_from_slice(br#"","description":"d","url":"https://example.com"}]}}"#);
+        json_mock(&mut server, "/res/v1/web/search", body)
+            .create_async()
+            .await;
+
+        let router = router_for(&server);
+        let params = WebSearchParams {
+            query: "cafe".to_string(),
+            ..Default::default()
+        };
+
+        let result = router.perform_web_search(&params).await.unwrap();
+        assert!(result.contains("Title: Caf\u{FFFD}"), "{}", result);
+    }
+
+    #[tokio::test]
+    async fn test_undecoded_content_encoding_reports_missing_feature() {
+        let mut server = mockito::Server::new_async().await;
+        // Brotli support is not compiled in, so reqwest leaves the body and header untouched
+        server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_header("content-encoding", "br")
+            .with_body(gzip("{}"))
+            .create_async()
+            .await;
+
+        let router = router_for(&server);
+        let params = WebSearchParams {
+            query: "rust".to_string(),
+            ..Default::default()
+        };
+
+        let error = router
+            .perform_web_search(&params)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("still br-encoded"));
+        assert!(error.contains("`brotli` feature"));
+    }
+
+    #[tokio::test]
+    async fn test_tool_budget_blocks_only_that_tool() {
+        let mut server = mockito::Server::new_async().await;
+        json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","web":{"results":[{"title":"Cafe","description":"d","url":"https://example.com"}]},
+                "locations":{"results":[{"id":"loc1","title":"Corner Cafe"}]}}"#,
+        )
+        .create_async()
+        .await;
+
+        let router = router_for(&server)
+            .with_tool_budget("brave_local_search", 1)
+            .unwrap();
+
+        let local = LocalSearchParams {
+            query: "coffee".to_string(),
+            ..Default::default()
+        };
+        assert!(router
+            .brave_local_search(local.clone())
+            .await
+            .contains("Name: Corner Cafe"));
+
+        // The second local search exceeds its sub-budget
+        let result = router.brave_local_search(local).await;
+        assert_eq!(
+            result,
+            "Error: Monthly budget for brave_local_search exhausted (1 searches)"
+        );
+
+        // Web search has no sub-budget and keeps working
+        let result = router
+            .brave_web_search(WebSearchParams {
+                query: "coffee".to_string(),
+                ..Default::default()
+            })
+            .await;
+        assert!(result.contains("Title: Cafe"));
+    }
+
+    #[test]
+    fn test_tool_budget_rejects_unknown_tool() {
+        let result =
+            BraveSearchRouter::new("test_key".to_string()).with_tool_budget("brave_typo", 1);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_validate_goggles_url() {
+        let url = validate_goggles_url(
+            "https://raw.githubusercontent.com/brave/goggles-quickstart/main/goggles/hacker_news.goggle",
+        )
+        .unwrap();
+        assert_eq!(url.host_str(), Some("raw.githubusercontent.com"));
+
+        let error = validate_goggles_url("not a url").unwrap_err();
+        assert!(error.contains("is not a valid URL"));
+
+        let error = validate_goggles_url("http://example.com/my.goggle").unwrap_err();
+        assert!(error.contains("must use https"));
+    }
+
+    #[tokio::test]
+    async fn test_web_search_rejects_invalid_goggles_before_sending() {
+        // No mock server: an invalid Goggle must be rejected without a request
+        let router = BraveSearchRouter::new("test_key".to_string())
+            .with_base_url("http://127.0.0.1:1".to_string());
+        let result = router
+            .brave_web_search(WebSearchParams {
+                query: "rust".to_string(),
+                goggles: Some("garbage".to_string()),
+                ..Default::default()
+            })
+            .await;
+
+        assert!(result.starts_with("Error parsing goggles: 'garbage' is not a valid URL"));
+    }
+
+    #[tokio::test]
+    async fn test_web_results_are_annotated_with_page_age() {
+        let page_age = (chrono::Utc::now() - chrono::Duration::days(95))
+            .format("%Y-%m-%dT%H:%M:%S")
+            .to_string();
+        let body = serde_json::json!({"type": "search", "web": {"results": [
+            {"title": "Old", "description": "d", "url": "https://old.example", "page_age": page_age},
+            {"title": "Undated", "description": "d", "url": "https://undated.example"},
+            {"title": "Odd", "description": "d", "url": "https://odd.example", "page_age": "recently"}
+        ]}});
+        let mut server = mockito::Server::new_async().await;
+        json_mock(&mut server, "/res/v1/web/search", body.to_string())
+            .create_async()
+            .await;
+        let router = router_for(&server);
+
+        let result = router
+            .brave_web_search(WebSearchParams {
+                query: "rust".to_string(),
+                ..Default::default()
+            })
+            .await;
+        assert!(
+            result.contains("URL: https://old.example\nUpdated: ~3 months ago"),
+            "{}",
+            result
+        );
+        // Missing and unparseable page ages get no annotation
+        assert_eq!(result.matches("Updated:").count(), 1, "{}", result);
+
+        let result = router
+            .brave_web_search(WebSearchParams {
+                query: "rust".to_string(),
+                output_format: Some(OutputFormat::Json),
+                ..Default::default()
+            })
+            .await;
+        let items: Vec<WebSearchItem> = json_results(&result);
+        assert_eq!(items[0].page_age.as_deref(), Some(page_age.as_str()));
+        assert_eq!(items[0].updated.as_deref(), Some("~3 months ago"));
+        assert_eq!(
+            (items[1].page_age.as_deref(), items[1].updated.as_deref()),
+            (None, None)
+        );
+        assert_eq!(items[2].page_age.as_deref(), Some("recently"));
+        assert_eq!(items[2].updated, None);
+    }
+
+    #[tokio::test]
+    async fn test_web_search_forwards_country_and_language() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("q".into(), "rust".into()),
+                mockito::Matcher::UrlEncoded("country".into(), "jp".into()),
+                mockito::Matcher::UrlEncoded("search_lang".into(), "ja".into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"type":"search","web":{"results":[{"title":"Rust","description":"d","url":"https://www.rust-lang.org/"}]}}"#)
+            .create_async()
+            .await;
+        let router = router_for(&server);
+
+        let result = router
+            .brave_web_search(WebSearchParams {
+                query: "rust".to_string(),
+                country: Some("JP".to_string()),
+                search_lang: Some("ja".to_string()),
+                ..Default::default()
+            })
+            .await;
+        assert!(result.contains("Title: Rust"), "{}", result);
+        mock.assert_async().await;
+
+        // Unknown codes are rejected before sending
+        let result = router
+            .brave_web_search(WebSearchParams {
+                query: "rust".to_string(),
+                country: Some("XX".to_string()),
+                ..Default::default()
+            })
+            .await;
+        assert!(
+            result.starts_with("Error parsing country code:"),
+            "{}",
+            result
+        );
+        let result = router
+            .brave_web_search(WebSearchParams {
+                query: "rust".to_string(),
+                search_lang: Some("xx".to_string()),
+                ..Default::default()
+            })
+            .await;
+        assert!(
+            result.starts_with("Error parsing language code:"),
+            "{}",
+            result
+        );
+    }
+
+    #[tokio::test]
+    async fn test_forced_safesearch_overrides_client_value() {
+        let mut server = mockito::Server::new_async().await;
+        let off = server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "safesearch".into(),
+                "off".into(),
+            ))
+            .expect(0)
+            .create_async()
+            .await;
+        let strict = server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("q".into(), "rust".into()),
+                mockito::Matcher::UrlEncoded("safesearch".into(), "strict".into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"type":"search","web":{"results":[{"title":"Rust","description":"d","url":"https://www.rust-lang.org/"}]}}"#)
+            .create_async()
+            .await;
+        let router = router_for(&server).with_forced_safesearch(Some(SafeSearchLevel::Strict));
+
+        let result = router
+            .brave_web_search(WebSearchParams {
+                query: "rust".to_string(),
+                safesearch: Some("off".to_string()),
+                ..Default::default()
+            })
+            .await;
+        assert!(result.contains("Title: Rust"), "{}", result);
+        strict.assert_async().await;
+        off.assert_async().await;
+
+        // Unknown levels are rejected before sending
+        let result = router
+            .brave_web_search(WebSearchParams {
+                query: "rust".to_string(),
+                safesearch: Some("none".to_string()),
+                ..Default::default()
+            })
+            .await;
+        assert!(
+            result.starts_with("Error parsing safesearch:"),
+            "{}",
+            result
+        );
+    }
+
+    #[tokio::test]
+    async fn test_extract_answer_puts_answer_above_results() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","web":{"results":[{"title":"Mount Everest","description":"Plan your trek. Mount <strong>Everest</strong> is 8,849 metres tall.","url":"https://example.com/everest"}]}}"#,
+        )
+        .create_async()
+        .await;
+        let router = router_for(&server);
+
+        let result = router
+            .brave_web_search(WebSearchParams {
+                query: "how tall is everest".to_string(),
+                extract_answer: Some(true),
+                ..Default::default()
+            })
+            .await;
+        assert!(
+            result
+                .starts_with("Answer: Mount Everest is 8,849 metres tall.\n\nTitle: Mount Everest"),
+            "{}",
+            result
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stable_sort_makes_output_deterministic() {
+        // The same results come back in a different order on the second request
+        let mut server = mockito::Server::new_async().await;
+        let mut mocks = Vec::new();
+        for urls in [["b", "c", "a"], ["c", "a", "b"]] {
+            let results = urls
+                .iter()
+                .map(|u| {
+                    format!(
+                        r#"{{"title":"{u}","description":"d","url":"https://example.com/{u}"}}"#
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+            let mock = json_mock(
+                &mut server,
+                "/res/v1/web/search",
+                format!(r#"{{"type":"search","web":{{"results":[{}]}}}}"#, results),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+            mocks.push(mock);
+        }
+
+        let router = router_for(&server);
 
-2. News Search - For current events and breaking news:
-   ```
-   brave_news_search(
-     query: "artificial intelligence developments",
-     count: 10,            // Optional: Number of results (default: 20, max: 50)
-     offset: 0,            // Optional: Pagination offset (default: 0, max: 9)
-     country: "US",        // Optional: Country code (default: US)
-     search_lang: "en",    // Optional: Language code (default: en)
-     freshness: "d"        // Optional: Timeframe - d=day, w=week, m=month
-   )
-   ```
+        let params = WebSearchParams {
+            query: "rust".to_string(),
+            stable_sort: Some(true),
+            ..Default::default()
+        };
+        let first = router.brave_web_search(params.clone()).await;
+        let second = router.brave_web_search(params).await;
 
-3. Local Search - For businesses and physical locations:
-   ```
-   brave_local_search(
-     query: "pizza restaurants near Times Square",
-     count: 5  // Optional: Number of results (default: 5, max: 20)
-   )
-   ```
+        for mock in mocks {
+            mock.assert_async().await;
+        }
+        assert_eq!(first, second);
+        let titles = first
+            .lines()
+            .filter_map(|line| line.strip_prefix("Title: "))
+            .collect::<Vec<_>>();
+        assert_eq!(titles, ["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn test_prefer_recent_orders_web_results_by_age() {
+        let mut server = mockito::Server::new_async().await;
+        json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","web":{"results":[
+                {"title":"Undated","description":"d","url":"https://a.example/"},
+                {"title":"Last year","description":"d","url":"https://b.example/","page_age":"2023-03-01T00:00:00"},
+                {"title":"Hours old","description":"d","url":"https://c.example/","age":"3 hours ago"},
+                {"title":"Also last year","description":"d","url":"https://d.example/","page_age":"2023-03-01T00:00:00"},
+                {"title":"Days old","description":"d","url":"https://e.example/","age":"2 days ago"}
+            ]}}"#,
+        )
+        .create_async()
+        .await;
+
+        let router = router_for(&server);
+
+        let titles = |output: &str| {
+            output
+                .lines()
+                .filter_map(|line| line.strip_prefix("Title: "))
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        let params = WebSearchParams {
+            query: "rust".to_string(),
+            ..Default::default()
+        };
+
+        let relevance = router.brave_web_search(params.clone()).await;
+        assert_eq!(
+            titles(&relevance),
+            [
+                "Undated",
+                "Last year",
+                "Hours old",
+                "Also last year",
+                "Days old"
+            ]
+        );
+
+        let recent = router
+            .brave_web_search(WebSearchParams {
+                prefer_recent: Some(true),
+                ..params
+            })
+            .await;
+        // Equal ages keep relevance order; undated results go last
+        assert_eq!(
+            titles(&recent),
+            [
+                "Hours old",
+                "Days old",
+                "Last year",
+                "Also last year",
+                "Undated"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prefer_recent_treats_out_of_range_ages_as_undated() {
+        let mut server = mockito::Server::new_async().await;
+        json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","web":{"results":[
+                {"title":"Ancient","description":"d","url":"https://a.example/","age":"99999999999 days ago"},
+                {"title":"Huge","description":"d","url":"https://b.example/","age":"9223372036854775807 years ago"},
+                {"title":"Negative","description":"d","url":"https://c.example/","age":"-3 days ago"},
+                {"title":"Days old","description":"d","url":"https://d.example/","age":"2 days ago"}
+            ]}}"#,
+        )
+        .create_async()
+        .await;
+
+        let router = router_for(&server);
+
+        let result = router
+            .brave_web_search(WebSearchParams {
+                query: "rust".to_string(),
+                prefer_recent: Some(true),
+                ..Default::default()
+            })
+            .await;
+        let titles = result
+            .lines()
+            .filter_map(|line| line.strip_prefix("Title: "))
+            .collect::<Vec<_>>();
+        assert_eq!(titles, ["Days old", "Ancient", "Huge", "Negative"]);
+    }
+
+    #[tokio::test]
+    async fn test_extra_params_reach_request_url() {
+        let mut server = mockito::Server::new_async().await;
+        let web = server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("q".into(), "rust".into()),
+                mockito::Matcher::UrlEncoded("text_decorations".into(), "0".into()),
+                mockito::Matcher::UrlEncoded("units".into(), "metric & more".into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"type":"search","web":{"results":[]}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let news = server
+            .mock("GET", "/res/v1/news/search")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "ui_lang".into(),
+                "en-US".into(),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"type":"news","results":[]}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let router = router_for(&server);
+
+        let extra = |pairs: &[(&str, &str)]| {
+            Some(
+                pairs
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect::<HashMap<_, _>>(),
+            )
+        };
+        router
+            .brave_web_search(WebSearchParams {
+                query: "rust".to_string(),
+                extra_params: extra(&[("text_decorations", "0"), ("units", "metric & more")]),
+                ..Default::default()
+            })
+            .await;
+        router
+            .brave_news_search(NewsSearchParams {
+                query: "rust".to_string(),
+                extra_params: extra(&[("ui_lang", "en-US")]),
+                ..Default::default()
+            })
+            .await;
+        web.assert_async().await;
+        news.assert_async().await;
+
+        // Rejected before any request is made
+        for (pairs, message) in [
+            (
+                vec![("count", "50")],
+                "Error parsing extra_params: 'count' cannot be overridden",
+            ),
+            (
+                vec![("SafeSearch", "off")],
+                "Error parsing extra_params: 'SafeSearch' cannot be overridden",
+            ),
+            (
+                vec![("api_key", "x")],
+                "Error parsing extra_params: 'api_key' looks like a credential",
+            ),
+            (
+                vec![("a&b", "1")],
+                "Error parsing extra_params: 'a&b' is not a valid parameter name",
+            ),
+            (
+                vec![("units", " ")],
+                "Error parsing extra_params: Value for 'units' must be non-empty",
+            ),
+        ] {
+            let result = router
+                .brave_web_search(WebSearchParams {
+                    query: "rust".to_string(),
+                    extra_params: extra(&pairs),
+                    ..Default::default()
+                })
+                .await;
+            assert!(result.starts_with(message), "{}", result);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_description_repeating_title_is_trimmed() {
+        let mut server = mockito::Server::new_async().await;
+        json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","web":{"results":[
+                {"title":"Tokio","description":"Tokio - An asynchronous Rust runtime","url":"https://tokio.rs/"},
+                {"title":"Rust","description":"Rust is a language","url":"https://www.rust-lang.org/"}
+            ]}}"#,
+        )
+        .create_async()
+        .await;
+
+        let router = router_for(&server);
+
+        let result = router
+            .brave_web_search(WebSearchParams {
+                query: "rust runtime".to_string(),
+                ..Default::default()
+            })
+            .await;
+        assert!(
+            result.contains("Title: Tokio\nDescription: An asynchronous Rust runtime\n"),
+            "{}",
+            result
+        );
+        assert!(
+            result.contains("Description: Rust is a language\n"),
+            "{}",
+            result
+        );
+    }
+
+    #[tokio::test]
+    async fn test_web_source_from_profile() {
+        let mut server = mockito::Server::new_async().await;
+        json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","web":{"results":[
+                {"title":"Rust","description":"d","url":"https://en.wikipedia.org/wiki/Rust",
+                 "profile":{"name":"Wikipedia","url":"https://en.wikipedia.org/wiki/Rust","long_name":"en.wikipedia.org"}},
+                {"title":"Blog","description":"d","url":"https://blog.example.org/",
+                 "profile":{"name":"","long_name":"blog.example.org"}},
+                {"title":"Bare","description":"d","url":"https://bare.example.com/"}
+            ]}}"#,
+        )
+        .create_async()
+        .await;
+
+        let router = router_for(&server);
+        let params = WebSearchParams {
+            query: "rust".to_string(),
+            ..Default::default()
+        };
+
+        let result = router.brave_web_search(params.clone()).await;
+        assert!(!result.contains("Source:"), "{}", result);
+
+        let result = router
+            .brave_web_search(WebSearchParams {
+                include_source: Some(true),
+                ..params
+            })
+            .await;
+        assert!(
+            result.contains("URL: https://en.wikipedia.org/wiki/Rust\nSource: Wikipedia\n"),
+            "{}",
+            result
+        );
+        // An empty short name falls back to the long name; no profile, no source
+        assert!(result.contains("URL: https://blog.example.org/\nSource: blog.example.org\n"));
+        assert!(
+            result.ends_with("URL: https://bare.example.com/"),
+            "{}",
+            result
+        );
+    }
+
+    #[tokio::test]
+    async fn test_web_stream_yields_the_same_items_as_the_tool() {
+        use futures::StreamExt;
+
+        let mut server = mockito::Server::new_async().await;
+        json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","web":{"results":[
+                {"title":"Rust","description":"A language","url":"https://www.rust-lang.org","profile":{"name":"Rust"}},
+                {"title":"Book","description":"The Rust book","url":"https://doc.rust-lang.org/book"}
+            ]}}"#,
+        )
+        .create_async()
+        .await;
+
+        let router = router_for(&server);
+
+        let params = WebSearchParams {
+            query: "rust".to_string(),
+            include_source: Some(true),
+            stable_sort: Some(true),
+            ..Default::default()
+        };
+        let streamed = router
+            .search_web_stream(params.clone())
+            .await
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await;
+        let batch = router
+            .brave_web_search(WebSearchParams {
+                output_format: Some(OutputFormat::Json),
+                ..params
+            })
+            .await;
+        let batch: Vec<WebSearchItem> = json_results(&batch);
+
+        assert_eq!(streamed.len(), 2);
+        assert_eq!(streamed, batch);
+        assert_eq!(streamed[0].url, "https://doc.rust-lang.org/book");
+        assert_eq!(streamed[1].source.as_deref(), Some("Rust"));
+    }
+
+    #[tokio::test]
+    async fn test_news_source_line() {
+        let mut server = mockito::Server::new_async().await;
+        json_mock(
+            &mut server,
+            "/res/v1/news/search",
+            r#"{"type":"news","results":[
+                {"title":"With meta","description":"d","url":"https://cdn.example.net/a",
+                 "meta_url":{"scheme":"https","hostname":"www.example-news.com"}},
+                {"title":"Without meta","description":"d","url":"https://news.example.org/b"}
+            ]}"#,
+        )
+        .expect(2)
+        .create_async()
+        .await;
+
+        let router = router_for(&server);
+
+        let params = NewsSearchParams {
+            query: "rust".to_string(),
+            ..Default::default()
+        };
+        let result = router.brave_news_search(params.clone()).await;
+        let articles = result.split("\n\n").collect::<Vec<_>>();
+        assert!(
+            articles[0].contains("URL: https://cdn.example.net/a\nSource: www.example-news.com\n")
+        );
+        assert!(articles[1].contains("\nSource: news.example.org\n"));
+
+        let result = router
+            .brave_news_search(NewsSearchParams {
+                include_source: Some(false),
+                ..params
+            })
+            .await;
+        assert!(!result.contains("Source:"));
+    }
+
+    #[tokio::test]
+    async fn test_transient_errors_are_retried() {
+        let mut server = mockito::Server::new_async().await;
+        let unavailable = server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::Any)
+            .with_status(503)
+            .expect(2)
+            .create_async()
+            .await;
+        let ok = json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","web":{"results":[{"title":"Recovered","description":"d","url":"https://example.com"}]}}"#,
+        )
+        .expect(1)
+        .create_async()
+        .await;
+
+        let router = router_for(&server).with_retries(2, Duration::from_millis(1));
+
+        let result = router
+            .brave_web_search(WebSearchParams {
+                query: "rust".to_string(),
+                ..Default::default()
+            })
+            .await;
+        assert!(result.starts_with("Title: Recovered"), "{}", result);
+        unavailable.assert_async().await;
+        ok.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_empty_results_are_retried_once_when_requested() {
+        let mut server = mockito::Server::new_async().await;
+        let empty = json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","web":{"results":[]}}"#,
+        )
+        .expect(1)
+        .create_async()
+        .await;
+        let populated = json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","web":{"results":[{"title":"Found","description":"d","url":"https://example.com"}]}}"#,
+        )
+        .expect(1)
+        .create_async()
+        .await;
+
+        let router = router_for(&server).with_empty_retry_delay(Duration::from_millis(1));
+
+        let result = router
+            .brave_web_search(WebSearchParams {
+                query: "rust".to_string(),
+                retry_on_empty: Some(true),
+                ..Default::default()
+            })
+            .await;
+        assert!(result.starts_with("Title: Found"), "{}", result);
+        empty.assert_async().await;
+        populated.assert_async().await;
+        assert_eq!(router.rate_limiter.usage().await.2, 2);
+    }
+
+    #[tokio::test]
+    async fn test_empty_results_are_final_without_retry_on_empty() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = json_mock(
+            &mut server,
+            "/res/v1/news/search",
+            r#"{"type":"news","results":[]}"#,
+        )
+        .expect(1)
+        .create_async()
+        .await;
+
+        let router = router_for(&server).with_empty_retry_delay(Duration::from_millis(1));
+
+        router
+            .brave_news_search(NewsSearchParams {
+                query: "rust".to_string(),
+                ..Default::default()
+            })
+            .await;
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_non_idempotent_requests_are_not_retried() {
+        let mut server = mockito::Server::new_async().await;
+        let unavailable = server
+            .mock("POST", "/res/v1/summarizer/search")
+            .with_status(503)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let router = router_for(&server).with_retries(2, Duration::from_millis(1));
+
+        let url =
+            reqwest::Url::parse(&format!("{}/res/v1/summarizer/search", server.url())).unwrap();
+        let err = router
+            .send_api_request(reqwest::Method::POST, url)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().starts_with("Brave API error: 503"),
+            "{}",
+            err
+        );
+        unavailable.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_response_has_distinct_error() {
+        let mut server = mockito::Server::new_async().await;
+        let _maintenance = server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::Any)
+            .with_status(503)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"type":"ErrorResponse","error":{"status":503,"code":"SERVICE_UNAVAILABLE","detail":"The API is down for scheduled maintenance."}}"#)
+            .create_async()
+            .await;
+        let router = router_for(&server);
+
+        let result = router
+            .brave_web_search(WebSearchParams {
+                query: "rust".to_string(),
+                ..Default::default()
+            })
+            .await;
+        assert_eq!(result, "Error: Brave API is under maintenance, retry later");
+    }
+
+    #[tokio::test]
+    async fn test_retries_stop_after_max_retries() {
+        let mut server = mockito::Server::new_async().await;
+        let unavailable = server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::Any)
+            .with_status(503)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let router = router_for(&server).with_retries(1, Duration::from_millis(1));
+
+        let result = router
+            .brave_web_search(WebSearchParams {
+                query: "rust".to_string(),
+                ..Default::default()
+            })
+            .await;
+        assert!(
+            result.starts_with("Error: Brave API error: 503"),
+            "{}",
+            result
+        );
+        unavailable.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_each_retry_takes_a_rate_limit_slot() {
+        let mut server = mockito::Server::new_async().await;
+        let unavailable = server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::Any)
+            .with_status(503)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let mut router = router_for(&server).with_retries(1, Duration::from_millis(1));
+        router.rate_limiter = RateLimiter::with_limits(1, RATE_LIMIT_PER_MONTH);
+
+        let result = router
+            .brave_web_search(WebSearchParams {
+                query: "rust".to_string(),
+                ..Default::default()
+            })
+            .await;
+        // The retry waited for the next per-second window instead of failing on the limit
+        assert!(
+            result.starts_with("Error: Brave API error: 503"),
+            "{}",
+            result
+        );
+        unavailable.assert_async().await;
+        // Both attempts count against the monthly quota
+        assert_eq!(router.rate_limiter.usage().await.2, 2);
+    }
+
+    // A payload carrying both the web/locations sections and a top-level news `results` array
+    const MIXED_RESPONSE: &str = r#"{
+        "type": "search",
+        "web": {"results": [
+            {"title": "Web result", "description": "d", "url": "https://example.com/web"}
+        ]},
+        "locations": {"results": [
+            {"id": "loc-1", "title": "Local result"}
+        ]},
+        "results": [
+            {"title": "News result", "description": "d", "url": "https://example.com/news"}
+        ]
+    }"#;
+
+    async fn mixed_response_router(path: &str) -> (mockito::ServerGuard, BraveSearchRouter) {
+        let mut server = mockito::Server::new_async().await;
+        json_mock(&mut server, path, MIXED_RESPONSE)
+            .create_async()
+            .await;
+        let router = router_for(&server);
+        (server, router)
+    }
+
+    #[tokio::test]
+    async fn test_each_endpoint_reads_its_own_results_field() {
+        let (_server, router) = mixed_response_router("/res/v1/web/search").await;
+        let result = router
+            .brave_web_search(WebSearchParams {
+                query: "rust".to_string(),
+                ..Default::default()
+            })
+            .await;
+        assert_eq!(
+            result,
+            "Title: Web result\nDescription: d\nURL: https://example.com/web"
+        );
+
+        let result = router
+            .brave_local_search(LocalSearchParams {
+                query: "rust".to_string(),
+                ..Default::default()
+            })
+            .await;
+        assert_eq!(result, "Name: Local result\nID: loc-1");
+
+        let (_server, router) = mixed_response_router("/res/v1/news/search").await;
+        let result = router
+            .brave_news_search(NewsSearchParams {
+                query: "rust".to_string(),
+                ..Default::default()
+            })
+            .await;
+        assert!(result.starts_with("Title: News result\n"), "{}", result);
+        assert!(!result.contains("Web result"));
+    }
+
+    #[tokio::test]
+    async fn test_web_search_ignores_malformed_top_level_results() {
+        // A top-level `results` that does not match the news shape must not break web parsing
+        let mut server = mockito::Server::new_async().await;
+        json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","results":[{"unexpected":true}],
+                "web":{"results":[{"title":"Web result","description":"d","url":"https://example.com"}]}}"#,
+        )
+        .create_async()
+        .await;
+        let router = router_for(&server);
+
+        let result = router
+            .brave_web_search(WebSearchParams {
+                query: "rust".to_string(),
+                ..Default::default()
+            })
+            .await;
+        assert!(result.starts_with("Title: Web result"), "{}", result);
+    }
+
+    #[tokio::test]
+    async fn test_deep_pagination_note() {
+        let mut server = mockito::Server::new_async().await;
+        json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","web":{"results":[]}}"#,
+        )
+        .create_async()
+        .await;
+
+        let mut router = router_for(&server);
+
+        let deep = WebSearchParams {
+            query: "rust".to_string(),
+            count: Some(20),
+            offset: Some(9),
+            ..Default::default()
+        };
+        let result = router.brave_web_search(deep.clone()).await;
+        assert!(
+            result.starts_with("Note: this page starts 180 results deep (offset 9 x count 20)"),
+            "{}",
+            result
+        );
+
+        let shallow = WebSearchParams {
+            count: Some(10),
+            offset: Some(1),
+            ..deep.clone()
+        };
+        assert!(!router.brave_web_search(shallow).await.contains("Note:"));
+
+        // The note can be turned off
+        let router = router.with_deep_pagination_threshold(None);
+        assert!(!router.brave_web_search(deep).await.contains("Note:"));
+    }
+
+    #[tokio::test]
+    async fn test_cache_is_partitioned_by_api_key() {
+        let mut server = mockito::Server::new_async().await;
+        let mut mocks = Vec::new();
+        for key in ["key-a", "key-b"] {
+            let mock = server
+                .mock("GET", "/res/v1/web/search")
+                .match_query(mockito::Matcher::Any)
+                .match_header("X-Subscription-Token", key)
+                .with_header("content-type", "application/json")
+                .with_body(format!(
+                    r#"{{"type":"search","web":{{"results":[{{"title":"{}","description":"d","url":"https://example.com"}}]}}}}"#,
+                    key
+                ))
+                .expect(1)
+                .create_async()
+                .await;
+            mocks.push(mock);
+        }
+
+        let mut tenant_a = BraveSearchRouter::new("key-a".to_string())
+            .with_base_url(server.url())
+            .with_cache_ttl(Some(Duration::from_secs(60)));
+        tenant_a.rate_limiter = RateLimiter::with_limits(10, RATE_LIMIT_PER_MONTH);
+        // Clones share the cache, so only the key partitions it
+        let mut tenant_b = tenant_a.clone();
+        tenant_b.api_key = "key-b".to_string();
+
+        let params = WebSearchParams {
+            query: "rust".to_string(),
+            ..Default::default()
+        };
+        let first = tenant_a.brave_web_search(params.clone()).await;
+        let cached = tenant_a.brave_web_search(params.clone()).await;
+        let other = tenant_b.brave_web_search(params).await;
+
+        assert!(first.starts_with("Title: key-a"));
+        assert_eq!(first, cached);
+        assert!(other.starts_with("Title: key-b"), "{}", other);
+        for mock in mocks {
+            mock.assert_async().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_no_results_query_served_from_negative_cache() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","web":{"results":[]}}"#,
+        )
+        .expect(1)
+        .create_async()
+        .await;
+
+        let router = router_for(&server).with_negative_cache_ttl(Some(Duration::from_secs(60)));
+
+        let params = WebSearchParams {
+            query: "qwzxv flurble".to_string(),
+            ..Default::default()
+        };
+        let first = router.brave_web_search(params.clone()).await;
+        let second = router.brave_web_search(params).await;
+        assert_eq!(first, second);
+        mock.assert_async().await;
+        assert_eq!(router.rate_limiter.usage().await.2, 1);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_identical_requests_share_one_call() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_chunked_body(|w| {
+                std::thread::sleep(Duration::from_millis(200));
+                w.write_all(br#"{"type":"search","web":{"results":[{"title":"Rust","description":"d","url":"https://www.rust-lang.org/"}]}}"#)
+            })
+            .expect(1)
+            .create_async()
+            .await;
+
+        let router = router_for(&server);
+        let params = WebSearchParams {
+            query: "rust".to_string(),
+            ..Default::default()
+        };
+
+        let results =
+            futures::future::join_all((0..10).map(|_| router.brave_web_search(params.clone())))
+                .await;
+        assert!(results[0].contains("Title: Rust"), "{}", results[0]);
+        assert!(results.iter().all(|result| *result == results[0]));
+        mock.assert_async().await;
+        assert_eq!(router.rate_limiter.usage().await.2, 1);
+    }
+
+    #[tokio::test]
+    async fn test_cache_clear_makes_next_query_hit_the_network() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","web":{"results":[{"title":"Rust","description":"d","url":"https://www.rust-lang.org/"}]}}"#,
+        )
+        .expect(2)
+        .create_async()
+        .await;
+
+        let mut router = router_for(&server).with_cache_ttl(Some(Duration::from_secs(60)));
+        let params = WebSearchParams {
+            query: "rust".to_string(),
+            ..Default::default()
+        };
+
+        router.brave_web_search(params.clone()).await;
+        router.brave_web_search(params.clone()).await;
+        // Without --admin-tools the cache is left alone
+        assert!(router.brave_cache_clear().await.starts_with("Error: "));
+
+        let router = router.with_admin_tools(true);
+        assert_eq!(
+            router.brave_cache_clear().await,
+            "Cleared 1 cached response"
+        );
+        router.brave_web_search(params).await;
+        mock.assert_async().await;
+    }
+
+    // Records every call and stores entries without expiring them
+    #[derive(Default)]
+    struct RecordingCache {
+        entries: Mutex<HashMap<String, String>>,
+        gets: Mutex<Vec<String>>,
+        puts: Mutex<Vec<(String, Duration)>>,
+    }
+
+    impl SearchCache for RecordingCache {
+        fn get<'a>(&'a self, key: &'a str) -> futures::future::BoxFuture<'a, Option<String>> {
+            Box::pin(async move {
+                self.gets.lock().await.push(key.to_string());
+                self.entries.lock().await.get(key).cloned()
+            })
+        }
+
+        fn put<'a>(
+            &'a self,
+            key: &'a str,
+            value: String,
+            ttl: Duration,
+        ) -> futures::future::BoxFuture<'a, ()> {
+            Box::pin(async move {
+                self.puts.lock().await.push((key.to_string(), ttl));
+                self.entries.lock().await.insert(key.to_string(), value);
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_cache_backend_stores_responses() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","web":{"results":[{"title":"Rust","description":"d","url":"https://www.rust-lang.org/"}]}}"#,
+        )
+        .expect(1)
+        .create_async()
+        .await;
+
+        let backend = Arc::new(RecordingCache::default());
+        let mut router = router_for(&server)
+            .with_cache_backend(backend.clone())
+            .with_cache_ttl(Some(Duration::from_secs(60)));
+        let params = WebSearchParams {
+            query: "rust".to_string(),
+            ..Default::default()
+        };
+
+        let first = router.brave_web_search(params.clone()).await;
+        let second = router.brave_web_search(params).await;
+        assert_eq!(first, second);
+        mock.assert_async().await;
+
+        let puts = backend.puts.lock().await;
+        assert_eq!(puts.len(), 1);
+        let (key, ttl) = &puts[0];
+        assert!(key.contains("/res/v1/web/search?q=rust"));
+        assert!(!key.contains("test_key"));
+        assert_eq!(*ttl, Duration::from_secs(60));
+        assert_eq!(backend.gets.lock().await.len(), 2);
+
+        // The backend does not support clearing, which the admin tool reports
+        let router = router.with_admin_tools(true);
+        assert!(router.brave_cache_clear().await.starts_with("Error: "));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_request_serves_stale_cache() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","web":{"results":[{"title":"Rust","description":"d","url":"https://www.rust-lang.org/"}]}}"#,
+        )
+        .expect(1)
+        .create_async()
+        .await;
+
+        // The limiter's clock stands still, so the second request is always over the limit
+        let (limiter, _clock) = mock_limiter(1, RATE_LIMIT_PER_MONTH);
+        let mut router = router_for(&server).with_cache_ttl(Some(Duration::from_millis(20)));
+        router.rate_limiter = limiter;
+        let params = WebSearchParams {
+            query: "rust".to_string(),
+            ..Default::default()
+        };
+
+        let fresh = router.brave_web_search(params.clone()).await;
+        assert!(fresh.contains("https://www.rust-lang.org/"), "{}", fresh);
+        tokio::time::sleep(Duration::from_millis(30)).await;
+
+        assert_eq!(
+            router.brave_web_search(params.clone()).await,
+            "Error: Rate limit exceeded"
+        );
+        let router = router.with_serve_stale_on_limit(true);
+        assert_eq!(router.brave_web_search(params.clone()).await, fresh);
+
+        // Queries with nothing cached still fail
+        let other = router
+            .brave_web_search(WebSearchParams {
+                query: "go".to_string(),
+                ..params
+            })
+            .await;
+        assert_eq!(other, "Error: Rate limit exceeded");
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_corrected_query_replaces_misspelled_results() {
+        let mut server = mockito::Server::new_async().await;
+        let misspelled = server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::UrlEncoded("q".into(), "rsut".into()))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"type":"search","query":{"original":"rsut","altered":"rust"},"web":{"results":[
+                    {"title":"Rsut Ltd","description":"d","url":"https://rsut.example.com/"}
+                ]}}"#,
+            )
+            .expect(2)
+            .create_async()
+            .await;
+        let corrected = server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("q".into(), "rust".into()),
+                mockito::Matcher::UrlEncoded("count".into(), "10".into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"type":"search","query":{"original":"rust"},"web":{"results":[
+                    {"title":"Rust","description":"d","url":"https://www.rust-lang.org/"}
+                ]}}"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let router = router_for(&server);
+        let params = WebSearchParams {
+            query: "rsut".to_string(),
+            ..Default::default()
+        };
+
+        // Without the flag the results are for the query as typed
+        let result = router.brave_web_search(params.clone()).await;
+        assert!(result.starts_with("Title: Rsut Ltd"), "{}", result);
+        assert!(!result.contains("Showing results for"));
+
+        let result = router
+            .brave_web_search(WebSearchParams {
+                use_corrected_query: Some(true),
+                ..params
+            })
+            .await;
+        assert_eq!(
+            result,
+            "Title: Rust\nDescription: d\nURL: https://www.rust-lang.org/\n\n\
+             Showing results for \"rust\" instead of \"rsut\" (Brave's spelling correction)"
+        );
+        misspelled.assert_async().await;
+        corrected.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_mixed_order_follows_brave_layout() {
+        let mut server = mockito::Server::new_async().await;
+        json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search",
+                "web":{"results":[
+                    {"title":"Web A","description":"a","url":"https://a.example.com/"},
+                    {"title":"Web B","description":"b","url":"https://b.example.com/"}
+                ]},
+                "news":{"results":[
+                    {"title":"News A","description":"na","url":"https://news.example.com/a"},
+                    {"title":"News B","url":"https://news.example.com/b"}
+                ]},
+                "videos":{"results":[
+                    {"title":"Video A","description":"va","url":"https://video.example.com/a"}
+                ]},
+                "locations":{"results":[{"id":"loc1","title":"Cafe"}]},
+                "mixed":{"type":"mixed","main":[
+                    {"type":"web","index":1,"all":false},
+                    {"type":"news","all":true},
+                    {"type":"locations","all":true},
+                    {"type":"web","index":0,"all":false},
+                    {"type":"videos","index":0,"all":false},
+                    {"type":"web","index":7,"all":false}
+                ]}}"#,
+        )
+        .create_async()
+        .await;
+
+        let router = router_for(&server).with_output_format(OutputFormat::Compact);
+        let params = WebSearchParams {
+            query: "example".to_string(),
+            mixed_order: Some(true),
+            ..Default::default()
+        };
+
+        let result = router.brave_web_search(params.clone()).await;
+        assert_eq!(
+            result,
+            "Web B | b.example.com | https://b.example.com/\n\
+             [news] News A | news.example.com | https://news.example.com/a\n\
+             [news] News B | news.example.com | https://news.example.com/b\n\
+             Web A | a.example.com | https://a.example.com/\n\
+             [video] Video A | video.example.com | https://video.example.com/a"
+        );
+
+        let result = router
+            .brave_web_search(WebSearchParams {
+                output_format: Some(OutputFormat::Json),
+                ..params.clone()
+            })
+            .await;
+        let items: serde_json::Value = json_results(&result);
+        assert!(items[0].get("kind").is_none());
+        assert_eq!(items[1]["kind"], "news");
+        assert_eq!(items[1]["title"], "News A");
+
+        // The layout is cut to count, and the ordering options still apply to what is left
+        let result = router
+            .brave_web_search(WebSearchParams {
+                count: Some(4),
+                stable_sort: Some(true),
+                ..params.clone()
+            })
+            .await;
+        assert_eq!(
+            result,
+            "Web A | a.example.com | https://a.example.com/\n\
+             Web B | b.example.com | https://b.example.com/\n\
+             [news] News A | news.example.com | https://news.example.com/a\n\
+             [news] News B | news.example.com | https://news.example.com/b"
+        );
+        let result = router
+            .brave_web_search(WebSearchParams {
+                count: Some(1),
+                ..params.clone()
+            })
+            .await;
+        assert_eq!(result, "Web B | b.example.com | https://b.example.com/");
 
-All searches respect rate limits and provide formatted, readable results. Choose the appropriate tool based on the type of information needed."#.to_string()),
-        }
+        // Without the flag only web results are shown, in Brave's web order
+        let result = router
+            .brave_web_search(WebSearchParams {
+                mixed_order: None,
+                ..params
+            })
+            .await;
+        assert_eq!(
+            result,
+            "Web A | a.example.com | https://a.example.com/\nWeb B | b.example.com | https://b.example.com/"
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[tokio::test]
+    async fn test_output_format_selects_formatter() {
+        let mut server = mockito::Server::new_async().await;
+        json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","web":{"results":[
+                {"title":"Rust","description":"d","url":"https://www.rust-lang.org/"}
+            ]}}"#,
+        )
+        .create_async()
+        .await;
+
+        let router = router_for(&server)
+            .with_output_format(OutputFormat::Markdown)
+            .with_compact_title_width(3);
+
+        let params = WebSearchParams {
+            query: "rust".to_string(),
+            ..Default::default()
+        };
+        // The router default applies when the call does not choose a format
+        let result = router.brave_web_search(params.clone()).await;
+        assert_eq!(result, "**[Rust](https://www.rust-lang.org/)**\nd");
+
+        let result = router
+            .brave_web_search(WebSearchParams {
+                output_format: Some(OutputFormat::Json),
+                ..params.clone()
+            })
+            .await;
+        let items: serde_json::Value = json_results(&result);
+        assert_eq!(items[0]["title"], "Rust");
+        assert!(!result.contains('\n'));
+
+        // `pretty` spreads the same JSON over indented lines
+        let pretty = router
+            .brave_web_search(WebSearchParams {
+                output_format: Some(OutputFormat::Json),
+                pretty: Some(true),
+                ..params.clone()
+            })
+            .await;
+        assert!(pretty.contains("\n    \"title\": \"Rust\""));
+        assert_eq!(json_results::<serde_json::Value>(&pretty), items);
+
+        // The compact format uses the router's title width
+        let result = router
+            .brave_web_search(WebSearchParams {
+                output_format: Some(OutputFormat::Compact),
+                ..params
+            })
+            .await;
+        assert_eq!(
+            result,
+            "Ru\u{2026} | www.rust-lang.org | https://www.rust-lang.org/"
+        );
+    }
 
     #[tokio::test]
-    async fn test_brave_search_apis() {
-        // Skip the test if API key is not set in environment
-        let api_key = std::env::var("BRAVE_API_KEY").unwrap_or_else(|_| {
-            eprintln!("BRAVE_API_KEY environment variable not set, skipping test");
-            String::from("dummy_key")
-        });
+    async fn test_news_thumbnail_size_prefers_original_when_available() {
+        let mut server = mockito::Server::new_async().await;
+        json_mock(
+            &mut server,
+            "/res/v1/news/search",
+            r#"{"type":"news","results":[
+                {"title":"Both","description":"d","url":"https://news.example.com/a",
+                 "thumbnail":{"src":"https://img.example.com/a-small.png","original":"https://img.example.com/a-full.png"}},
+                {"title":"Small only","description":"d","url":"https://news.example.com/b",
+                 "thumbnail":{"src":"https://img.example.com/b-small.png"}}
+            ]}"#,
+        )
+        .create_async()
+        .await;
+        let router = router_for(&server);
+
+        let thumbnails = |result: String| {
+            json_results::<Vec<NewsItem>>(&result)
+                .into_iter()
+                .map(|item| item.thumbnail)
+                .collect::<Vec<_>>()
+        };
+        let search = |size: Option<&str>| NewsSearchParams {
+            query: "rust".to_string(),
+            thumbnail_size: size.map(str::to_string),
+            output_format: Some(OutputFormat::Json),
+            ..Default::default()
+        };
 
-        // Only run this test if we have a real API key
-        if api_key == "dummy_key" {
-            // Skip the test
-            return;
-        }
+        let original = router.brave_news_search(search(Some("original"))).await;
+        assert_eq!(
+            thumbnails(original),
+            vec![
+                Some("https://img.example.com/a-full.png".to_string()),
+                Some("https://img.example.com/b-small.png".to_string()),
+            ]
+        );
+        let small = router.brave_news_search(search(None)).await;
+        assert_eq!(
+            thumbnails(small),
+            vec![
+                Some("https://img.example.com/a-small.png".to_string()),
+                Some("https://img.example.com/b-small.png".to_string()),
+            ]
+        );
 
-        // Create a BraveSearchRouter with the API key
-        let router = BraveSearchRouter::new(api_key);
+        let result = router.brave_news_search(search(Some("huge"))).await;
+        assert_eq!(
+            result,
+            "Error parsing thumbnail_size: Unknown thumbnail size: huge (expected small or original)"
+        );
+    }
 
-        // Test 1: Web Search
-        let web_result = router
-            .brave_web_search("Rust programming language".to_string(), Some(3), None)
+    #[tokio::test]
+    async fn test_news_json_round_trips_into_news_items() {
+        let mut server = mockito::Server::new_async().await;
+        json_mock(
+            &mut server,
+            "/res/v1/news/search",
+            r#"{"type":"news","results":[
+                {"title":"Rust 2.0","description":"Released","url":"https://news.example.com/rust",
+                 "age":"2 hours ago","page_age":"2024-05-10T10:00:00","breaking":true,
+                 "thumbnail":{"src":"https://img.example.com/t.png"},
+                 "meta_url":{"hostname":"news.example.com"}},
+                {"title":"Older","description":"d","url":"https://blog.example.org/post"}
+            ]}"#,
+        )
+        .create_async()
+        .await;
+
+        let router = router_for(&server);
+
+        let result = router
+            .brave_news_search(NewsSearchParams {
+                query: "rust".to_string(),
+                output_format: Some(OutputFormat::Json),
+                ..Default::default()
+            })
             .await;
+        let items: Vec<NewsItem> = json_results(&result);
+        assert_eq!(
+            items,
+            vec![
+                NewsItem {
+                    title: "Rust 2.0".to_string(),
+                    description: "Released".to_string(),
+                    url: "https://news.example.com/rust".to_string(),
+                    age: Some("2 hours ago".to_string()),
+                    published_at: Some("2024-05-10T10:00:00Z".to_string()),
+                    breaking: true,
+                    thumbnail: Some("https://img.example.com/t.png".to_string()),
+                    extra_thumbnails: Vec::new(),
+                    source: Some("news.example.com".to_string()),
+                },
+                NewsItem {
+                    title: "Older".to_string(),
+                    description: "d".to_string(),
+                    url: "https://blog.example.org/post".to_string(),
+                    age: None,
+                    published_at: None,
+                    breaking: false,
+                    thumbnail: None,
+                    extra_thumbnails: Vec::new(),
+                    source: Some("blog.example.org".to_string()),
+                },
+            ]
+        );
+        // The flag is a boolean field, not a title prefix
+        assert!(!result.contains("[BREAKING]"));
+        assert_eq!(
+            result,
+            format!(
+                r#"{{"results":{},"total_estimate":null}}"#,
+                serde_json::to_string(&items).unwrap()
+            )
+        );
+    }
 
-        println!("Web search result: {}", web_result);
-        assert!(!web_result.is_empty());
-        assert!(web_result.contains("Rust"));
+    #[tokio::test]
+    async fn test_news_published_in_requested_timezone() {
+        let mut server = mockito::Server::new_async().await;
+        json_mock(
+            &mut server,
+            "/res/v1/news/search",
+            r#"{"type":"news","results":[{"title":"t","description":"d",
+                "url":"https://news.example.com/a","page_age":"2024-05-01T05:30:00"}]}"#,
+        )
+        .create_async()
+        .await;
+
+        let router = router_for(&server);
+
+        let params = NewsSearchParams {
+            query: "rust".to_string(),
+            tz: Some("Asia/Tokyo".to_string()),
+            ..Default::default()
+        };
+        let result = router.brave_news_search(params.clone()).await;
+        assert!(
+            result.contains("\nPublished: 2024-05-01 14:30 JST"),
+            "{}",
+            result
+        );
 
-        // Test 2: News Search with country and language
-        let news_result = router
-            .brave_news_search(
-                "technology".to_string(),
-                Some(3),
-                None,
-                Some("JP".to_string()),
-                Some("en".to_string()),
-                Some("w".to_string()),
-            )
+        let result = router
+            .brave_news_search(NewsSearchParams {
+                tz: Some("Tokyo".to_string()),
+                ..params
+            })
             .await;
+        assert!(result.starts_with("Error parsing timezone: Unknown timezone: Tokyo"));
+    }
 
-        println!("News search result (JP, en): {}", news_result);
-        assert!(!news_result.is_empty());
-        assert!(news_result != "No news results found");
-        assert!(!news_result.starts_with("Error parsing"));
+    #[test]
+    fn test_format_local_results_compacts_weekly_hours() {
+        let pois_data: BravePoiResponse = serde_json::from_str(
+            r#"{"results":[{"id":"poi1","name":"Corner Bakery","opening_hours":[
+                "Monday: 7:00 AM - 6:00 PM","Tuesday: 7:00 AM - 6:00 PM",
+                "Wednesday: 7:00 AM - 6:00 PM","Thursday: 7:00 AM - 6:00 PM",
+                "Friday: 7:00 AM - 6:00 PM","Saturday: 8:00 AM - 2:00 PM","Sunday: Closed"
+            ]}]}"#,
+        )
+        .unwrap();
+        let desc_data = || BraveDescription {
+            descriptions: HashMap::new(),
+        };
+        let router = BraveSearchRouter::new("test_key".to_string());
 
-        // Test 3: Local Search
-        let local_result = router
-            .brave_local_search("coffee shop".to_string(), Some(2))
-            .await;
+        let result = router.format_local_results(
+            pois_data,
+            desc_data(),
+            &TextFormatter,
+            OutputOptions::default(),
+            false,
+        );
+        assert!(
+            result.contains(
+                "\nHours: Mon-Fri: 7:00 AM - 6:00 PM, Sat: 8:00 AM - 2:00 PM, Sun: Closed\n"
+            ),
+            "{}",
+            result
+        );
 
-        println!("Local search result: {}", local_result);
-        assert!(!local_result.is_empty());
+        // raw_hours keeps Brave's entries as-is
+        let pois_data: BravePoiResponse = serde_json::from_str(
+            r#"{"results":[{"id":"poi1","name":"Corner Bakery","opening_hours":[
+                "Monday: 7:00 AM - 6:00 PM","Tuesday: 7:00 AM - 6:00 PM"
+            ]}]}"#,
+        )
+        .unwrap();
+        let result = router.format_local_results(
+            pois_data,
+            desc_data(),
+            &TextFormatter,
+            OutputOptions::default(),
+            true,
+        );
+        assert!(result.contains("\nHours: Monday: 7:00 AM - 6:00 PM, Tuesday: 7:00 AM - 6:00 PM\n"));
     }
 
     #[tokio::test]
-    async fn test_news_search_with_query() {
-        // Skip the test if API key is not set in environment
-        let api_key = std::env::var("BRAVE_API_KEY").unwrap_or_else(|_| {
-            eprintln!("BRAVE_API_KEY environment variable not set, skipping test");
-            String::from("dummy_key")
-        });
+    async fn test_local_min_rating_filter() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::UrlEncoded("q".into(), "pizza".into()))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"type":"search","locations":{"results":[
+                    {"id":"loc1","title":"Great Slice","rating":{"ratingValue":4.6,"reviewCount":210}},
+                    {"id":"loc2","title":"Soggy Crust","rating":{"ratingValue":2.9,"reviewCount":40}},
+                    {"id":"loc3","title":"New Place"},
+                    {"id":"loc4","title":"Solid Pie","rating":{"ratingValue":4.0}}
+                ]}}"#,
+            )
+            .create_async()
+            .await;
+        // The POI lookup (taken here for open_now) is filtered the same way
+        server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::UrlEncoded("q".into(), "tacos".into()))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"type":"search","locations":{"results":[{"id":"p1"},{"id":"p2"},{"id":"p3"}]}}"#,
+            )
+            .create_async()
+            .await;
+        json_mock(
+            &mut server,
+            "/res/v1/local/pois",
+            r#"{"results":[
+                {"id":"p1","name":"Taqueria Uno","rating":{"rating_value":4.8,"rating_count":99},"opening_hours":["Open 24 hours"]},
+                {"id":"p2","name":"Taco Dos","rating":{"rating_value":3.1,"rating_count":12},"opening_hours":["Open 24 hours"]},
+                {"id":"p3","name":"Taco Tres","opening_hours":["Open 24 hours"]}
+            ]}"#,
+        )
+        .create_async()
+        .await;
+        json_mock(
+            &mut server,
+            "/res/v1/local/descriptions",
+            r#"{"descriptions":{}}"#,
+        )
+        .create_async()
+        .await;
+
+        let router = router_for(&server);
+
+        let params = LocalSearchParams {
+            query: "pizza".to_string(),
+            min_rating: Some(4.0),
+            ..Default::default()
+        };
+        let result = router.brave_local_search(params.clone()).await;
+        assert_eq!(result.matches("Name: ").count(), 2, "{}", result);
+        assert!(result.contains("Name: Great Slice") && result.contains("Name: Solid Pie"));
+        assert!(!result.contains("Soggy Crust") && !result.contains("New Place"));
+
+        let result = router
+            .brave_local_search(LocalSearchParams {
+                query: "tacos".to_string(),
+                open_now: Some(true),
+                ..params.clone()
+            })
+            .await;
+        assert_eq!(result.matches("Name: ").count(), 1, "{}", result);
+        assert!(result.contains("Name: Taqueria Uno"));
+
+        // Solid Pie has no review count, so it counts as 0 reviews
+        let result = router
+            .brave_local_search(LocalSearchParams {
+                min_reviews: Some(100),
+                ..params.clone()
+            })
+            .await;
+        assert_eq!(result.matches("Name: ").count(), 1, "{}", result);
+        assert!(result.contains("Name: Great Slice"));
 
-        // Only run this test if we have a real API key
-        if api_key == "dummy_key" {
-            // Skip the test
-            return;
-        }
+        let result = router
+            .brave_local_search(LocalSearchParams {
+                min_rating: Some(7.5),
+                ..params
+            })
+            .await;
+        assert_eq!(result, "Error: min_rating must be between 0 and 5, got 7.5");
+    }
 
-        // Create a BraveSearchRouter with the API key
-        let router = BraveSearchRouter::new(api_key);
+    #[test]
+    fn test_min_reviews_filter() {
+        let rating = |value: Option<f64>, count: Option<u32>| BraveRating {
+            rating_value: value,
+            rating_count: count,
+        };
+        let params = |min_rating: Option<f64>, min_reviews: Option<u32>| LocalSearchParams {
+            min_rating,
+            min_reviews,
+            ..Default::default()
+        };
 
-        // Search for current news with US country code and English language
-        // Use "news" as a generic query that should always return results
-        let news_result = router
-            .brave_news_search(
-                "news".to_string(),
-                Some(3),
-                None,
-                Some("US".to_string()),
-                Some("en".to_string()),
-                None,
+        let at_least_50 = params(None, Some(50));
+        assert!(meets_rating_filters(
+            Some(&rating(Some(4.9), Some(120))),
+            &at_least_50
+        ));
+        assert!(meets_rating_filters(
+            Some(&rating(None, Some(50))),
+            &at_least_50
+        ));
+        // One glowing review is not enough
+        assert!(!meets_rating_filters(
+            Some(&rating(Some(5.0), Some(1))),
+            &at_least_50
+        ));
+        // Missing counts are treated as 0
+        assert!(!meets_rating_filters(
+            Some(&rating(Some(4.5), None)),
+            &at_least_50
+        ));
+        assert!(!meets_rating_filters(None, &at_least_50));
+        assert!(meets_rating_filters(None, &params(None, Some(0))));
+
+        // Combined with min_rating, both must pass
+        let both = params(Some(4.0), Some(50));
+        assert!(meets_rating_filters(
+            Some(&rating(Some(4.2), Some(80))),
+            &both
+        ));
+        assert!(!meets_rating_filters(
+            Some(&rating(Some(3.9), Some(800))),
+            &both
+        ));
+        assert!(!meets_rating_filters(
+            Some(&rating(Some(4.8), Some(20))),
+            &both
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_local_search_offset_pages_results() {
+        let mut server = mockito::Server::new_async().await;
+        let locations = server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("result_filter".into(), "locations".into()),
+                mockito::Matcher::UrlEncoded("count".into(), "5".into()),
+                mockito::Matcher::UrlEncoded("offset".into(), "2".into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"type":"search","locations":{"results":[{"id":"loc11","title":"Page Three Pizza"}]}}"#,
             )
+            .expect(1)
+            .create_async()
             .await;
 
-        println!("News search result: {}", news_result);
+        let router = router_for(&server);
 
-        // Verify we got results
-        assert!(!news_result.is_empty());
-        assert!(news_result != "No news results found");
-        assert!(!news_result.starts_with("Error parsing"));
+        let params = LocalSearchParams {
+            query: "pizza near Central Park".to_string(),
+            offset: Some(2),
+            numbered: Some(true),
+            ..Default::default()
+        };
+        let result = router.brave_local_search(params.clone()).await;
+        locations.assert_async().await;
+        // Numbering continues from the page, as with web search
+        assert!(
+            result.starts_with("11. Name: Page Three Pizza"),
+            "{}",
+            result
+        );
 
-        // Print the API response details
-        println!("\nNews search API response received successfully!");
+        // With no locations on the page, the web search fallback is sent with the
+        // same offset, clamped to Brave's maximum of 9
+        let both_requests = server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::UrlEncoded("offset".into(), "9".into()))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"type":"search","web":{"results":[]}}"#)
+            .expect(2)
+            .create_async()
+            .await;
+        router
+            .brave_local_search(LocalSearchParams {
+                offset: Some(40),
+                ..params
+            })
+            .await;
+        both_requests.assert_async().await;
     }
 
-    // New unit tests
-    #[test]
-    fn test_country_code_from_str() {
-        // Test valid country codes
-        assert_eq!(CountryCode::from_str("US").unwrap(), CountryCode::US);
-        assert_eq!(CountryCode::from_str("us").unwrap(), CountryCode::US);
-        assert_eq!(CountryCode::from_str("JP").unwrap(), CountryCode::JP);
-        assert_eq!(CountryCode::from_str("all").unwrap(), CountryCode::ALL);
+    #[tokio::test]
+    async fn test_sparse_local_results_are_supplemented_with_web() {
+        let mut server = mockito::Server::new_async().await;
+        let locations = server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "result_filter".into(),
+                "locations".into(),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"type":"search","locations":{"results":[{"id":"loc1","title":"Lone Diner"}]}}"#,
+            )
+            .expect(3)
+            .create_async()
+            .await;
+        // The web search has no result_filter, so the URL goes straight from q to count
+        let web = server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::Regex("^q=[^&]*&count=".to_string()))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"type":"search","web":{"results":[{"title":"Diners in Smalltown","description":"d","url":"https://example.com/diners"}]}}"#,
+            )
+            .expect(2)
+            .create_async()
+            .await;
 
-        // Test invalid country code
-        let invalid = CountryCode::from_str("ZZ");
-        assert!(invalid.is_err());
-        assert_eq!(invalid.unwrap_err(), "Unknown country code: ZZ");
-    }
+        let router = router_for(&server);
+        let params = LocalSearchParams {
+            query: "diner in Smalltown".to_string(),
+            min_local_results: Some(3),
+            ..Default::default()
+        };
 
-    #[test]
-    fn test_language_code_from_str() {
-        // Test valid language codes
-        assert_eq!(LanguageCode::from_str("en").unwrap(), LanguageCode::EN);
-        assert_eq!(LanguageCode::from_str("EN").unwrap(), LanguageCode::EN);
-        assert_eq!(LanguageCode::from_str("en-gb").unwrap(), LanguageCode::EnGb);
+        let result = router.brave_local_search(params.clone()).await;
+        assert!(result.starts_with("Name: Lone Diner"), "{}", result);
+        assert!(
+            result.contains("\n\nSupplemented with web results:\n\nTitle: Diners in Smalltown"),
+            "{}",
+            result
+        );
+        // In JSON the supplement is the web search's own results object
+        let result = router
+            .brave_local_search(LocalSearchParams {
+                output_format: Some(OutputFormat::Json),
+                ..params.clone()
+            })
+            .await;
+        let json: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(json["locations"][0]["name"], "Lone Diner");
         assert_eq!(
-            LanguageCode::from_str("zh-hans").unwrap(),
-            LanguageCode::ZhHans
+            json["web_supplement"]["results"][0]["title"],
+            "Diners in Smalltown"
         );
+        assert!(json["web_supplement"]["total_estimate"].is_null());
 
-        // Test invalid language code
-        let invalid = LanguageCode::from_str("xx");
-        assert!(invalid.is_err());
-        assert_eq!(invalid.unwrap_err(), "Unknown language code: xx");
+        // Enough locations: no supplement and no web request
+        let result = router
+            .brave_local_search(LocalSearchParams {
+                min_local_results: Some(1),
+                ..params
+            })
+            .await;
+        assert!(!result.contains("Supplemented"), "{}", result);
+        locations.assert_async().await;
+        web.assert_async().await;
     }
 
-    #[test]
-    fn test_country_code_display() {
-        assert_eq!(CountryCode::US.to_string(), "us");
-        assert_eq!(CountryCode::ALL.to_string(), "all");
-        assert_eq!(CountryCode::JP.to_string(), "jp");
-    }
+    #[tokio::test]
+    async fn test_long_id_lists_are_looked_up_in_batches() {
+        let mut server = mockito::Server::new_async().await;
+        let pois = json_mock(
+            &mut server,
+            "/res/v1/local/pois",
+            r#"{"results":[{"id":"loc","name":"Cafe"}]}"#,
+        )
+        .expect(2)
+        .create_async()
+        .await;
+
+        let router = router_for(&server);
+
+        // 60 IDs of 40 characters need about 2,700 bytes of query, more than one URL allows
+        let ids = (0..60).map(|i| format!("{:040}", i)).collect::<Vec<_>>();
+        let base = reqwest::Url::parse(&format!("{}/res/v1/local/pois", server.url())).unwrap();
+        let batches = BraveSearchRouter::id_batches(&base, &ids);
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches.concat(), ids);
+        for batch in &batches {
+            assert!(
+                BraveSearchRouter::ids_url(&base, batch).as_str().len() <= MAX_LOOKUP_URL_LENGTH
+            );
+        }
 
-    #[test]
-    fn test_language_code_display() {
-        assert_eq!(LanguageCode::EN.to_string(), "en");
-        assert_eq!(LanguageCode::EnGb.to_string(), "en-gb");
-        assert_eq!(LanguageCode::ZhHans.to_string(), "zh-hans");
+        // Each batch is one request, and the results are merged
+        let data = router.get_pois_data(&ids).await.unwrap();
+        assert_eq!(data.results.len(), 2);
+        pois.assert_async().await;
     }
 
     #[tokio::test]
-    async fn test_rate_limiter() {
-        let limiter = RateLimiter::new();
+    async fn test_lookup_rejected_as_too_long_is_split() {
+        let mut server = mockito::Server::new_async().await;
+        // Four or more IDs in one request are rejected
+        let too_long = server
+            .mock("GET", "/res/v1/local/descriptions")
+            .match_query(mockito::Matcher::Regex("^(ids=[^&]*&){3}ids=".to_string()))
+            .with_status(414)
+            .with_body("URI Too Long")
+            .expect(1)
+            .create_async()
+            .await;
+        let short = server
+            .mock("GET", "/res/v1/local/descriptions")
+            .match_query(mockito::Matcher::Regex(
+                "^ids=[^&]*(&ids=[^&]*)?$".to_string(),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"descriptions":{"a":"first","c":"third"}}"#)
+            .expect(2)
+            .create_async()
+            .await;
 
-        // First request should succeed
-        assert!(limiter.check_rate_limit().await.is_ok());
+        let router = router_for(&server);
 
-        // Simulate reaching per-second limit
-        {
-            let mut count = limiter.request_count.lock().await;
-            count.second = RATE_LIMIT_PER_SECOND;
+        let ids = ["a", "b", "c", "d"].map(String::from);
+        let data = router.get_descriptions_data(&ids).await.unwrap();
+        assert_eq!(data.descriptions["c"], "third");
+        too_long.assert_async().await;
+        short.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_local_malformed_coordinates_are_noted() {
+        let mut server = mockito::Server::new_async().await;
+        let _locations = json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","locations":{"results":[{"id":"loc1","title":"Odd Cafe","coordinates":[47.6]},{"id":"loc2","title":"Good Cafe","coordinates":[47.6,-122.3]}]}}"#,
+        )
+        .create_async()
+        .await;
+        let router = router_for(&server);
+
+        let result = router
+            .brave_local_search(LocalSearchParams {
+                query: "cafe".to_string(),
+                output_format: Some(OutputFormat::Json),
+                ..Default::default()
+            })
+            .await;
+        let items: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(items[0]["name"], "Odd Cafe");
+        assert!(items[0]["coordinates"].is_null());
+        assert_eq!(
+            items[0]["coordinates_note"],
+            "Brave returned malformed coordinates (expected [latitude, longitude], got 1 value)"
+        );
+        assert_eq!(items[1]["coordinates"], serde_json::json!([47.6, -122.3]));
+        assert!(items[1].get("coordinates_note").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_local_category_filter() {
+        let body = r#"{"type":"search","locations":{"results":[
+            {"id":"loc1","title":"Blue Bottle","categories":["Coffee shop"]},
+            {"id":"loc2","title":"Trattoria","categories":["Italian restaurant"]},
+            {"id":"loc3","title":"Corner Cafe","categories":["Cafe","Bakery"]},
+            {"id":"loc4","title":"The Tap Room","categories":["Bar"]},
+            {"id":"loc5","title":"Untagged Spot"}
+        ]}}"#;
+        let mut server = mockito::Server::new_async().await;
+        let mut mocks = Vec::new();
+        // The category is appended to the query sent to Brave
+        for category in ["cafe", "museum"] {
+            let query = format!("food near Union Square {}", category);
+            let mock = server
+                .mock("GET", "/res/v1/web/search")
+                .match_query(mockito::Matcher::UrlEncoded("q".into(), query))
+                .with_header("content-type", "application/json")
+                .with_body(body)
+                .expect(1)
+                .create_async()
+                .await;
+            mocks.push(mock);
         }
 
-        // Next request should fail due to rate limit
-        assert!(limiter.check_rate_limit().await.is_err());
+        let router = router_for(&server);
 
-        // Reset counter and test monthly limit
-        {
-            let mut count = limiter.request_count.lock().await;
-            count.second = 0;
-            count.month = RATE_LIMIT_PER_MONTH;
+        let params = LocalSearchParams {
+            query: "food near Union Square".to_string(),
+            category: Some("cafe".to_string()),
+            ..Default::default()
+        };
+        let result = router.brave_local_search(params.clone()).await;
+        assert_eq!(result.matches("Name: ").count(), 3, "{}", result);
+        assert!(result.contains("Name: Blue Bottle"));
+        assert!(result.contains("Name: Corner Cafe"));
+        assert!(result.contains("Name: Untagged Spot"));
+        assert!(!result.contains("Trattoria") && !result.contains("Tap Room"));
+
+        // Only the untagged location survives a filter nothing is tagged with
+        let result = router
+            .brave_local_search(LocalSearchParams {
+                category: Some("museum".to_string()),
+                ..params.clone()
+            })
+            .await;
+        assert_eq!(result.matches("Name: ").count(), 1, "{}", result);
+        assert!(result.contains("Name: Untagged Spot"));
+        for mock in mocks {
+            mock.assert_async().await;
         }
 
-        // Request should fail due to monthly limit
-        assert!(limiter.check_rate_limit().await.is_err());
+        let result = router
+            .brave_local_search(LocalSearchParams {
+                category: Some("spaceport".to_string()),
+                ..params
+            })
+            .await;
+        assert!(result.starts_with("Error parsing category: Unknown category: spaceport"));
+    }
+
+    #[tokio::test]
+    async fn test_clones_share_rate_limit_and_cache() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","web":{"results":[]}}"#,
+        )
+        .expect(1)
+        .create_async()
+        .await;
+
+        let mut router = router_for(&server).with_cache_ttl(Some(Duration::from_secs(60)));
+        router.rate_limiter = RateLimiter::with_limits(1, RATE_LIMIT_PER_MONTH);
+        let first = router.clone();
+        let second = router.clone();
+
+        let search = |query: &str| WebSearchParams {
+            query: query.to_string(),
+            ..Default::default()
+        };
+        // One clone spends the only request of this second...
+        assert!(!first
+            .brave_web_search(search("rust"))
+            .await
+            .starts_with("Error"));
+        assert_eq!(first.rate_limiter.usage().await.2, 1);
+        assert_eq!(second.rate_limiter.usage().await.2, 1);
+        assert_eq!(router.rate_limiter.usage().await.2, 1);
+
+        // ...so a new query through the other is rate limited,
+        let result = second.brave_web_search(search("tokio")).await;
+        assert_eq!(result, "Error: Rate limit exceeded");
+        // while the first query is served from the shared cache
+        assert!(!second
+            .brave_web_search(search("rust"))
+            .await
+            .starts_with("Error"));
+        mock.assert_async().await;
+    }
+
+    #[test]
+    fn test_crossed_quota_threshold() {
+        // Each threshold is reported once, by the request that reaches it
+        assert_eq!(crossed_quota_threshold(79, 100), None);
+        assert_eq!(crossed_quota_threshold(80, 100), Some(80));
+        assert_eq!(crossed_quota_threshold(81, 100), None);
+        assert_eq!(crossed_quota_threshold(90, 100), Some(90));
+        assert_eq!(crossed_quota_threshold(100, 100), Some(100));
+
+        // Coarse limits can cross several thresholds at once; the highest wins
+        assert_eq!(crossed_quota_threshold(1, 1), Some(100));
+        assert_eq!(crossed_quota_threshold(0, 0), None);
     }
 
     #[test]
@@ -1246,4 +5749,203 @@ mod tests {
             .unwrap()
             .contains("Brave Search MCP Server"));
     }
+
+    #[tokio::test]
+    async fn test_total_estimate_is_shown_only_when_present() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::UrlEncoded("q".into(), "rust".into()))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"type":"search","web":{"total_count":125000,"results":[
+                    {"title":"Rust","description":"d","url":"https://www.rust-lang.org/"}]}}"#,
+            )
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::UrlEncoded("q".into(), "tokio".into()))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"type":"search","web":{"results":[
+                    {"title":"Tokio","description":"d","url":"https://tokio.rs/"}]}}"#,
+            )
+            .create_async()
+            .await;
+        json_mock(
+            &mut server,
+            "/res/v1/news/search",
+            r#"{"type":"news","total_count":42,"results":[
+                {"title":"Rust 2.0","description":"d","url":"https://news.example.com/rust"}]}"#,
+        )
+        .create_async()
+        .await;
+
+        let router = router_for(&server);
+        let search = |query: &str, output_format: Option<OutputFormat>| WebSearchParams {
+            query: query.to_string(),
+            output_format,
+            ..Default::default()
+        };
+
+        let result = router.brave_web_search(search("rust", None)).await;
+        assert!(
+            result.ends_with("\n\nEstimated total results: 125000"),
+            "{}",
+            result
+        );
+        let result = router
+            .brave_web_search(search("rust", Some(OutputFormat::Json)))
+            .await;
+        let json: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(json["total_estimate"], 125000);
+        assert_eq!(json["results"][0]["title"], "Rust");
+
+        // Without an estimate the text output is unchanged, and JSON keeps its shape
+        let result = router.brave_web_search(search("tokio", None)).await;
+        assert!(!result.contains("Estimated total results"), "{}", result);
+        let result = router
+            .brave_web_search(search("tokio", Some(OutputFormat::Json)))
+            .await;
+        let json: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert!(json["total_estimate"].is_null());
+        assert_eq!(json["results"][0]["title"], "Tokio");
+
+        let result = router
+            .brave_news_search(NewsSearchParams {
+                query: "rust".to_string(),
+                ..Default::default()
+            })
+            .await;
+        assert!(result.contains("Estimated total results: 42"), "{}", result);
+    }
+
+    #[tokio::test]
+    async fn test_local_open_now_keeps_open_locations() {
+        let mut server = mockito::Server::new_async().await;
+        json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","locations":{"results":[
+                {"id":"p1","title":"Night Owl"},{"id":"p2","title":"Shuttered"},{"id":"p3","title":"Mystery"}
+            ]}}"#,
+        )
+        .create_async()
+        .await;
+        // Named locations still need the POI lookup for their hours
+        let pois = json_mock(
+            &mut server,
+            "/res/v1/local/pois",
+            r#"{"results":[
+                {"id":"p1","name":"Night Owl","opening_hours":["Open 24 hours"]},
+                {"id":"p2","name":"Shuttered","opening_hours":["Mon-Sun: Closed"]},
+                {"id":"p3","name":"Mystery"}
+            ]}"#,
+        )
+        .create_async()
+        .await;
+        json_mock(
+            &mut server,
+            "/res/v1/local/descriptions",
+            r#"{"descriptions":{}}"#,
+        )
+        .create_async()
+        .await;
+
+        let router = router_for(&server);
+
+        let result = router
+            .brave_local_search(LocalSearchParams {
+                query: "diner".to_string(),
+                open_now: Some(true),
+                tz: Some("America/New_York".to_string()),
+                ..Default::default()
+            })
+            .await;
+        assert_eq!(result.matches("Name: ").count(), 1, "{}", result);
+        assert!(result.contains("Name: Night Owl"), "{}", result);
+        pois.assert_async().await;
+
+        let result = router
+            .brave_local_search(LocalSearchParams {
+                query: "diner".to_string(),
+                open_now: Some(true),
+                tz: Some("Gotham".to_string()),
+                ..Default::default()
+            })
+            .await;
+        assert!(
+            result.starts_with("Error parsing timezone: Unknown timezone: Gotham"),
+            "{}",
+            result
+        );
+    }
+
+    #[tokio::test]
+    async fn test_news_max_thumbnails_caps_thumbnails_per_article() {
+        let mut server = mockito::Server::new_async().await;
+        json_mock(
+            &mut server,
+            "/res/v1/news/search",
+            r#"{"type":"news","results":[
+                {"title":"Gallery","description":"d","url":"https://news.example.com/a","thumbnail":[
+                    {"src":"https://img.example.com/1.png"},
+                    {"src":"https://img.example.com/2.png"},
+                    {"src":"https://img.example.com/3.png"}]},
+                {"title":"Single","description":"d","url":"https://news.example.com/b",
+                 "thumbnail":{"src":"https://img.example.com/s.png"}}
+            ]}"#,
+        )
+        .create_async()
+        .await;
+
+        let router = router_for(&server);
+        let search = |max_thumbnails: Option<usize>| NewsSearchParams {
+            query: "rust".to_string(),
+            max_thumbnails,
+            output_format: Some(OutputFormat::Json),
+            ..Default::default()
+        };
+        let thumbnails = |result: &str| {
+            json_results::<Vec<NewsItem>>(result)
+                .iter()
+                .map(|item| item.thumbnails().map(str::to_string).collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        };
+
+        // One thumbnail by default, as before
+        let result = router.brave_news_search(search(None)).await;
+        assert!(!result.contains("extra_thumbnails"), "{}", result);
+        assert_eq!(
+            thumbnails(&result),
+            vec![
+                vec!["https://img.example.com/1.png".to_string()],
+                vec!["https://img.example.com/s.png".to_string()],
+            ]
+        );
+
+        let result = router.brave_news_search(search(Some(2))).await;
+        assert_eq!(
+            thumbnails(&result),
+            vec![
+                vec![
+                    "https://img.example.com/1.png".to_string(),
+                    "https://img.example.com/2.png".to_string(),
+                ],
+                vec!["https://img.example.com/s.png".to_string()],
+            ]
+        );
+
+        let result = router
+            .brave_news_search(NewsSearchParams {
+                output_format: None,
+                ..search(Some(5))
+            })
+            .await;
+        assert_eq!(result.matches("Thumbnail: ").count(), 4, "{}", result);
+
+        let result = router.brave_news_search(search(Some(0))).await;
+        assert_eq!(thumbnails(&result), vec![Vec::<String>::new(); 2]);
+    }
 }
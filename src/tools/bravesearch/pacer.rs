@@ -0,0 +1,141 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+// Leaky-bucket pacing of outbound Brave API requests.
+//
+// Each request reserves the next free slot, at least `interval` after the
+// previous one, and waits for it. A burst of calls therefore leaves the
+// server as an evenly spaced stream instead of being sent, or rejected, all
+// at once. Clones of a pacer share its slots.
+
+#[derive(Clone)]
+pub(crate) struct Pacer {
+    interval: Duration,
+    next_slot: Arc<Mutex<Instant>>,
+}
+
+impl Pacer {
+    pub(crate) fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            next_slot: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    pub(crate) fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Reserve the next slot, at least `interval` after the previously reserved one, without
+    /// waiting for it
+    pub(crate) async fn reserve(&self) -> Instant {
+        let mut next_slot = self.next_slot.lock().await;
+        let slot = (*next_slot).max(Instant::now());
+        *next_slot = slot + self.interval;
+        slot
+    }
+
+    /// Wait until the next slot, at least `interval` after the previously reserved one
+    #[cfg(test)]
+    pub(crate) async fn acquire(&self) {
+        tokio::time::sleep_until(self.reserve().await).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_client::{notifications, TestClient};
+    use super::super::test_fixtures::{json_mock, router_for};
+    use super::super::WebSearchParams;
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_sequential_requests_are_spaced_by_interval() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","web":{"results":[]}}"#,
+        )
+        .expect(3)
+        .create_async()
+        .await;
+        let router = router_for(&server).with_request_pacing(Some(Duration::from_millis(100)));
+
+        let start = Instant::now();
+        for query in ["rust", "tokio", "serde"] {
+            let result = router
+                .brave_web_search(WebSearchParams {
+                    query: query.to_string(),
+                    ..Default::default()
+                })
+                .await;
+            assert!(!result.starts_with("Error"), "{}", result);
+        }
+        // The first request goes out at once; each later one waits one interval
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_acquires_get_distinct_slots() {
+        let pacer = Pacer::new(Duration::from_millis(50));
+        let start = Instant::now();
+        futures::future::join_all((0..3).map(|_| pacer.acquire())).await;
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn test_client_is_told_when_a_request_waits() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = json_mock(
+            &mut server,
+            "/res/v1/web/search",
+            r#"{"type":"search","web":{"results":[]}}"#,
+        )
+        .create_async()
+        .await;
+        let router = router_for(&server).with_request_pacing(Some(Duration::from_millis(500)));
+
+        let mut client = TestClient::start(router).await;
+        let waits = |messages: &[serde_json::Value]| {
+            notifications(messages, "notifications/message")
+                .into_iter()
+                .filter(|params| params["logger"] == "bravesearch/wait")
+                .count()
+        };
+
+        // The first request goes out at once; the second waits for its slot
+        let first = client
+            .call_tool(
+                2,
+                "brave_web_search",
+                json!({"query": "rust", "progress_token": "web-1"}),
+            )
+            .await;
+        assert!(notifications(&first, "notifications/progress").is_empty());
+        assert_eq!(waits(&first), 0, "{:?}", first);
+        let second = client
+            .call_tool(
+                3,
+                "brave_web_search",
+                json!({"query": "tokio", "progress_token": "web-2"}),
+            )
+            .await;
+        assert_eq!(
+            notifications(&second, "notifications/progress"),
+            vec![&json!({"progressToken": "web-2", "progress": 0, "total": 10})]
+        );
+        assert_eq!(waits(&second), 1, "{:?}", second);
+
+        // Without a progress token the wait is not reported
+        let third = client
+            .call_tool(4, "brave_web_search", json!({"query": "serde"}))
+            .await;
+        assert!(notifications(&third, "notifications/progress").is_empty());
+        assert_eq!(waits(&third), 0, "{:?}", third);
+    }
+}
@@ -0,0 +1,383 @@
+use std::collections::HashMap;
+
+use rmcp::model::ProgressToken;
+use rmcp::schemars;
+use serde::Deserialize;
+
+use super::OutputFormat;
+
+// Tool parameters for the Brave Search tools.
+//
+// Each tool takes one aggregated parameter struct so optional settings can be
+// added without growing the tool function signatures.
+
+#[derive(Debug, Clone, Default, Deserialize, schemars::JsonSchema)]
+pub struct WebSearchParams {
+    #[schemars(
+        description = "Search query to find relevant web results. Limited to maximum 400 characters or 50 words. Use specific, concise queries for best results."
+    )]
+    pub query: String,
+
+    #[schemars(
+        description = "Number of results to return, between 1-20 (default 10). Higher values provide more comprehensive results but may include less relevant items."
+    )]
+    pub count: Option<usize>,
+
+    #[schemars(
+        description = "Pagination offset for viewing additional results, maximum value 9 (default 0). Use incremental values to see more results beyond the initial set."
+    )]
+    pub offset: Option<usize>,
+
+    #[schemars(
+        description = "When true, prefixes each result with its 1-based position (e.g. '1. Title: ...'). Numbering continues across pages, so offset 1 with count 10 starts at 11. Defaults to false."
+    )]
+    pub numbered: Option<bool>,
+
+    #[schemars(
+        description = "HTTPS URL of a Brave Goggle used to re-rank results (e.g. 'https://raw.githubusercontent.com/brave/goggles-quickstart/main/goggles/hacker_news.goggle'). The value must be a well-formed https URL and is rejected before sending otherwise. Omit to use Brave's default ranking."
+    )]
+    pub goggles: Option<String>,
+
+    #[schemars(
+        description = "Search operators appended to the query after validation, so they cannot be mistyped: site (a domain, e.g. 'docs.rs'), filetype (an extension, e.g. 'pdf'), intitle (text required in the title), exact_phrase (a phrase to match exactly), and exclude (words or phrases to leave out). Multi-word values are quoted automatically; do not add quotes or a leading '-'. The query may be empty when operators are given. Invalid values are rejected before sending. Example: {\"site\": \"github.com\", \"exclude\": [\"archived\"]}."
+    )]
+    pub operators: Option<QueryOperators>,
+
+    #[schemars(
+        description = "Country code to tailor web results to a region, using the same codes as brave_news_search (e.g. US, GB, JP, or ALL for worldwide). Results can differ meaningfully by country. Omit to send no country and let Brave decide."
+    )]
+    pub country: Option<String>,
+
+    #[schemars(
+        description = "Search language for web results, using the same codes as brave_news_search (e.g. en, ja, pt-br). Omit to send no language and let Brave decide."
+    )]
+    pub search_lang: Option<String>,
+
+    #[schemars(
+        description = "Adult content filtering: 'off', 'moderate', or 'strict'. Omit to let Brave apply its default (moderate). The server operator may force a level, which then overrides this value."
+    )]
+    pub safesearch: Option<String>,
+
+    #[schemars(
+        description = "Maximum size of the combined output in bytes. Results are dropped whole from the end once the budget is reached (never cut mid-result), followed by a note like '(3 more results omitted)'. The first result is always included. Omit for no limit."
+    )]
+    pub max_output_bytes: Option<usize>,
+
+    #[schemars(
+        description = "When true, sorts results by URL (ties keep Brave's relevance order) so repeated identical queries produce byte-identical output, which helps caching and snapshot tests. Defaults to false (Brave's relevance order)."
+    )]
+    pub stable_sort: Option<bool>,
+
+    #[schemars(
+        description = "When true, orders results newest first using each result's age (page_age or relative age), keeping Brave's relevance order among results of the same age. Results without age data go last. Useful for fast-moving topics. Defaults to false."
+    )]
+    pub prefer_recent: Option<bool>,
+
+    #[schemars(
+        description = "When true, follows Brave's own page layout (its 'mixed' ranking): news and video results Brave placed among the web results are included at their positions, labeled '[news]' or '[video]' (a 'kind' field in json). The layout is cut to count, and stable_sort and prefer_recent reorder what is left. Defaults to false (web results only)."
+    )]
+    pub mixed_order: Option<bool>,
+
+    #[schemars(
+        description = "When true, adds each result's source site name (from Brave's site profile, e.g. 'Wikipedia') as a 'Source:' line, or a 'source' field in json. Results without a profile get none. Defaults to false."
+    )]
+    pub include_source: Option<bool>,
+
+    #[schemars(
+        description = "When true, removes tracking query parameters (utm_*, gclid, fbclid, msclkid, and similar) from each result URL, keeping all other parameters. Defaults to false (URLs exactly as Brave returns them)."
+    )]
+    pub clean_urls: Option<bool>,
+
+    #[schemars(
+        description = "When true, wraps whole-word, case-insensitive occurrences of the query's words in titles and descriptions with the server's highlight marker (default '**', i.e. bold in markdown). Single-character words are not highlighted. Defaults to false."
+    )]
+    pub highlight: Option<bool>,
+
+    #[schemars(
+        description = "When true, picks the sentence from the top results' descriptions that most likely answers the query directly (e.g. a definition or a figure) and shows it as an 'Answer:' line above the full results. Nothing is added when no sentence looks like an answer. Ignored for output_format 'json'. Defaults to false."
+    )]
+    pub extract_answer: Option<bool>,
+
+    #[schemars(
+        description = "When true and Brave returns no results, the search is sent once more after a short delay (about a second) before concluding there are none, since Brave occasionally returns an empty page for a valid query. The retry counts against the rate limit and quota. Defaults to false, because empty results are usually genuine."
+    )]
+    pub retry_on_empty: Option<bool>,
+
+    #[schemars(
+        description = "When true and Brave reports a spell-corrected query (e.g. 'rust' for 'rsut'), searches the corrected query instead and returns those results with a note naming the substitution. Costs one extra API request when a correction is made. Defaults to false (results for the query as typed)."
+    )]
+    pub use_corrected_query: Option<bool>,
+
+    #[schemars(
+        description = "Output format: 'text' (labeled lines), 'json' (an array of result objects), 'markdown' (linked titles), or 'compact' (one 'title | host | url' line per result, titles cut to the server's width). Omit to use the server's default format (text unless configured). Numbering, max_output_bytes, and notes do not apply to json."
+    )]
+    pub output_format: Option<OutputFormat>,
+
+    #[schemars(
+        description = "When true, indents output_format 'json' across lines for human readers. Defaults to false: single-line JSON, which costs fewer tokens. Ignored for other formats."
+    )]
+    pub pretty: Option<bool>,
+
+    #[schemars(
+        description = "Extra Brave API query parameters sent verbatim, for API features this server does not model yet (e.g. {\"ui_lang\": \"en-US\"}). Names may use letters, digits, '_', '-', '.'; values must be non-empty. Parameters this tool sets itself (q, count, offset, ...), safesearch, and credential-like names are rejected."
+    )]
+    pub extra_params: Option<HashMap<String, String>>,
+
+    #[schemars(
+        description = "Optional MCP progress token. When set, each result is delivered while the response is still arriving: a notifications/progress (progress N of count) followed by a notifications/message with logger 'bravesearch/partial' whose data holds the progressToken, progress, and the formatted result as content. Partials come in Brave's order; the final tool result is unchanged (sorting and truncation apply only there). Omit for a single buffered result.",
+        with = "Option<serde_json::Value>"
+    )]
+    pub progress_token: Option<ProgressToken>,
+}
+
+/// Structured search operators for `brave_web_search`, rendered by `QueryBuilder`
+#[derive(Debug, Clone, Default, Deserialize, schemars::JsonSchema)]
+pub struct QueryOperators {
+    #[schemars(description = "Only return results from this domain (e.g. 'example.com')")]
+    pub site: Option<String>,
+
+    #[schemars(description = "Only return files with this extension (e.g. 'pdf')")]
+    pub filetype: Option<String>,
+
+    #[schemars(description = "Text that must appear in the page title")]
+    pub intitle: Option<String>,
+
+    #[schemars(description = "Phrase that must appear exactly as given")]
+    pub exact_phrase: Option<String>,
+
+    #[schemars(description = "Words or phrases that results must not contain")]
+    pub exclude: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, schemars::JsonSchema)]
+pub struct WebSearchBatchParams {
+    #[schemars(
+        description = "List of web search queries to run, between 1-5 entries. Each query follows the same limits as brave_web_search (maximum 400 characters or 50 words). Extra queries beyond the fifth are rejected with an error."
+    )]
+    pub queries: Vec<String>,
+
+    #[schemars(
+        description = "Number of results to return per query, between 1-20 (default 10). Keep this small when batching to limit the total output size."
+    )]
+    pub count: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, schemars::JsonSchema)]
+pub struct NewsSearchParams {
+    #[schemars(
+        description = "News search query specifying the news topic or keywords to search for. Limited to maximum 400 characters or 50 words. Use clear, specific terms for more targeted news results."
+    )]
+    pub query: String,
+
+    #[schemars(
+        description = "Number of news articles to return, between 1-50 (default 20). Higher values provide more comprehensive coverage of a news topic."
+    )]
+    pub count: Option<usize>,
+
+    #[schemars(
+        description = "Pagination offset for viewing additional news results, maximum value 9 (default 0). Use with subsequent requests to see more news beyond the initial set."
+    )]
+    pub offset: Option<usize>,
+
+    #[schemars(
+        description = "Country code to filter news by geographic region. Options: ALL (worldwide), AR, AU, AT, BE, BR, CA, CL, DK, FI, FR, DE, HK, IN, ID, IT, JP, KR, MY, MX, NL, NZ, NO, CN, PL, PT, PH, RU, SA, ZA, ES, SE, CH, TW, TR, GB, US (default US). Use to get region-specific news coverage."
+    )]
+    pub country: Option<String>,
+
+    #[schemars(
+        description = "Search language for news articles. Options: ar, eu, bn, bg, ca, zh-hans, zh-hant, hr, cs, da, nl, en, en-gb, et, fi, fr, gl, de, gu, he, hi, hu, is, it, ja, kn, ko, lv, lt, ms, ml, mr, nb, pl, pt, pt-br, pa, ro, ru, sr, sk, sl, es, sv, ta, te, th, tr, uk, vi (default en). Determines the language of retrieved news articles."
+    )]
+    pub search_lang: Option<String>,
+
+    #[schemars(
+        description = "Timeframe filter to specify how recent the news should be. Use h (hour), d (day), w (week), m (month), or y (year) to control recency. Omit to use the server's configured default freshness (all time periods if none is configured). Most useful for filtering out older news when researching time-sensitive topics."
+    )]
+    pub freshness: Option<String>,
+
+    #[schemars(
+        description = "IANA timezone name (e.g. 'Asia/Tokyo', 'America/New_York') for the 'Published:' timestamp, shown as local time with the zone abbreviation (e.g. '2024-05-01 14:30 JST'). Omit for ISO-8601 UTC. Unknown names are rejected."
+    )]
+    pub tz: Option<String>,
+
+    #[schemars(
+        description = "When true, adds a 'Source: <hostname>' line naming the outlet, taken from the result's metadata or the article URL. Defaults to true."
+    )]
+    pub include_source: Option<bool>,
+
+    #[schemars(
+        description = "When true, removes tracking query parameters (utm_*, gclid, fbclid, msclkid, and similar) from each article URL, which news sites often tag for campaigns, keeping all other parameters. Defaults to false (article URLs exactly as Brave returns them)."
+    )]
+    pub clean_urls: Option<bool>,
+
+    #[schemars(
+        description = "When true, wraps whole-word, case-insensitive occurrences of the query's words in titles and descriptions with the server's highlight marker (default '**', i.e. bold in markdown). Single-character words are not highlighted. Defaults to false."
+    )]
+    pub highlight: Option<bool>,
+
+    #[schemars(
+        description = "When true, lists every article's thumbnail URL in one trailing 'Thumbnails:' block (for clients that render a gallery) instead of a 'Thumbnail:' line inside each article. Defaults to false (inline). JSON output always keeps the thumbnail on each result."
+    )]
+    pub thumbnails_as_gallery: Option<bool>,
+
+    #[schemars(
+        description = "Thumbnail resolution: 'small' (Brave's resized thumbnail, the default) or 'original' (the full-size image, falling back to the small one when Brave has no original)."
+    )]
+    pub thumbnail_size: Option<String>,
+
+    #[schemars(
+        description = "Maximum number of thumbnail URLs per article, for gallery-style clients; extra ones come from articles Brave sends several images for. Defaults to 1. Use 0 for no thumbnails."
+    )]
+    pub max_thumbnails: Option<usize>,
+
+    #[schemars(
+        description = "When true, prefixes each article with its 1-based position (e.g. '1. Title: ...'). Numbering continues across pages, so offset 1 with count 20 starts at 21. Defaults to false."
+    )]
+    pub numbered: Option<bool>,
+
+    #[schemars(
+        description = "Maximum size of the combined output in bytes. Results are dropped whole from the end once the budget is reached (never cut mid-result), followed by a note like '(3 more results omitted)'. The first result is always included. Omit for no limit."
+    )]
+    pub max_output_bytes: Option<usize>,
+
+    #[schemars(
+        description = "When true, sorts results by URL (ties keep Brave's relevance order) so repeated identical queries produce byte-identical output, which helps caching and snapshot tests. Defaults to false (Brave's relevance order)."
+    )]
+    pub stable_sort: Option<bool>,
+
+    #[schemars(
+        description = "When true and Brave returns no articles, the news search is sent once more after a short delay (about a second) before concluding there are none, since Brave occasionally returns an empty page for a valid query. The retry counts against the rate limit and quota. Defaults to false, because a narrow freshness window often has genuinely no news."
+    )]
+    pub retry_on_empty: Option<bool>,
+
+    #[schemars(
+        description = "When true and Brave reports a spell-corrected query (e.g. 'rust' for 'rsut'), searches news for the corrected query instead and returns those articles with a note naming the substitution. Costs one extra API request when a correction is made. Defaults to false (articles for the query as typed)."
+    )]
+    pub use_corrected_query: Option<bool>,
+
+    #[schemars(
+        description = "Output format: 'text' (labeled lines), 'json' (an array of result objects), 'markdown' (linked titles), or 'compact' (one 'title | host | url' line per result, titles cut to the server's width). Omit to use the server's default format (text unless configured). Numbering, max_output_bytes, and notes do not apply to json."
+    )]
+    pub output_format: Option<OutputFormat>,
+
+    #[schemars(
+        description = "When true, indents the articles of output_format 'json' across lines for human readers. Defaults to false: single-line JSON, which costs fewer tokens. Ignored for other formats."
+    )]
+    pub pretty: Option<bool>,
+
+    #[schemars(
+        description = "Extra query parameters sent verbatim to Brave's news search endpoint, for API features this server does not model yet (e.g. {\"ui_lang\": \"en-US\"}). Names may use letters, digits, '_', '-', '.'; values must be non-empty. Parameters this tool sets itself (q, count, offset, freshness, ...), safesearch, and credential-like names are rejected."
+    )]
+    pub extra_params: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, schemars::JsonSchema)]
+pub struct LocalSearchParams {
+    #[schemars(
+        description = "Local search query specifying what and where to search. Format should include both the category/business type and location (e.g., 'pizza near Central Park', 'coffee shops in Seattle', 'gas stations near me'). More specific queries yield better results."
+    )]
+    pub query: String,
+
+    #[schemars(
+        description = "Number of location results to return, between 1-20 (default 5). Higher values provide more options but may include less relevant locations. For popular searches in dense areas, higher values are recommended."
+    )]
+    pub count: Option<usize>,
+
+    #[schemars(
+        description = "Narrows results to one kind of place: restaurant, cafe, bar, bakery, hotel, grocery, pharmacy, gas_station, park, or museum. The category is added to the query unless it already names it, and locations Brave tags with other categories are dropped (untagged locations are kept). Omit for all categories."
+    )]
+    pub category: Option<String>,
+
+    #[schemars(
+        description = "Pagination offset in pages of `count` results (max 9, default 0). Use with the same count to page through more locations; numbering continues across pages. The web search fallback uses the same offset."
+    )]
+    pub offset: Option<usize>,
+
+    #[schemars(
+        description = "Minimum star rating (0-5) a location needs to be included, e.g. 4.0 for well-rated places only. Unrated locations are dropped when this is set. Filtering happens after Brave returns `count` results, so fewer may be shown. Omit for no rating filter."
+    )]
+    pub min_rating: Option<f64>,
+
+    #[schemars(
+        description = "Minimum number of reviews a location needs to be included, so places with a handful of inflated reviews are skipped. Locations without a review count are treated as having 0. Combined with min_rating, both must pass. Omit for no review filter."
+    )]
+    pub min_reviews: Option<u32>,
+
+    #[schemars(
+        description = "When fewer than this many locations are found (after rating and category filters), web results for the same query are appended under a 'Supplemented with web results:' heading, which helps in sparse areas. Costs one extra API request when it applies. Omit to fall back to web search only when no locations are found."
+    )]
+    pub min_local_results: Option<usize>,
+
+    #[schemars(
+        description = "When true, shows opening hours exactly as Brave returns them, one entry per day. Defaults to false, which collapses consecutive days with the same hours (e.g. 'Mon-Fri: 9:00 AM - 5:00 PM')."
+    )]
+    pub raw_hours: Option<bool>,
+
+    #[schemars(
+        description = "When true, keeps only locations open right now according to their opening hours, checked at the current time in `tz`. Locations without hours, or with hours that cannot be read, are dropped. Always fetches location details (one or two extra API requests). Filtering happens after Brave returns `count` results, so fewer may be shown. Defaults to false."
+    )]
+    pub open_now: Option<bool>,
+
+    #[schemars(
+        description = "IANA timezone name (e.g. 'America/New_York') of the searched area, used by open_now to tell the local time. Omit for UTC. Unknown names are rejected."
+    )]
+    pub tz: Option<String>,
+
+    #[schemars(
+        description = "When true, prefixes each location with its 1-based position (e.g. '1. Name: ...'). Defaults to false."
+    )]
+    pub numbered: Option<bool>,
+
+    #[schemars(
+        description = "Maximum size of the combined output in bytes. Results are dropped whole from the end once the budget is reached (never cut mid-result), followed by a note like '(3 more results omitted)'. The first result is always included. Omit for no limit."
+    )]
+    pub max_output_bytes: Option<usize>,
+
+    #[schemars(
+        description = "When true, sorts results by location ID (ties keep Brave's relevance order) so repeated identical queries produce byte-identical output, which helps caching and snapshot tests. Defaults to false (Brave's relevance order)."
+    )]
+    pub stable_sort: Option<bool>,
+
+    #[schemars(
+        description = "Output format: 'text' (labeled lines), 'json' (an array of result objects), 'markdown' (linked titles), or 'compact' (one 'title | host | url' line per result, titles cut to the server's width). Omit to use the server's default format (text unless configured). Numbering, max_output_bytes, and notes do not apply to json."
+    )]
+    pub output_format: Option<OutputFormat>,
+
+    #[schemars(
+        description = "When true, indents the places (with their ratings and hours) of output_format 'json' across lines for human readers. Defaults to false: single-line JSON, which costs fewer tokens. Ignored for other formats."
+    )]
+    pub pretty: Option<bool>,
+
+    #[schemars(
+        description = "Extra query parameters sent verbatim with the search that finds the places (and its web fallback), not with the details and descriptions lookups; for API features this server does not model yet (e.g. {\"ui_lang\": \"en-US\"}). Names may use letters, digits, '_', '-', '.'; values must be non-empty. Parameters this tool sets itself (q, count, offset, ...), safesearch, and credential-like names are rejected."
+    )]
+    pub extra_params: Option<HashMap<String, String>>,
+
+    #[schemars(
+        description = "Optional MCP progress token. When set, the server sends notifications/progress for each stage of the search (1/3 searching locations, 2/3 fetching details, 3/3 fetching descriptions); stages that are not needed are skipped. Omit for no progress notifications.",
+        with = "Option<serde_json::Value>"
+    )]
+    pub progress_token: Option<ProgressToken>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, schemars::JsonSchema)]
+pub struct SuggestParams {
+    #[schemars(
+        description = "The partial query to complete, as the user has typed it so far (e.g. 'rust asy')."
+    )]
+    pub query: String,
+
+    #[schemars(
+        description = "Country code the suggestions should suit, e.g. 'US' or 'GB' (same codes as brave_news_search). Omit for Brave's default."
+    )]
+    pub country: Option<String>,
+
+    #[schemars(
+        description = "Language of the suggestions, e.g. 'en' or 'de' (same codes as brave_news_search). Omit for Brave's default."
+    )]
+    pub search_lang: Option<String>,
+
+    #[schemars(
+        description = "Number of suggestions to return, between 1-20 (default 5). Larger values are capped at 20."
+    )]
+    pub count: Option<usize>,
+}
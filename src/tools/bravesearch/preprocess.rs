@@ -0,0 +1,133 @@
+// Deployment-specific rewriting of search queries.
+//
+// Every web, news, local, and suggest query passes through the router's
+// preprocessor just before its request URL is built (after `operators` are applied to web
+// queries), so a deployment can add its own rules, such as always excluding a
+// site, without clients having to repeat them.
+
+/// Hook for rewriting queries before they are sent to Brave
+pub trait QueryPreprocessor: Send + Sync {
+    fn process(&self, query: &str) -> String;
+}
+
+/// The default preprocessor, which leaves queries untouched
+pub struct IdentityPreprocessor;
+
+impl QueryPreprocessor for IdentityPreprocessor {
+    fn process(&self, query: &str) -> String {
+        query.to_string()
+    }
+}
+
+/// Restricts every query to one site (`site:docs.rs`) or excludes one
+/// (`-site:pinterest.com`). Queries that already carry the operator are left as they are.
+pub struct SiteRestriction {
+    operator: String,
+}
+
+impl SiteRestriction {
+    /// Only return results from `domain`
+    pub fn only(domain: &str) -> Self {
+        Self {
+            operator: format!("site:{}", domain),
+        }
+    }
+
+    /// Never return results from `domain`
+    pub fn exclude(domain: &str) -> Self {
+        Self {
+            operator: format!("-site:{}", domain),
+        }
+    }
+}
+
+impl QueryPreprocessor for SiteRestriction {
+    fn process(&self, query: &str) -> String {
+        let present = query
+            .split_whitespace()
+            .any(|word| word.eq_ignore_ascii_case(&self.operator));
+        if present {
+            query.to_string()
+        } else {
+            format!("{} {}", query.trim_end(), self.operator)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_fixtures::router_for;
+    use super::super::{NewsSearchParams, WebSearchParams};
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_site_restriction_appends_operator_once() {
+        let exclude = SiteRestriction::exclude("pinterest.com");
+        assert_eq!(
+            exclude.process("kitchen ideas "),
+            "kitchen ideas -site:pinterest.com"
+        );
+        assert_eq!(
+            exclude.process("kitchen -site:Pinterest.com ideas"),
+            "kitchen -site:Pinterest.com ideas"
+        );
+
+        let only = SiteRestriction::only("docs.rs");
+        assert_eq!(only.process("tokio select"), "tokio select site:docs.rs");
+        assert_eq!(IdentityPreprocessor.process(" as is "), " as is ");
+    }
+
+    // Appends an exclusion the way a deployment-specific preprocessor would
+    struct ExcludePinterest;
+
+    impl QueryPreprocessor for ExcludePinterest {
+        fn process(&self, query: &str) -> String {
+            format!("{} -site:pinterest.com", query)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_preprocessed_query_is_sent() {
+        let mut server = mockito::Server::new_async().await;
+        let web = server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "q".into(),
+                "kitchen ideas -site:pinterest.com".into(),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"type":"search","web":{"results":[]}}"#)
+            .create_async()
+            .await;
+        let news = server
+            .mock("GET", "/res/v1/news/search")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "q".into(),
+                "kitchen ideas -site:pinterest.com".into(),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"type":"news","results":[]}"#)
+            .create_async()
+            .await;
+
+        let router = router_for(&server).with_query_preprocessor(Arc::new(ExcludePinterest));
+
+        let result = router
+            .brave_web_search(WebSearchParams {
+                query: "kitchen ideas".to_string(),
+                ..Default::default()
+            })
+            .await;
+        assert!(!result.starts_with("Error"), "{}", result);
+        let result = router
+            .brave_news_search(NewsSearchParams {
+                query: "kitchen ideas".to_string(),
+                ..Default::default()
+            })
+            .await;
+        assert!(!result.starts_with("Error"), "{}", result);
+        web.assert_async().await;
+        news.assert_async().await;
+    }
+}
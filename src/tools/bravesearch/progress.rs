@@ -0,0 +1,260 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use rmcp::model::{
+    LoggingLevel, LoggingMessageNotificationParam, ProgressNotificationParam, ProgressToken,
+};
+use rmcp::service::Peer;
+use rmcp::RoleServer;
+
+// MCP progress notifications for multi-stage tools.
+//
+// Notifications are only sent when the call carries a progress token and the
+// session has a peer; otherwise every report is a no-op.
+//
+// Rate limit waits happen deep in the shared request path, which knows
+// nothing of the call it serves. A tool with a progress token runs under its
+// reporter (`scope`), and `report_wait` finds it in a task-local.
+
+// Logger name of the logging notifications that carry partial results
+const PARTIAL_LOGGER_NAME: &str = "bravesearch/partial";
+// Logger name of the logging notifications that explain a rate limit wait
+const WAIT_LOGGER_NAME: &str = "bravesearch/wait";
+
+tokio::task_local! {
+    static CALL_PROGRESS: ProgressReporter;
+}
+
+/// Tell the client of the tool call running on this task that it is held back for `delay` by
+/// rate limiting, so an interactive client can show that it is waiting rather than hung
+pub(crate) async fn report_wait(delay: Duration) {
+    if delay.is_zero() {
+        return;
+    }
+    tracing::debug!("Rate limited, waiting {:?}", delay);
+    if let Ok(progress) = CALL_PROGRESS.try_with(ProgressReporter::clone) {
+        progress.waiting(delay).await;
+    }
+}
+
+/// Clones share the progress reported so far
+#[derive(Clone)]
+pub(crate) struct ProgressReporter {
+    target: Option<(Peer<RoleServer>, ProgressToken)>,
+    total: u32,
+    reported: Arc<AtomicU32>,
+}
+
+impl ProgressReporter {
+    pub(crate) fn new(
+        peer: Option<Peer<RoleServer>>,
+        token: Option<ProgressToken>,
+        total: u32,
+    ) -> Self {
+        Self {
+            target: peer.zip(token),
+            total,
+            reported: Arc::new(AtomicU32::new(0)),
+        }
+    }
+
+    /// Run `call` with this reporter receiving its rate limit waits. Without a token the
+    /// enclosing reporter, if any, keeps them, so a local search's web fallback still reports.
+    pub(crate) async fn scope<F: Future>(&self, call: F) -> F::Output {
+        if self.target.is_none() {
+            return call.await;
+        }
+        CALL_PROGRESS.scope(self.clone(), call).await
+    }
+
+    /// Report that stage `stage` (1-based) of `total` has started
+    pub(crate) async fn stage(&self, stage: u32, label: &str) {
+        let Some((peer, token)) = &self.target else {
+            return;
+        };
+        tracing::debug!("Progress {}/{}: {}", stage, self.total, label);
+        self.reported.fetch_max(stage, Ordering::Relaxed);
+        Self::notify(peer, token, stage, self.total).await;
+    }
+
+    /// Report a wait of `delay`, repeating the progress so far (waiting is not progress) and
+    /// sending the reason in a logging notification tagged with the progress token
+    async fn waiting(&self, delay: Duration) {
+        let Some((peer, token)) = &self.target else {
+            return;
+        };
+        let progress = self.reported.load(Ordering::Relaxed);
+        Self::notify(peer, token, progress, self.total).await;
+
+        let params = LoggingMessageNotificationParam {
+            level: LoggingLevel::Info,
+            logger: Some(WAIT_LOGGER_NAME.to_string()),
+            data: serde_json::json!({
+                "progressToken": token,
+                "progress": progress,
+                "message": format!("Rate limited, waiting {}ms", delay.as_millis()),
+            }),
+        };
+        if let Err(e) = peer.notify_logging_message(params).await {
+            tracing::debug!("Failed to send rate limit wait: {}", e);
+        }
+    }
+
+    async fn notify(peer: &Peer<RoleServer>, token: &ProgressToken, progress: u32, total: u32) {
+        let params = ProgressNotificationParam {
+            progress_token: token.clone(),
+            progress,
+            total: Some(total),
+        };
+        // Progress is best-effort; a failed send must not fail the search
+        if let Err(e) = peer.notify_progress(params).await {
+            tracing::debug!("Failed to send progress notification: {}", e);
+        }
+    }
+
+    /// Report result `progress` (1-based) of `total`, delivering its formatted `content` in a
+    /// logging notification tagged with the progress token.
+    ///
+    /// Partials are sent whatever the client's logging level, since the progress token opts in.
+    pub(crate) async fn partial(&self, progress: u32, content: String) {
+        let Some((peer, token)) = &self.target else {
+            return;
+        };
+        self.stage(progress, "partial result").await;
+
+        let params = LoggingMessageNotificationParam {
+            level: LoggingLevel::Info,
+            logger: Some(PARTIAL_LOGGER_NAME.to_string()),
+            data: serde_json::json!({
+                "progressToken": token,
+                "progress": progress,
+                "content": content,
+            }),
+        };
+        if let Err(e) = peer.notify_logging_message(params).await {
+            tracing::debug!("Failed to send partial result: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_client::{notifications, TestClient};
+    use super::super::test_fixtures::{json_mock, router_for};
+    use super::super::{RateLimitBehavior, RateLimiterConfig, RATE_LIMIT_PER_MONTH};
+    use serde_json::json;
+
+    async fn mock_local_pipeline(server: &mut mockito::Server) -> Vec<mockito::Mock> {
+        let mut mocks = Vec::new();
+        // Searches with open_now go through the details and descriptions lookups
+        for (path, body) in [
+            (
+                "/res/v1/web/search",
+                r#"{"type":"search","locations":{"results":[{"id":"loc1","title":"Corner Cafe"}]}}"#,
+            ),
+            (
+                "/res/v1/local/pois",
+                r#"{"results":[{"id":"loc1","name":"Corner Cafe","opening_hours":["Open 24 hours"]}]}"#,
+            ),
+            (
+                "/res/v1/local/descriptions",
+                r#"{"descriptions":{"loc1":"Cozy"}}"#,
+            ),
+        ] {
+            let mock = json_mock(server, path, body).create_async().await;
+            mocks.push(mock);
+        }
+        mocks
+    }
+
+    #[tokio::test]
+    async fn test_local_search_reports_each_stage() {
+        let mut server = mockito::Server::new_async().await;
+        let _mocks = mock_local_pipeline(&mut server).await;
+        let router = router_for(&server);
+
+        let mut client = TestClient::start(router).await;
+        let messages = client
+            .call_tool(
+                2,
+                "brave_local_search",
+                json!({"query": "cafe near me", "open_now": true, "progress_token": "local-1"}),
+            )
+            .await;
+
+        let progress = notifications(&messages, "notifications/progress");
+        assert_eq!(
+            progress,
+            vec![
+                &json!({"progressToken": "local-1", "progress": 1, "total": 3}),
+                &json!({"progressToken": "local-1", "progress": 2, "total": 3}),
+                &json!({"progressToken": "local-1", "progress": 3, "total": 3}),
+            ]
+        );
+        let response = messages.last().unwrap();
+        let text = response["result"]["content"][0]["text"].as_str().unwrap();
+        assert!(text.contains("Name: Corner Cafe"), "{}", text);
+        assert!(text.contains("Description: Cozy"), "{}", text);
+    }
+
+    #[tokio::test]
+    async fn test_no_progress_without_token() {
+        let mut server = mockito::Server::new_async().await;
+        let _mocks = mock_local_pipeline(&mut server).await;
+        let router = router_for(&server);
+
+        let mut client = TestClient::start(router).await;
+        let messages = client
+            .call_tool(
+                2,
+                "brave_local_search",
+                json!({"query": "cafe near me", "open_now": true}),
+            )
+            .await;
+        assert!(notifications(&messages, "notifications/progress").is_empty());
+        assert!(messages.last().unwrap()["result"].is_object());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_wait_is_reported_as_progress() {
+        let mut server = mockito::Server::new_async().await;
+        let _mocks = mock_local_pipeline(&mut server).await;
+        let router = router_for(&server)
+            .with_rate_limit_config(RateLimiterConfig {
+                per_second: 1,
+                per_month: RATE_LIMIT_PER_MONTH,
+            })
+            .unwrap()
+            .with_rate_limit_behavior(RateLimitBehavior::Wait);
+
+        let mut client = TestClient::start(router).await;
+        let messages = client
+            .call_tool(
+                2,
+                "brave_local_search",
+                json!({"query": "cafe near me", "open_now": true, "progress_token": "local-1"}),
+            )
+            .await;
+
+        // The details and descriptions lookups each wait for the next window, repeating the
+        // stage they are in rather than moving progress forward
+        let progress = notifications(&messages, "notifications/progress")
+            .into_iter()
+            .map(|params| params["progress"].as_u64().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(progress, vec![1, 2, 2, 3, 3]);
+        let waits = notifications(&messages, "notifications/message")
+            .into_iter()
+            .filter(|params| params["logger"] == "bravesearch/wait")
+            .collect::<Vec<_>>();
+        assert_eq!(waits.len(), 2, "{:?}", messages);
+        assert_eq!(waits[0]["data"]["progressToken"], "local-1");
+        assert!(waits[0]["data"]["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("Rate limited, waiting"));
+        assert!(messages.last().unwrap()["result"].is_object());
+    }
+}
@@ -0,0 +1,249 @@
+use anyhow::{anyhow, Result};
+
+use super::params::QueryOperators;
+
+// Construction of search operators (`site:`, `filetype:`, ...).
+//
+// Operators are validated and quoted here rather than trusted from free
+// text, so a stray space or quote cannot silently turn an operator into a
+// plain search word.
+
+/// One operator appended to the query
+#[derive(Debug, Clone)]
+enum Operator {
+    Site(String),
+    FileType(String),
+    InTitle(String),
+    ExactPhrase(String),
+    Exclude(String),
+}
+
+/// Builds a query string from free text plus validated search operators.
+///
+/// ```
+/// use bravesearch_mcp::QueryBuilder;
+///
+/// let query = QueryBuilder::new("async runtime")
+///     .site("docs.rs")
+///     .exclude("tutorial")
+///     .build()
+///     .unwrap();
+/// assert_eq!(query, "async runtime site:docs.rs -tutorial");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct QueryBuilder {
+    text: String,
+    operators: Vec<Operator>,
+}
+
+impl QueryBuilder {
+    /// Start from the free-text part of the query, which may be empty
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            operators: Vec::new(),
+        }
+    }
+
+    /// Restrict results to a domain such as `example.com` (`site:example.com`)
+    pub fn site(mut self, domain: impl Into<String>) -> Self {
+        self.operators.push(Operator::Site(domain.into()));
+        self
+    }
+
+    /// Restrict results to a file extension such as `pdf` (`filetype:pdf`)
+    pub fn filetype(mut self, extension: impl Into<String>) -> Self {
+        self.operators.push(Operator::FileType(extension.into()));
+        self
+    }
+
+    /// Require text in the page title (`intitle:word`, quoted when it has spaces)
+    pub fn intitle(mut self, text: impl Into<String>) -> Self {
+        self.operators.push(Operator::InTitle(text.into()));
+        self
+    }
+
+    /// Require an exact phrase (`"the phrase"`)
+    pub fn exact_phrase(mut self, phrase: impl Into<String>) -> Self {
+        self.operators.push(Operator::ExactPhrase(phrase.into()));
+        self
+    }
+
+    /// Exclude results containing a word or phrase (`-word`, `-"two words"`)
+    pub fn exclude(mut self, term: impl Into<String>) -> Self {
+        self.operators.push(Operator::Exclude(term.into()));
+        self
+    }
+
+    /// The query string, or an error naming the first invalid operator
+    pub fn build(&self) -> Result<String> {
+        let mut parts = Vec::with_capacity(self.operators.len() + 1);
+        let text = self.text.trim();
+        if !text.is_empty() {
+            parts.push(text.to_string());
+        }
+        for operator in &self.operators {
+            parts.push(render(operator)?);
+        }
+        if parts.is_empty() {
+            return Err(anyhow!("query is empty"));
+        }
+        Ok(parts.join(" "))
+    }
+}
+
+fn render(operator: &Operator) -> Result<String> {
+    match operator {
+        Operator::Site(domain) => {
+            let domain = domain.trim().trim_end_matches('/');
+            let valid = domain.contains('.')
+                && !domain.starts_with(['.', '-'])
+                && domain
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '.' || c == '-');
+            if !valid {
+                return Err(anyhow!(
+                    "site must be a domain such as example.com, got {:?}",
+                    domain
+                ));
+            }
+            Ok(format!("site:{}", domain.to_lowercase()))
+        }
+        Operator::FileType(extension) => {
+            let extension = extension.trim().trim_start_matches('.');
+            if extension.is_empty()
+                || extension.len() > 10
+                || !extension.chars().all(|c| c.is_ascii_alphanumeric())
+            {
+                return Err(anyhow!(
+                    "filetype must be a file extension such as pdf, got {:?}",
+                    extension
+                ));
+            }
+            Ok(format!("filetype:{}", extension.to_ascii_lowercase()))
+        }
+        Operator::InTitle(text) => Ok(format!("intitle:{}", term("intitle", text)?)),
+        Operator::ExactPhrase(phrase) => {
+            let phrase = plain_text("exact_phrase", phrase)?;
+            Ok(format!("\"{}\"", phrase))
+        }
+        Operator::Exclude(text) => {
+            if text.trim_start().starts_with('-') {
+                return Err(anyhow!("exclude terms are given without a leading '-'"));
+            }
+            Ok(format!("-{}", term("exclude", text)?))
+        }
+    }
+}
+
+/// `text` as a single search term, quoted when it spans several words
+fn term(name: &str, text: &str) -> Result<String> {
+    let text = plain_text(name, text)?;
+    if text.contains(char::is_whitespace) {
+        Ok(format!("\"{}\"", text))
+    } else {
+        Ok(text.to_string())
+    }
+}
+
+/// `text` trimmed, rejecting empty values and quotes that would unbalance the query
+fn plain_text<'a>(name: &str, text: &'a str) -> Result<&'a str> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(anyhow!("{} must not be empty", name));
+    }
+    if text.contains('"') {
+        return Err(anyhow!("{} must not contain '\"'", name));
+    }
+    Ok(text)
+}
+
+impl QueryOperators {
+    /// `query` with these operators appended
+    pub(crate) fn apply(&self, query: &str) -> Result<String> {
+        let mut builder = QueryBuilder::new(query);
+        if let Some(site) = &self.site {
+            builder = builder.site(site.as_str());
+        }
+        if let Some(filetype) = &self.filetype {
+            builder = builder.filetype(filetype.as_str());
+        }
+        if let Some(intitle) = &self.intitle {
+            builder = builder.intitle(intitle.as_str());
+        }
+        if let Some(phrase) = &self.exact_phrase {
+            builder = builder.exact_phrase(phrase.as_str());
+        }
+        for term in self.exclude.iter().flatten() {
+            builder = builder.exclude(term.as_str());
+        }
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn one(builder: QueryBuilder) -> String {
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn test_each_operator() {
+        let base = || QueryBuilder::new("rust");
+        assert_eq!(one(base().site(" Docs.rs/ ")), "rust site:docs.rs");
+        assert_eq!(one(base().filetype(".PDF")), "rust filetype:pdf");
+        assert_eq!(one(base().intitle("async")), "rust intitle:async");
+        assert_eq!(one(base().intitle("async io")), "rust intitle:\"async io\"");
+        assert_eq!(one(base().exact_phrase("zero cost")), "rust \"zero cost\"");
+        assert_eq!(one(base().exclude("game")), "rust -game");
+        assert_eq!(one(base().exclude("video game")), "rust -\"video game\"");
+    }
+
+    #[test]
+    fn test_invalid_operators_are_rejected() {
+        let base = || QueryBuilder::new("rust");
+        for builder in [
+            base().site("https://example.com"),
+            base().site("not a domain"),
+            base().filetype("p d f"),
+            base().intitle(" "),
+            base().exact_phrase("say \"hi\""),
+            base().exclude("-game"),
+            QueryBuilder::new("  "),
+        ] {
+            assert!(builder.build().is_err(), "{:?}", builder);
+        }
+        let err = base().filetype("tar.gz").build().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "filetype must be a file extension such as pdf, got \"tar.gz\""
+        );
+    }
+
+    #[test]
+    fn test_operators_combine_in_order() {
+        let operators = QueryOperators {
+            site: Some("github.com".to_string()),
+            filetype: Some("md".to_string()),
+            intitle: Some("readme".to_string()),
+            exact_phrase: Some("getting started".to_string()),
+            exclude: Some(vec!["archived".to_string(), "fork".to_string()]),
+        };
+        assert_eq!(
+            operators.apply("tokio").unwrap(),
+            "tokio site:github.com filetype:md intitle:readme \"getting started\" -archived -fork"
+        );
+        // Operators alone make a valid query
+        assert_eq!(
+            QueryOperators {
+                site: Some("docs.rs".to_string()),
+                ..Default::default()
+            }
+            .apply("")
+            .unwrap(),
+            "site:docs.rs"
+        );
+    }
+}
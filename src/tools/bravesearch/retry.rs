@@ -0,0 +1,185 @@
+use std::time::Duration;
+
+use rand::Rng;
+
+// Retry policy for transient Brave API failures.
+//
+// Delays grow exponentially from the base delay, and each one is jittered by
+// up to 25% either way so many server instances do not retry in lockstep.
+
+/// Default retries of a transient API failure: none, so retrying is opt-in
+pub const DEFAULT_MAX_RETRIES: u32 = 0;
+/// Default base retry backoff in milliseconds, doubled per retry
+pub const DEFAULT_RETRY_BASE_MS: u64 = 500;
+
+// Longest wait before any retry; larger delays (a huge base delay, or many doublings) are cut
+// to this rather than overflowing `Duration`
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(10 * 60);
+
+// Fraction of each delay that jitter may add or remove
+const JITTER_RATIO: f64 = 0.25;
+
+// Maintenance windows outlast ordinary 5xx blips, so their retries back off this much longer
+const MAINTENANCE_BACKOFF_FACTOR: u32 = 4;
+
+// Wait before re-sending a search that came back empty (`retry_on_empty`); just over a second,
+// so the retry is admitted by the default one-request-per-second limit
+pub(crate) const DEFAULT_EMPTY_RETRY_DELAY_MS: u64 = 1100;
+
+pub(crate) const MAINTENANCE_MESSAGE: &str = "Brave API is under maintenance, retry later";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RetryPolicy {
+    pub(crate) max_retries: u32,
+    pub(crate) base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: DEFAULT_MAX_RETRIES,
+            base_delay: Duration::from_millis(DEFAULT_RETRY_BASE_MS),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Un-jittered delay before retry number `attempt` (0-based): base * 2^attempt, at most
+    /// `MAX_RETRY_DELAY`
+    pub(crate) fn nominal_delay(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.min(16)))
+            .min(MAX_RETRY_DELAY)
+    }
+
+    /// Delay before retry number `attempt`, jittered by up to ±25% and at most `MAX_RETRY_DELAY`
+    pub(crate) fn delay(&self, attempt: u32, rng: &mut impl Rng) -> Duration {
+        let jitter = rng.gen_range(-JITTER_RATIO..=JITTER_RATIO);
+        // The capped nominal delay is small enough that scaling it cannot overflow
+        self.nominal_delay(attempt)
+            .mul_f64(1.0 + jitter)
+            .min(MAX_RETRY_DELAY)
+    }
+
+    /// Delay before retrying a maintenance response: the normal delay times four, at most
+    /// `MAX_RETRY_DELAY`
+    pub(crate) fn maintenance_delay(&self, attempt: u32, rng: &mut impl Rng) -> Duration {
+        self.delay(attempt, rng)
+            .saturating_mul(MAINTENANCE_BACKOFF_FACTOR)
+            .min(MAX_RETRY_DELAY)
+    }
+}
+
+/// Whether a 503 body is Brave's maintenance response rather than a generic outage
+pub(crate) fn is_maintenance_body(body: &str) -> bool {
+    body.to_lowercase().contains("maintenance")
+}
+
+/// Whether requests with `method` may be retried: sending them twice must be harmless, so
+/// bodies such as a future POST endpoint's are never resent
+pub(crate) fn is_idempotent(method: &reqwest::Method) -> bool {
+    matches!(
+        *method,
+        reqwest::Method::GET
+            | reqwest::Method::HEAD
+            | reqwest::Method::OPTIONS
+            | reqwest::Method::PUT
+            | reqwest::Method::DELETE
+    )
+}
+
+/// Whether a response status is worth retrying (rate limiting and transient server errors)
+pub(crate) fn is_retryable_status(status: reqwest::StatusCode) -> bool {
+    matches!(status.as_u16(), 429 | 500 | 502 | 503 | 504)
+}
+
+/// Whether a transport error is worth retrying (the request never got a response)
+pub(crate) fn is_retryable_error(error: &reqwest::Error) -> bool {
+    error.is_connect() || error.is_timeout()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delays_stay_within_jitter_bounds() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            base_delay: Duration::from_millis(200),
+        };
+        let mut rng = rand::thread_rng();
+
+        for attempt in 0..5 {
+            let nominal = policy.nominal_delay(attempt);
+            assert_eq!(nominal, Duration::from_millis(200 * 2u64.pow(attempt)));
+
+            let (low, high) = (nominal.mul_f64(0.75), nominal.mul_f64(1.25));
+            for _ in 0..1000 {
+                let delay = policy.delay(attempt, &mut rng);
+                assert!(
+                    delay >= low && delay <= high,
+                    "attempt {}: {:?} outside {:?}..={:?}",
+                    attempt,
+                    delay,
+                    low,
+                    high
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_huge_delays_are_capped() {
+        let policy = RetryPolicy {
+            max_retries: 20,
+            base_delay: Duration::from_millis(u64::MAX),
+        };
+        let mut rng = rand::thread_rng();
+
+        assert_eq!(policy.nominal_delay(20), MAX_RETRY_DELAY);
+        for _ in 0..1000 {
+            assert!(policy.delay(20, &mut rng) <= MAX_RETRY_DELAY);
+            assert!(policy.maintenance_delay(20, &mut rng) <= MAX_RETRY_DELAY);
+        }
+
+        // Many doublings of an ordinary base reach the cap too
+        let policy = RetryPolicy {
+            max_retries: 30,
+            base_delay: Duration::from_millis(DEFAULT_RETRY_BASE_MS),
+        };
+        assert_eq!(policy.nominal_delay(30), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn test_maintenance_backs_off_longer() {
+        let policy = RetryPolicy {
+            max_retries: 1,
+            base_delay: Duration::from_millis(200),
+        };
+        let mut rng = rand::thread_rng();
+        // The shortest maintenance delay exceeds the longest normal one
+        for _ in 0..1000 {
+            assert!(policy.maintenance_delay(0, &mut rng) > Duration::from_millis(250));
+        }
+
+        assert!(is_maintenance_body(
+            r#"{"type":"ErrorResponse","error":{"detail":"Down for scheduled Maintenance"}}"#
+        ));
+        assert!(!is_maintenance_body("Service Unavailable"));
+    }
+
+    #[test]
+    fn test_retryable_statuses() {
+        for code in [429, 500, 502, 503, 504] {
+            assert!(is_retryable_status(
+                reqwest::StatusCode::from_u16(code).unwrap()
+            ));
+        }
+        for code in [400, 401, 403, 404, 422] {
+            assert!(!is_retryable_status(
+                reqwest::StatusCode::from_u16(code).unwrap()
+            ));
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::fmt;
+
+use super::{BraveSearchResponse, BraveSearchRouter, RateLimiter, WebSearchParams};
+
+// Canned web search response used to verify response parsing without network access
+const CANNED_WEB_RESPONSE: &str = r#"{
+    "type": "search",
+    "web": {
+        "results": [
+            {
+                "title": "Brave Search",
+                "description": "Search the web privately.",
+                "url": "https://search.brave.com/"
+            }
+        ]
+    }
+}"#;
+
+/// Outcome of a single self-test check
+#[derive(Debug, Clone)]
+pub struct SelfTestCheck {
+    pub name: &'static str,
+    pub passed: bool,
+    pub detail: String,
+}
+
+impl SelfTestCheck {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            passed: true,
+            detail: detail.into(),
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            passed: false,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Pass/fail report produced by `BraveSearchRouter::run_self_test`
+#[derive(Debug, Clone, Default)]
+pub struct SelfTestReport {
+    pub checks: Vec<SelfTestCheck>,
+}
+
+impl SelfTestReport {
+    /// True when every check passed
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+
+    /// Names of the checks that failed
+    pub fn failures(&self) -> Vec<&'static str> {
+        self.checks
+            .iter()
+            .filter(|check| !check.passed)
+            .map(|check| check.name)
+            .collect()
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            let status = if check.passed { "PASS" } else { "FAIL" };
+            writeln!(f, "[{}] {}: {}", status, check.name, check.detail)?;
+        }
+        let passed = self.checks.iter().filter(|check| check.passed).count();
+        write!(f, "{}/{} checks passed", passed, self.checks.len())
+    }
+}
+
+/// Parse the canned response and confirm the expected web result comes through
+fn check_canned_response() -> SelfTestCheck {
+    const NAME: &str = "response parsing";
+
+    match serde_json::from_str::<BraveSearchResponse>(CANNED_WEB_RESPONSE) {
+        Ok(data) => {
+            let results = data.web.unwrap_or_default().results;
+            match results.first() {
+                Some(result) if results.len() == 1 && result.title == "Brave Search" => {
+                    SelfTestCheck::pass(NAME, "canned web response parsed")
+                }
+                _ => SelfTestCheck::fail(
+                    NAME,
+                    format!("expected 1 web result, parsed {}", results.len()),
+                ),
+            }
+        }
+        Err(e) => SelfTestCheck::fail(NAME, format!("failed to parse canned response: {}", e)),
+    }
+}
+
+/// Confirm a fresh limiter admits a request and then rejects one over the per-second limit
+async fn check_rate_limiter() -> SelfTestCheck {
+    const NAME: &str = "rate limiter";
+
+    let limiter = RateLimiter::with_limits(1, 10);
+    if let Err(e) = limiter.check_rate_limit().await {
+        return SelfTestCheck::fail(NAME, format!("first request was rejected: {}", e));
+    }
+    match limiter.check_rate_limit().await {
+        Err(_) => SelfTestCheck::pass(NAME, "requests over the per-second limit are rejected"),
+        Ok(_) => SelfTestCheck::fail(NAME, "second request within one second was admitted"),
+    }
+}
+
+impl BraveSearchRouter {
+    /// Run offline checks followed by one minimal live request to validate the API key
+    pub async fn run_self_test(&self) -> SelfTestReport {
+        let mut checks = vec![check_canned_response(), check_rate_limiter().await];
+
+        let params = WebSearchParams {
+            query: "brave search".to_string(),
+            count: Some(1),
+            ..Default::default()
+        };
+        checks.push(match self.perform_web_search(&params).await {
+            Ok(_) => SelfTestCheck::pass("api key", "minimal web search succeeded"),
+            Err(e) => SelfTestCheck::fail("api key", format!("web search failed: {}", e)),
+        });
+
+        SelfTestReport { checks }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canned_response_check_passes() {
+        let check = check_canned_response();
+        assert!(check.passed, "{}", check.detail);
+    }
+
+    #[tokio::test]
+    async fn test_report_names_failed_check() {
+        // An unreachable API host fails only the live check
+        let router = BraveSearchRouter::new("test_key".to_string())
+            .with_base_url("http://127.0.0.1:1".to_string());
+        let report = router.run_self_test().await;
+
+        assert!(!report.passed());
+        assert_eq!(report.failures(), vec!["api key"]);
+        assert!(report.to_string().contains("[FAIL] api key"));
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use super::{BraveSearchRouter, RateLimitBehavior};
+
+// Effective runtime configuration for `brave_server_config`.
+//
+// Every value is read from the router itself rather than from the CLI, so
+// library users and operators see what the server is actually enforcing.
+// The API key is always redacted.
+
+const REDACTED: &str = "[redacted]";
+
+// Tools that refuse to run unless `with_admin_tools(true)`
+const ADMIN_TOOLS: &[&str] = &["brave_cache_clear"];
+
+/// The router's effective configuration, as reported by `brave_server_config`
+#[derive(Debug, Serialize)]
+pub(crate) struct ServerConfig {
+    api_key: &'static str,
+    /// Whether answers come from canned fixtures instead of Brave
+    demo: bool,
+    base_url: String,
+    /// Names of the `with_custom_header` headers; values are left out like the key
+    custom_headers: Vec<String>,
+    enabled_tools: Vec<String>,
+    rate_limits: RateLimitConfig,
+    /// Per-request timeout; None means reqwest's default of no timeout
+    request_timeout_secs: Option<u64>,
+    retries: RetryConfig,
+    min_request_interval_ms: Option<u128>,
+    forced_safesearch: Option<String>,
+    cache: CacheConfig,
+    default_news_freshness: Option<String>,
+    deep_pagination_threshold: Option<usize>,
+    highlight_marker: String,
+}
+
+#[derive(Debug, Serialize)]
+struct RateLimitConfig {
+    per_second: usize,
+    /// Whether a request over the per-second limit fails or waits
+    behavior: RateLimitBehavior,
+    per_month: usize,
+    used_this_month: usize,
+    /// Minimum gap between "monthly quota exhausted" logs
+    quota_log_interval_secs: u64,
+    tool_budgets: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct RetryConfig {
+    max_retries: u32,
+    base_delay_ms: u128,
+    /// Delay before the single retry of an empty search (`retry_on_empty`)
+    empty_retry_delay_ms: u128,
+}
+
+#[derive(Debug, Serialize)]
+struct CacheConfig {
+    enabled: bool,
+    ttl_secs: Option<u64>,
+    negative_ttl_secs: Option<u64>,
+}
+
+impl BraveSearchRouter {
+    /// Snapshot of the effective configuration, with the API key redacted
+    pub(crate) async fn server_config(&self) -> ServerConfig {
+        let (per_second, per_month, used_this_month) = self.rate_limiter.usage().await;
+        let mut enabled_tools = Self::tool_box()
+            .map
+            .keys()
+            .filter(|name| self.admin_tools || !ADMIN_TOOLS.contains(&name.as_ref()))
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        enabled_tools.sort();
+
+        ServerConfig {
+            api_key: REDACTED,
+            demo: self.demo,
+            base_url: self.base_url.clone(),
+            custom_headers: self
+                .custom_headers
+                .keys()
+                .map(|name| name.to_string())
+                .collect(),
+            enabled_tools,
+            rate_limits: RateLimitConfig {
+                per_second,
+                behavior: self.rate_limit_behavior,
+                per_month,
+                used_this_month,
+                quota_log_interval_secs: self.quota_log.interval().as_secs(),
+                tool_budgets: self
+                    .rate_limiter
+                    .tool_budgets
+                    .iter()
+                    .map(|(tool, budget)| (tool.clone(), *budget))
+                    .collect(),
+            },
+            // The client is built without a timeout
+            request_timeout_secs: None,
+            retries: RetryConfig {
+                max_retries: self.retry_policy.max_retries,
+                base_delay_ms: self.retry_policy.base_delay.as_millis(),
+                empty_retry_delay_ms: self.empty_retry_delay.as_millis(),
+            },
+            min_request_interval_ms: self
+                .pacer
+                .as_ref()
+                .map(|pacer| pacer.interval().as_millis()),
+            forced_safesearch: self.forced_safesearch.map(|level| level.to_string()),
+            cache: CacheConfig {
+                enabled: self.cache.is_some(),
+                ttl_secs: self
+                    .cache
+                    .as_ref()
+                    .and_then(|cache| cache.ttl())
+                    .map(|ttl| ttl.as_secs()),
+                negative_ttl_secs: self
+                    .cache
+                    .as_ref()
+                    .and_then(|cache| cache.negative_ttl())
+                    .map(|ttl| ttl.as_secs()),
+            },
+            default_news_freshness: self.default_news_freshness.clone(),
+            deep_pagination_threshold: self.deep_pagination_threshold,
+            highlight_marker: self.highlight_marker.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_config_reports_settings_and_redacts_key() {
+        let router = BraveSearchRouter::new("BSA-secret-key".to_string())
+            .with_base_url("https://gateway.example/".to_string())
+            .with_retries(3, Duration::from_millis(250))
+            .with_cache_ttl(Some(Duration::from_secs(60)))
+            .with_tool_budget("brave_local_search", 2000)
+            .unwrap()
+            .with_custom_header("X-Tenant-Id", "acme")
+            .unwrap();
+
+        let output = router.brave_server_config().await;
+        assert!(!output.contains("BSA-secret-key"), "{}", output);
+        assert!(!output.contains("acme"), "{}", output);
+
+        let config: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(config["api_key"], "[redacted]");
+        assert_eq!(config["base_url"], "https://gateway.example");
+        assert_eq!(config["custom_headers"], serde_json::json!(["x-tenant-id"]));
+        assert_eq!(config["retries"]["max_retries"], 3);
+        assert_eq!(config["retries"]["base_delay_ms"], 250);
+        assert_eq!(config["cache"]["ttl_secs"], 60);
+        assert_eq!(
+            config["cache"]["negative_ttl_secs"],
+            serde_json::Value::Null
+        );
+        assert_eq!(config["rate_limits"]["per_second"], 1);
+        assert_eq!(config["rate_limits"]["behavior"], "error");
+        assert_eq!(config["rate_limits"]["quota_log_interval_secs"], 60);
+        assert_eq!(
+            config["rate_limits"]["tool_budgets"]["brave_local_search"],
+            2000
+        );
+        assert!(config["enabled_tools"]
+            .as_array()
+            .unwrap()
+            .contains(&"brave_server_config".into()));
+    }
+}
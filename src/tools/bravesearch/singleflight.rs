@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use tokio::sync::watch;
+
+use super::cache::CacheKey;
+
+// Deduplication of identical in-flight API requests.
+//
+// The first caller for a key (the leader) makes the request; callers that
+// arrive while it is in flight wait for its outcome instead of sending their
+// own. Errors reach followers as their message only. If the leader is
+// cancelled before finishing, its followers each fall back to calling
+// themselves, so a dropped client never strands others.
+
+/// Outcome shared with followers; errors are passed on by message
+type Outcome = Option<Result<String, String>>;
+
+type Calls = Arc<Mutex<HashMap<CacheKey, watch::Receiver<Outcome>>>>;
+
+/// In-flight requests by key, shared by clones
+#[derive(Clone, Default)]
+pub(crate) struct SingleFlight {
+    calls: Calls,
+}
+
+/// Removes the leader's entry when it finishes or is dropped
+struct Lead {
+    key: CacheKey,
+    calls: Calls,
+}
+
+impl Drop for Lead {
+    fn drop(&mut self) {
+        self.calls.lock().unwrap().remove(&self.key);
+    }
+}
+
+impl SingleFlight {
+    /// Run `call` for `key`, or share the result of an identical call already in flight
+    pub(crate) async fn run<F, Fut>(&self, key: CacheKey, call: F) -> Result<String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<String>>,
+    {
+        let leader = {
+            let mut calls = self.calls.lock().unwrap();
+            match calls.get(&key) {
+                Some(outcome) => Err(outcome.clone()),
+                None => {
+                    let (tx, rx) = watch::channel(None);
+                    calls.insert(key.clone(), rx);
+                    Ok(tx)
+                }
+            }
+        };
+
+        match leader {
+            Ok(tx) => {
+                let _lead = Lead {
+                    key,
+                    calls: self.calls.clone(),
+                };
+                let result = call().await;
+                tx.send_replace(Some(match &result {
+                    Ok(body) => Ok(body.clone()),
+                    Err(e) => Err(e.to_string()),
+                }));
+                result
+            }
+            Err(mut outcome) => {
+                tracing::debug!("Sharing the result of an identical in-flight request");
+                // Cloned out at once, so the channel's read lock is not held across awaits
+                let shared = outcome
+                    .wait_for(Option::is_some)
+                    .await
+                    .map(|shared| shared.clone());
+                match shared {
+                    Ok(Some(Ok(body))) => Ok(body),
+                    Ok(Some(Err(message))) => Err(anyhow::Error::msg(message)),
+                    // The leader was dropped before finishing
+                    Ok(None) | Err(_) => call().await,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    fn key() -> CacheKey {
+        CacheKey::new("key", &"https://example.com/search?q=rust".parse().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_leader_lets_followers_call() {
+        let flight = SingleFlight::default();
+        let calls = &AtomicUsize::new(0);
+        let call = move || async move {
+            calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok("body".to_string())
+        };
+
+        let leader = flight.run(key(), call);
+        let follower = async {
+            // Let the leader register first, then cancel it by timing it out
+            tokio::task::yield_now().await;
+            flight.run(key(), call).await
+        };
+        let (leader, follower) = tokio::join!(
+            tokio::time::timeout(Duration::from_millis(10), leader),
+            follower
+        );
+        assert!(leader.is_err());
+        assert_eq!(follower.unwrap(), "body");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(flight.calls.lock().unwrap().is_empty());
+    }
+}
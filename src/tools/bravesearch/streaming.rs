@@ -0,0 +1,270 @@
+use anyhow::Result;
+
+use super::cache::CacheKey;
+use super::progress::ProgressReporter;
+use super::{decode_body, BraveSearchRouter, BraveWebResult};
+
+// Incremental delivery of web results while the response is still arriving.
+//
+// rmcp has no partial tool results, so each web result is sent as soon as
+// its JSON object is complete, as a progress notification paired with a
+// logging notification carrying the formatted result. The tool's final
+// response is unchanged and still built from the whole body, so sorting and
+// truncation only apply there.
+
+/// One level of JSON nesting seen by the scanner
+struct Frame {
+    array: bool,
+    /// Key this container is stored under in its parent object
+    label: Option<String>,
+    /// Object key whose value is being read
+    key: Option<String>,
+}
+
+/// Finds the complete objects of `web.results` in a JSON body fed in arbitrary chunks
+#[derive(Default)]
+pub(crate) struct WebResultScanner {
+    body: Vec<u8>,
+    scanned: usize,
+    stack: Vec<Frame>,
+    in_string: bool,
+    escaped: bool,
+    string_start: usize,
+    last_string: Option<String>,
+    result_start: Option<usize>,
+}
+
+impl WebResultScanner {
+    /// Append `chunk` and return the raw JSON of every web result it completes
+    pub(crate) fn feed(&mut self, chunk: &[u8]) -> Vec<Vec<u8>> {
+        self.body.extend_from_slice(chunk);
+        let mut results = Vec::new();
+        for i in self.scanned..self.body.len() {
+            let byte = self.body[i];
+            if self.in_string {
+                if self.escaped {
+                    self.escaped = false;
+                } else if byte == b'\\' {
+                    self.escaped = true;
+                } else if byte == b'"' {
+                    self.in_string = false;
+                    self.last_string = Some(
+                        String::from_utf8_lossy(&self.body[self.string_start..i]).into_owned(),
+                    );
+                }
+                continue;
+            }
+
+            match byte {
+                b'"' => {
+                    self.in_string = true;
+                    self.string_start = i + 1;
+                }
+                b':' => {
+                    if let Some(frame) = self.stack.last_mut().filter(|frame| !frame.array) {
+                        frame.key = self.last_string.take();
+                    }
+                }
+                b',' => {
+                    if let Some(frame) = self.stack.last_mut() {
+                        frame.key = None;
+                    }
+                }
+                b'{' | b'[' => {
+                    if byte == b'{' && self.in_web_results() {
+                        self.result_start = Some(i);
+                    }
+                    let label = self.stack.last().and_then(|frame| frame.key.clone());
+                    self.stack.push(Frame {
+                        array: byte == b'[',
+                        label,
+                        key: None,
+                    });
+                }
+                b'}' | b']' => {
+                    self.stack.pop();
+                    if byte == b'}' && self.in_web_results() {
+                        if let Some(start) = self.result_start.take() {
+                            results.push(self.body[start..=i].to_vec());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.scanned = self.body.len();
+        results
+    }
+
+    /// Whether the innermost container is the `results` array of the top-level `web` object
+    fn in_web_results(&self) -> bool {
+        match self.stack.as_slice() {
+            [_, web, results] => {
+                !web.array
+                    && web.label.as_deref() == Some("web")
+                    && results.array
+                    && results.label.as_deref() == Some("results")
+            }
+            _ => false,
+        }
+    }
+
+    /// Everything fed so far
+    pub(crate) fn into_body(self) -> Vec<u8> {
+        self.body
+    }
+}
+
+impl BraveSearchRouter {
+    /// Fetch a web search body like `send_request`, reporting each web result through
+    /// `progress` as soon as it has arrived, rendered with `render`
+    pub(crate) async fn send_request_streaming(
+        &self,
+        url: reqwest::Url,
+        progress: &ProgressReporter,
+        render: impl Fn(BraveWebResult) -> String,
+    ) -> Result<String> {
+        let key = CacheKey::new(&self.api_key, &url);
+        if let Some(cache) = &self.cache {
+            if let Some(body) = cache.get(&key).await {
+                return Ok(body);
+            }
+        }
+
+        // Shared with identical requests like `send_request`; a caller that joins a request
+        // already in flight gets its final body without partials, as on a cache hit
+        self.in_flight
+            .run(key.clone(), || async {
+                let mut response = match self.send_api_response(reqwest::Method::GET, url).await {
+                    Ok(response) => response,
+                    Err(e) => return self.serve_stale(&key, e).await,
+                };
+                let mut scanner = WebResultScanner::default();
+                let mut delivered = 0;
+                while let Some(chunk) = response.chunk().await? {
+                    for raw in scanner.feed(&chunk) {
+                        // A result the final parse would reject is left to that parse to report
+                        let Ok(result) = serde_json::from_slice::<BraveWebResult>(&raw) else {
+                            continue;
+                        };
+                        delivered += 1;
+                        progress.partial(delivered, render(result)).await;
+                    }
+                }
+
+                let body = decode_body(&scanner.into_body());
+                if let Some(cache) = &self.cache {
+                    cache.insert(key, body.clone()).await;
+                }
+                Ok(body)
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_client::{notifications, TestClient};
+    use super::super::test_fixtures::{json_mock, router_for};
+    use super::*;
+    use serde_json::json;
+
+    const BODY: &str = r#"{"type":"search","news":{"results":[{"title":"News"}]},"web":{"type":"search","results":[{"title":"A \"quoted\" {brace}","description":"d","url":"https://a.example","profile":{"name":"A"}},{"title":"B","description":"d","url":"https://b.example"}]},"locations":{"results":[{"id":"loc1"}]}}"#;
+
+    #[test]
+    fn test_scanner_finds_web_results_across_chunk_boundaries() {
+        for chunk_size in [1, 7, BODY.len()] {
+            let mut scanner = WebResultScanner::default();
+            let results = BODY
+                .as_bytes()
+                .chunks(chunk_size)
+                .flat_map(|chunk| scanner.feed(chunk))
+                .map(|raw| {
+                    serde_json::from_slice::<serde_json::Value>(&raw).unwrap()["url"].clone()
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                results,
+                vec![json!("https://a.example"), json!("https://b.example")]
+            );
+            assert_eq!(scanner.into_body(), BODY.as_bytes());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_partial_results_arrive_before_final_response() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = json_mock(&mut server, "/res/v1/web/search", BODY)
+            .create_async()
+            .await;
+        let router = router_for(&server);
+
+        let mut client = TestClient::start(router).await;
+        let messages = client
+            .call_tool(
+                2,
+                "brave_web_search",
+                json!({"query": "rust", "count": 2, "progress_token": "web-1"}),
+            )
+            .await;
+
+        let progress = notifications(&messages, "notifications/progress");
+        assert_eq!(
+            progress,
+            vec![
+                &json!({"progressToken": "web-1", "progress": 1, "total": 2}),
+                &json!({"progressToken": "web-1", "progress": 2, "total": 2}),
+            ]
+        );
+        let partials = notifications(&messages, "notifications/message")
+            .into_iter()
+            .filter(|params| params["logger"] == "bravesearch/partial")
+            .collect::<Vec<_>>();
+        assert_eq!(partials.len(), 2, "{:?}", messages);
+        assert_eq!(partials[0]["data"]["progressToken"], "web-1");
+        assert!(partials[0]["data"]["content"]
+            .as_str()
+            .unwrap()
+            .starts_with("Title: A \"quoted\" {brace}"));
+        assert!(partials[1]["data"]["content"]
+            .as_str()
+            .unwrap()
+            .contains("URL: https://b.example"));
+
+        // The final response still carries every result
+        let text = messages.last().unwrap()["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap();
+        assert!(text.contains("https://a.example") && text.contains("https://b.example"));
+    }
+
+    #[tokio::test]
+    async fn test_streamed_and_plain_requests_share_one_call() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_chunked_body(|w| {
+                std::thread::sleep(std::time::Duration::from_millis(200));
+                w.write_all(BODY.as_bytes())
+            })
+            .expect(1)
+            .create_async()
+            .await;
+        let router = router_for(&server);
+        let url =
+            reqwest::Url::parse(&format!("{}/res/v1/web/search?q=rust", server.url())).unwrap();
+        let progress = ProgressReporter::new(None, None, 2);
+
+        let (streamed, plain) = futures::future::join(
+            router.send_request_streaming(url.clone(), &progress, |result| result.title),
+            router.send_request(url),
+        )
+        .await;
+        assert_eq!(streamed.unwrap(), BODY);
+        assert_eq!(plain.unwrap(), BODY);
+        mock.assert_async().await;
+        assert_eq!(router.rate_limiter.usage().await.2, 1);
+    }
+}
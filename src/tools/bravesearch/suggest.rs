@@ -0,0 +1,180 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+use super::{BraveSearchRouter, CountryCode, LanguageCode, SuggestParams};
+
+// Query completions for `brave_suggest`.
+//
+// The suggest endpoint answers in the OpenSearch suggestions shape, a
+// two-element array of the query and its completions
+// (`["rust", ["rust lang", "rust game"]]`), so it gets its own response type
+// instead of going through `BraveSearchResponse`.
+
+// Most suggestions Brave returns for one query
+const MAX_SUGGESTIONS: usize = 20;
+
+#[derive(Debug, Deserialize)]
+struct BraveSuggestResponse(#[allow(dead_code)] String, Vec<String>);
+
+impl BraveSearchRouter {
+    /// Completions Brave suggests for `params.query`, one per line
+    pub(crate) async fn perform_suggest(&self, params: &SuggestParams) -> Result<String> {
+        let count = params.count.unwrap_or(5).clamp(1, MAX_SUGGESTIONS);
+        // Completions come from the same text the search tools would send
+        let mut query_params = vec![
+            ("q", self.preprocessor.process(&params.query)),
+            ("count", count.to_string()),
+        ];
+        if let Some(country) = params.country.as_deref() {
+            let country = CountryCode::from_str(country).map_err(|e| anyhow!(e))?;
+            query_params.push(("country", country.to_string()));
+        }
+        if let Some(lang) = params.search_lang.as_deref() {
+            let lang = LanguageCode::from_str(lang).map_err(|e| anyhow!(e))?;
+            query_params.push(("lang", lang.to_string()));
+        }
+        let url = reqwest::Url::parse_with_params(
+            &format!("{}/res/v1/suggest/search", self.base_url),
+            &query_params,
+        )?;
+
+        let body = self.send_request(url).await?;
+        let BraveSuggestResponse(_, suggestions) = serde_json::from_str(&body)?;
+        if suggestions.is_empty() {
+            return Ok("No suggestions found".to_string());
+        }
+        Ok(suggestions.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_fixtures::router_for;
+    use super::super::SiteRestriction;
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_suggest_returns_completions_one_per_line() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/res/v1/suggest/search")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("q".into(), "rust".into()),
+                mockito::Matcher::UrlEncoded("country".into(), "gb".into()),
+                mockito::Matcher::UrlEncoded("lang".into(), "en".into()),
+                mockito::Matcher::UrlEncoded("count".into(), "5".into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(r#"["rust",["rust lang","rust game","rustacean"]]"#)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/res/v1/suggest/search")
+            .match_query(mockito::Matcher::UrlEncoded("q".into(), "qwzx".into()))
+            .with_header("content-type", "application/json")
+            .with_body(r#"["qwzx",[]]"#)
+            .create_async()
+            .await;
+
+        let router = router_for(&server);
+
+        let result = router
+            .brave_suggest(SuggestParams {
+                query: "rust".to_string(),
+                country: Some("gb".to_string()),
+                search_lang: Some("en".to_string()),
+                count: None,
+            })
+            .await;
+        assert_eq!(result, "rust lang\nrust game\nrustacean");
+        mock.assert_async().await;
+
+        let result = router
+            .brave_suggest(SuggestParams {
+                query: "qwzx".to_string(),
+                ..Default::default()
+            })
+            .await;
+        assert_eq!(result, "No suggestions found");
+
+        let result = router
+            .brave_suggest(SuggestParams {
+                query: "rust".to_string(),
+                country: Some("XX".to_string()),
+                ..Default::default()
+            })
+            .await;
+        assert!(
+            result.starts_with("Error parsing country code: "),
+            "{}",
+            result
+        );
+    }
+
+    #[tokio::test]
+    async fn test_suggest_count_is_clamped() {
+        let mut server = mockito::Server::new_async().await;
+        let mut mocks = Vec::new();
+        for count in ["20", "1", "8"] {
+            let mock = server
+                .mock("GET", "/res/v1/suggest/search")
+                .match_query(mockito::Matcher::AllOf(vec![
+                    mockito::Matcher::UrlEncoded("q".into(), "rust".into()),
+                    mockito::Matcher::UrlEncoded("count".into(), count.into()),
+                ]))
+                .with_header("content-type", "application/json")
+                .with_body(r#"["rust",["rust lang"]]"#)
+                .expect(1)
+                .create_async()
+                .await;
+            mocks.push(mock);
+        }
+
+        let router = router_for(&server);
+        // Brave's maximum caps larger counts, and zero asks for one
+        for count in [100, 0, 8] {
+            let result = router
+                .brave_suggest(SuggestParams {
+                    query: "rust".to_string(),
+                    count: Some(count),
+                    ..Default::default()
+                })
+                .await;
+            assert_eq!(result, "rust lang");
+        }
+        for mock in mocks {
+            mock.assert_async().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_suggest_query_is_preprocessed() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/res/v1/suggest/search")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "q".into(),
+                "kitchen ideas -site:pinterest.com".into(),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(r#"["kitchen ideas -site:pinterest.com",["kitchen ideas small"]]"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let router = router_for(&server)
+            .with_query_preprocessor(Arc::new(SiteRestriction::exclude("pinterest.com")));
+
+        let result = router
+            .brave_suggest(SuggestParams {
+                query: "kitchen ideas".to_string(),
+                ..Default::default()
+            })
+            .await;
+        assert_eq!(result, "kitchen ideas small");
+        mock.assert_async().await;
+    }
+}
@@ -0,0 +1,104 @@
+use rmcp::ServiceExt;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+use super::BraveSearchRouter;
+
+// In-process MCP client for tests that exercise notifications end to end.
+
+// Minimal JSON-RPC client speaking newline-delimited messages over an in-memory pipe
+pub(crate) struct TestClient {
+    reader: BufReader<tokio::io::ReadHalf<tokio::io::DuplexStream>>,
+    writer: tokio::io::WriteHalf<tokio::io::DuplexStream>,
+}
+
+impl TestClient {
+    pub(crate) async fn start(router: BraveSearchRouter) -> Self {
+        let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+        tokio::spawn(async move {
+            if let Ok(server) = router.serve(tokio::io::split(server_io)).await {
+                let _ = server.waiting().await;
+            }
+        });
+        Self::connect(client_io).await
+    }
+
+    /// Initialize an MCP session with a server already serving the other end of `client_io`
+    pub(crate) async fn connect(client_io: tokio::io::DuplexStream) -> Self {
+        let (reader, writer) = tokio::io::split(client_io);
+        let mut client = Self {
+            reader: BufReader::new(reader),
+            writer,
+        };
+        client
+            .send(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "initialize",
+                "params": {
+                    "protocolVersion": "2024-11-05",
+                    "capabilities": {},
+                    "clientInfo": {"name": "test", "version": "0.1.0"}
+                }
+            }))
+            .await;
+        let init = client.recv().await;
+        assert!(init["result"]["capabilities"]["logging"].is_object());
+        client
+            .send(json!({"jsonrpc": "2.0", "method": "notifications/initialized"}))
+            .await;
+        client
+    }
+
+    pub(crate) async fn send(&mut self, message: Value) {
+        self.send_line(&message.to_string()).await;
+    }
+
+    /// Send `line` as it is, e.g. for input that is not valid JSON
+    pub(crate) async fn send_line(&mut self, line: &str) {
+        let line = format!("{}\n", line);
+        self.writer.write_all(line.as_bytes()).await.unwrap();
+    }
+
+    pub(crate) async fn recv(&mut self) -> Value {
+        let mut line = String::new();
+        tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            self.reader.read_line(&mut line),
+        )
+        .await
+        .expect("timed out waiting for server message")
+        .unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    /// Call a tool and collect every message up to and including its response
+    pub(crate) async fn call_tool(&mut self, id: u64, name: &str, arguments: Value) -> Vec<Value> {
+        self.send(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "tools/call",
+            "params": {"name": name, "arguments": arguments}
+        }))
+        .await;
+
+        let mut messages = Vec::new();
+        loop {
+            let message = self.recv().await;
+            let done = message["id"] == json!(id);
+            messages.push(message);
+            if done {
+                return messages;
+            }
+        }
+    }
+}
+
+/// Params of every notification with `method` among `messages`, in order
+pub(crate) fn notifications<'a>(messages: &'a [Value], method: &str) -> Vec<&'a Value> {
+    messages
+        .iter()
+        .filter(|m| m["method"] == method)
+        .map(|m| &m["params"])
+        .collect()
+}
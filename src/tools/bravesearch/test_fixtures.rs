@@ -0,0 +1,25 @@
+use super::{BraveSearchRouter, RateLimiter, RATE_LIMIT_PER_MONTH};
+
+// Fixtures shared by the tests that search against a mock Brave API.
+
+/// A router sending its requests to `server`, allowed 10 requests a second so that tests
+/// making several are not rate limited
+pub(crate) fn router_for(server: &mockito::Server) -> BraveSearchRouter {
+    let mut router = BraveSearchRouter::new("test_key".to_string()).with_base_url(server.url());
+    router.rate_limiter = RateLimiter::with_limits(10, RATE_LIMIT_PER_MONTH);
+    router
+}
+
+/// A mock of `GET path` with any query, answering `body` as JSON; chain further matchers or
+/// `expect` before `create_async`
+pub(crate) fn json_mock(
+    server: &mut mockito::Server,
+    path: &str,
+    body: impl AsRef<[u8]>,
+) -> mockito::Mock {
+    server
+        .mock("GET", path)
+        .match_query(mockito::Matcher::Any)
+        .with_header("content-type", "application/json")
+        .with_body(body)
+}
@@ -0,0 +1,215 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, SecondsFormat, Utc};
+use chrono_tz::Tz;
+
+// Timestamp normalization for Brave result metadata.
+//
+// Brave reports publication time either as an absolute `page_age`
+// (e.g. "2024-05-01T14:30:00") or a relative `age` (e.g. "2 hours ago",
+// sometimes an absolute date like "May 1, 2024").
+
+/// Parse an absolute timestamp in one of the formats Brave is known to return
+pub(crate) fn parse_absolute(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(value) {
+        return Some(parsed.with_timezone(&Utc));
+    }
+    // Timestamps without an offset are treated as UTC
+    for format in ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"] {
+        if let Ok(parsed) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(parsed.and_utc());
+        }
+    }
+    for format in ["%Y-%m-%d", "%B %d, %Y", "%b %d, %Y"] {
+        if let Ok(parsed) = NaiveDate::parse_from_str(value, format) {
+            return parsed.and_hms_opt(0, 0, 0).map(|dt| dt.and_utc());
+        }
+    }
+    None
+}
+
+/// Parse a relative age such as "3 days ago" or "an hour ago" into a duration. Negative
+/// amounts, and amounts too large for a duration, are rejected rather than panicking
+pub(crate) fn parse_relative(value: &str) -> Option<Duration> {
+    let lowered = value.trim().to_lowercase();
+    let mut words = lowered.split_whitespace();
+
+    let amount = match words.next()? {
+        "a" | "an" => 1,
+        number => number.parse::<i64>().ok().filter(|amount| *amount >= 0)?,
+    };
+    let unit = words.next()?;
+    if words.next() != Some("ago") {
+        return None;
+    }
+
+    match unit.trim_end_matches('s') {
+        "second" => Duration::try_seconds(amount),
+        "minute" => Duration::try_minutes(amount),
+        "hour" => Duration::try_hours(amount),
+        "day" => Duration::try_days(amount),
+        "week" => Duration::try_weeks(amount),
+        "month" => Duration::try_days(amount.checked_mul(30)?),
+        "year" => Duration::try_days(amount.checked_mul(365)?),
+        _ => None,
+    }
+}
+
+/// Resolve a result's publication time, preferring the absolute `page_age`
+/// and falling back to the relative `age` measured from `now`. An age reaching back before
+/// the earliest representable time gives None
+pub(crate) fn published_at(
+    page_age: Option<&str>,
+    age: Option<&str>,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    page_age.and_then(parse_absolute).or_else(|| {
+        let age = age?;
+        match parse_relative(age) {
+            Some(ago) => now.checked_sub_signed(ago),
+            None => parse_absolute(age),
+        }
+    })
+}
+
+/// Approximate time from `timestamp` to `now` (e.g. "~3 months ago"), in the largest unit
+/// that fits. Anything under an hour, including timestamps in the future, is "within the
+/// last hour"
+pub(crate) fn time_ago(timestamp: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let elapsed = now - timestamp;
+    let (amount, unit) = if elapsed < Duration::hours(1) {
+        return "within the last hour".to_string();
+    } else if elapsed < Duration::days(1) {
+        (elapsed.num_hours(), "hour")
+    } else if elapsed < Duration::weeks(2) {
+        (elapsed.num_days(), "day")
+    } else if elapsed < Duration::days(60) {
+        (elapsed.num_weeks(), "week")
+    } else if elapsed < Duration::days(365) {
+        (elapsed.num_days() / 30, "month")
+    } else {
+        (elapsed.num_days() / 365, "year")
+    };
+    let plural = if amount == 1 { "" } else { "s" };
+    format!("~{} {}{} ago", amount, unit, plural)
+}
+
+/// Format a timestamp as ISO-8601 in UTC (e.g. "2024-05-01T14:30:00Z")
+pub(crate) fn to_iso8601(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Parse an IANA timezone name such as "Asia/Tokyo"
+pub(crate) fn parse_timezone(name: &str) -> Result<Tz> {
+    name.trim().parse::<Tz>().map_err(|_| {
+        anyhow!(
+            "Unknown timezone: {} (expected an IANA name such as 'Asia/Tokyo' or 'America/New_York')",
+            name
+        )
+    })
+}
+
+/// Format a timestamp as local time in `tz` with the zone abbreviation (e.g. "2024-05-01 14:30 JST")
+pub(crate) fn to_zoned(timestamp: DateTime<Utc>, tz: Tz) -> String {
+    timestamp
+        .with_timezone(&tz)
+        .format("%Y-%m-%d %H:%M %Z")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 10, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_published_at_from_age_only() {
+        let published = published_at(None, Some("2 hours ago"), now()).unwrap();
+        assert_eq!(to_iso8601(published), "2024-05-10T10:00:00Z");
+
+        let published = published_at(None, Some("a day ago"), now()).unwrap();
+        assert_eq!(to_iso8601(published), "2024-05-09T12:00:00Z");
+
+        let published = published_at(None, Some("May 1, 2024"), now()).unwrap();
+        assert_eq!(to_iso8601(published), "2024-05-01T00:00:00Z");
+    }
+
+    #[test]
+    fn test_published_at_from_page_age_only() {
+        let published = published_at(Some("2024-05-01T14:30:00"), None, now()).unwrap();
+        assert_eq!(to_iso8601(published), "2024-05-01T14:30:00Z");
+
+        let published = published_at(Some("2024-05-01T14:30:00+09:00"), None, now()).unwrap();
+        assert_eq!(to_iso8601(published), "2024-05-01T05:30:00Z");
+    }
+
+    #[test]
+    fn test_published_at_prefers_page_age() {
+        let published =
+            published_at(Some("2024-05-01T14:30:00"), Some("2 hours ago"), now()).unwrap();
+        assert_eq!(to_iso8601(published), "2024-05-01T14:30:00Z");
+
+        // An unparseable page_age falls back to the relative age
+        let published = published_at(Some("recently"), Some("3 days ago"), now()).unwrap();
+        assert_eq!(to_iso8601(published), "2024-05-07T12:00:00Z");
+    }
+
+    #[test]
+    fn test_time_ago_uses_largest_fitting_unit() {
+        let ago = |duration: Duration| time_ago(now() - duration, now());
+        assert_eq!(ago(Duration::minutes(20)), "within the last hour");
+        assert_eq!(ago(Duration::minutes(-5)), "within the last hour");
+        assert_eq!(ago(Duration::hours(1)), "~1 hour ago");
+        assert_eq!(ago(Duration::hours(30)), "~1 day ago");
+        assert_eq!(ago(Duration::days(13)), "~13 days ago");
+        assert_eq!(ago(Duration::days(20)), "~2 weeks ago");
+        assert_eq!(ago(Duration::days(95)), "~3 months ago");
+        assert_eq!(ago(Duration::days(800)), "~2 years ago");
+    }
+
+    #[test]
+    fn test_to_zoned_converts_utc() {
+        let published = Utc.with_ymd_and_hms(2024, 5, 1, 5, 30, 0).unwrap();
+        let tokyo = parse_timezone("Asia/Tokyo").unwrap();
+        assert_eq!(to_zoned(published, tokyo), "2024-05-01 14:30 JST");
+
+        // Daylight saving time applies in May, and the date rolls back
+        let new_york = parse_timezone("America/New_York").unwrap();
+        assert_eq!(to_zoned(published, new_york), "2024-05-01 01:30 EDT");
+
+        let winter = Utc.with_ymd_and_hms(2024, 1, 15, 3, 0, 0).unwrap();
+        assert_eq!(to_zoned(winter, new_york), "2024-01-14 22:00 EST");
+    }
+
+    #[test]
+    fn test_parse_timezone_rejects_unknown_names() {
+        assert!(parse_timezone("Europe/Berlin").is_ok());
+        let err = parse_timezone("Mars/Olympus_Mons").unwrap_err().to_string();
+        assert!(err.starts_with("Unknown timezone: Mars/Olympus_Mons"));
+        assert!(parse_timezone("JST").is_err());
+    }
+
+    #[test]
+    fn test_published_at_unparseable() {
+        assert!(published_at(None, None, now()).is_none());
+        assert!(published_at(None, Some("sometime"), now()).is_none());
+        assert!(published_at(None, Some("3 fortnights ago"), now()).is_none());
+    }
+
+    #[test]
+    fn test_out_of_range_and_negative_ages_are_rejected() {
+        // Overflows the multiplication, the duration, and the subtraction respectively
+        assert!(parse_relative("9223372036854775807 years ago").is_none());
+        assert!(parse_relative("9223372036854775807 seconds ago").is_none());
+        assert!(published_at(None, Some("99999999999 days ago"), now()).is_none());
+
+        assert!(parse_relative("-3 days ago").is_none());
+        assert!(published_at(None, Some("-3 days ago"), now()).is_none());
+        assert!(parse_relative("0 days ago").is_some());
+    }
+}
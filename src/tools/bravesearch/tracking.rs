@@ -0,0 +1,73 @@
+// Removal of tracking query parameters from result URLs (`clean_urls`).
+//
+// Only parameters that exist purely for campaign or click attribution are
+// dropped; everything else in the query is kept in its original order, since
+// it may select the page content (e.g. `?id=42`, `?page=2`). URLs that do not
+// parse or carry no tracking parameters are returned unchanged.
+
+// Parameter name prefixes used only for attribution (matched case-insensitively)
+const TRACKING_PREFIXES: &[&str] = &["utm_", "_hs", "mc_", "oly_"];
+
+// Individual attribution parameters (matched case-insensitively)
+const TRACKING_PARAMS: &[&str] = &[
+    "gclid", "gclsrc", "dclid", "fbclid", "msclkid", "yclid", "twclid", "igshid", "mkt_tok", "_ga",
+    "_gl", "vero_id", "wickedid",
+];
+
+fn is_tracking_param(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    TRACKING_PREFIXES
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+        || TRACKING_PARAMS.contains(&name.as_str())
+}
+
+/// `url` without its tracking query parameters
+pub(crate) fn strip_tracking_params(url: &str) -> String {
+    let Ok(mut parsed) = reqwest::Url::parse(url) else {
+        return url.to_string();
+    };
+    if !parsed
+        .query_pairs()
+        .any(|(name, _)| is_tracking_param(&name))
+    {
+        return url.to_string();
+    }
+
+    let kept = parsed
+        .query_pairs()
+        .filter(|(name, _)| !is_tracking_param(name))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect::<Vec<_>>();
+    if kept.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(kept);
+    }
+    parsed.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracking_params_are_removed_and_functional_ones_kept() {
+        assert_eq!(
+            strip_tracking_params(
+                "https://example.com/article?id=42&utm_source=brave&UTM_Medium=search&page=2&fbclid=abc#comments"
+            ),
+            "https://example.com/article?id=42&page=2#comments"
+        );
+        assert_eq!(
+            strip_tracking_params("https://example.com/?utm_campaign=spring&gclid=x"),
+            "https://example.com/"
+        );
+        // Clean and unparseable URLs are left exactly as they were
+        assert_eq!(
+            strip_tracking_params("https://example.com/search?q=a+b&ref=home"),
+            "https://example.com/search?q=a+b&ref=home"
+        );
+        assert_eq!(strip_tracking_params("not a url"), "not a url");
+    }
+}
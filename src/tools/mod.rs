@@ -1,3 +1,11 @@
 pub mod bravesearch;
 
-pub use bravesearch::BraveSearchRouter;
+pub use bravesearch::{
+    BraveSearchRouter, HmacSigner, LocalSearchParams, MemoryCache, NewsSearchParams, NoopSigner,
+    OutputFormat, QueryBuilder, QueryOperators, QueryPreprocessor, RateLimitBehavior,
+    RateLimiterConfig, RequestSigner, ResultFormatter, SafeSearchLevel, SearchCache,
+    SiteRestriction, SuggestParams, WebSearchBatchParams, WebSearchItem, WebSearchParams,
+    DEFAULT_COMPACT_TITLE_WIDTH, DEFAULT_DEEP_PAGINATION_THRESHOLD, DEFAULT_HIGHLIGHT_MARKER,
+    DEFAULT_MAX_RETRIES, DEFAULT_QUOTA_LOG_INTERVAL, DEFAULT_RETRY_BASE_MS, RATE_LIMIT_PER_MONTH,
+    RATE_LIMIT_PER_SECOND,
+};
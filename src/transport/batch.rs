@@ -0,0 +1,285 @@
+use rmcp::model::{ClientJsonRpcMessage, JsonRpcMessage, RequestId, ServerJsonRpcMessage};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::time::Duration;
+
+// JSON-RPC 2.0 batches, shared by the stdio and SSE transports.
+//
+// rmcp handles one message at a time, so an incoming array is split into its
+// messages here and the request ids of each batch are tracked. The responses
+// to a batch's requests are held until the last one is ready, then sent
+// together as one array. Notifications get no response, so a batch of only
+// notifications produces no output at all.
+//
+// Input that is not JSON is answered with a `Parse error`, and each entry
+// that is not a valid JSON-RPC message with an `Invalid Request` error (in
+// its batch's array when it came in one). A batch is never held forever: a
+// member cancelled by the client (`notifications/cancelled`) is no longer
+// waited for, and a batch still incomplete after `BATCH_TIMEOUT` is sent with
+// the responses it has; anything answered later is sent on its own.
+
+/// How long a batch waits for its slowest response before the rest are sent without it
+pub(crate) const BATCH_TIMEOUT: Duration = Duration::from_secs(60);
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+
+/// The requests of one batch still to be answered, and the responses so far
+struct Batch {
+    id: u64,
+    pending: HashSet<RequestId>,
+    responses: Vec<Value>,
+}
+
+#[derive(Default)]
+pub(crate) struct Batches {
+    next_id: u64,
+    open: Vec<Batch>,
+}
+
+/// What one line or request body decodes to
+#[derive(Default)]
+pub(crate) struct Decoded {
+    /// Messages for the service, in order
+    pub(crate) messages: Vec<ClientJsonRpcMessage>,
+    /// Replies to send the client straight away: errors, or batches a cancellation completed
+    pub(crate) replies: Vec<String>,
+    /// A new batch waiting for responses, to `expire` after `BATCH_TIMEOUT`
+    pub(crate) batch: Option<u64>,
+}
+
+impl Batches {
+    /// Decode one line or request body, remembering the request ids of a batch
+    pub(crate) fn decode(&mut self, text: &str) -> Decoded {
+        let mut decoded = Decoded::default();
+        if text.trim().is_empty() {
+            return decoded;
+        }
+        let value = match serde_json::from_str::<Value>(text) {
+            Ok(value) => value,
+            Err(e) => {
+                tracing::warn!("Malformed JSON-RPC input: {}", e);
+                decoded
+                    .replies
+                    .push(error_object(Value::Null, PARSE_ERROR, "Parse error").to_string());
+                return decoded;
+            }
+        };
+
+        let Value::Array(items) = value else {
+            let cancelled = cancelled_request(&value);
+            match decode_message(value) {
+                Ok(message) => decoded.messages.push(message),
+                Err(error) => decoded.replies.push(error.to_string()),
+            }
+            decoded
+                .replies
+                .extend(cancelled.and_then(|id| self.cancel(&id)));
+            return decoded;
+        };
+        if items.is_empty() {
+            decoded
+                .replies
+                .push(error_object(Value::Null, INVALID_REQUEST, "Invalid Request").to_string());
+            return decoded;
+        }
+
+        let mut errors = Vec::new();
+        let mut cancelled = Vec::new();
+        for item in items {
+            cancelled.extend(cancelled_request(&item));
+            match decode_message(item) {
+                Ok(message) => decoded.messages.push(message),
+                Err(error) => errors.push(error),
+            }
+        }
+        let pending = decoded
+            .messages
+            .iter()
+            .filter_map(|message| match message {
+                JsonRpcMessage::Request(request) => Some(request.id.clone()),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+        if pending.is_empty() {
+            if !errors.is_empty() {
+                decoded.replies.push(Value::Array(errors).to_string());
+            }
+        } else {
+            let id = self.next_id;
+            self.next_id += 1;
+            self.open.push(Batch {
+                id,
+                pending,
+                responses: errors,
+            });
+            decoded.batch = Some(id);
+        }
+        for id in cancelled {
+            decoded.replies.extend(self.cancel(&id));
+        }
+        decoded
+    }
+
+    /// The text to send for `message`, or None while the rest of its batch is outstanding
+    pub(crate) fn encode(
+        &mut self,
+        message: ServerJsonRpcMessage,
+    ) -> serde_json::Result<Option<String>> {
+        let id = match &message {
+            JsonRpcMessage::Response(response) => Some(response.id.clone()),
+            JsonRpcMessage::Error(error) => Some(error.id.clone()),
+            _ => None,
+        };
+        let Some(index) = id.and_then(|id| self.take_pending(&id)) else {
+            return serde_json::to_string(&message).map(Some);
+        };
+
+        self.open[index]
+            .responses
+            .push(serde_json::to_value(&message)?);
+        Ok(self.complete(index))
+    }
+
+    /// Send batch `id` with the responses it has, if it is still waiting for some
+    pub(crate) fn expire(&mut self, id: u64) -> Option<String> {
+        let index = self.open.iter().position(|batch| batch.id == id)?;
+        tracing::warn!(
+            "JSON-RPC batch still waiting for {} responses after {:?}; sending it without them",
+            self.open[index].pending.len(),
+            BATCH_TIMEOUT
+        );
+        let batch = self.open.remove(index);
+        (!batch.responses.is_empty()).then(|| Value::Array(batch.responses).to_string())
+    }
+
+    /// Stop waiting for request `id`, which the client cancelled and may never be answered
+    fn cancel(&mut self, id: &RequestId) -> Option<String> {
+        let index = self.take_pending(id)?;
+        self.complete(index)
+    }
+
+    /// Remove `id` from the batch waiting for it, returning the batch's index
+    fn take_pending(&mut self, id: &RequestId) -> Option<usize> {
+        let index = self
+            .open
+            .iter()
+            .position(|batch| batch.pending.contains(id))?;
+        self.open[index].pending.remove(id);
+        Some(index)
+    }
+
+    /// The batch at `index` as one array, removing it, once nothing is pending
+    fn complete(&mut self, index: usize) -> Option<String> {
+        if !self.open[index].pending.is_empty() {
+            return None;
+        }
+        let batch = self.open.remove(index);
+        (!batch.responses.is_empty()).then(|| Value::Array(batch.responses).to_string())
+    }
+}
+
+/// A message for the service, or the `Invalid Request` error answering it
+fn decode_message(value: Value) -> Result<ClientJsonRpcMessage, Value> {
+    let id = match value.get("id") {
+        Some(id @ (Value::String(_) | Value::Number(_))) => id.clone(),
+        _ => Value::Null,
+    };
+    serde_json::from_value(value).map_err(|e| {
+        tracing::warn!("Invalid JSON-RPC message: {}", e);
+        error_object(id, INVALID_REQUEST, "Invalid Request")
+    })
+}
+
+/// The request a `notifications/cancelled` message cancels
+fn cancelled_request(value: &Value) -> Option<RequestId> {
+    if value.get("method")? != "notifications/cancelled" {
+        return None;
+    }
+    serde_json::from_value(value.get("params")?.get("requestId")?.clone()).ok()
+}
+
+/// A JSON-RPC error response to `id`
+fn error_object(id: Value, code: i64, message: &str) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::{CallToolResult, ServerMessage, ServerResult};
+
+    fn response(id: u32) -> ServerJsonRpcMessage {
+        let id = serde_json::from_value(json!(id)).unwrap();
+        let result = ServerResult::CallToolResult(CallToolResult::success(Vec::new()));
+        ServerMessage::Response(result, id).into_json_rpc_message()
+    }
+
+    fn request(id: u32) -> Value {
+        json!({"jsonrpc": "2.0", "id": id, "method": "ping"})
+    }
+
+    #[test]
+    fn test_invalid_input_gets_spec_errors() {
+        let mut batches = Batches::default();
+
+        let decoded = batches.decode("{not json");
+        assert!(decoded.messages.is_empty());
+        let reply: Value = serde_json::from_str(&decoded.replies[0]).unwrap();
+        assert_eq!(reply["error"]["code"], PARSE_ERROR);
+        assert_eq!(reply["id"], Value::Null);
+
+        let decoded = batches.decode("[]");
+        let reply: Value = serde_json::from_str(&decoded.replies[0]).unwrap();
+        assert_eq!(reply["error"]["code"], INVALID_REQUEST);
+
+        // Invalid entries are answered inside the batch, alongside the valid requests
+        let decoded = batches.decode(&json!([request(1), {"id": 7, "foo": 1}, 5]).to_string());
+        assert_eq!(decoded.messages.len(), 1);
+        assert!(decoded.replies.is_empty());
+        let line = batches.encode(response(1)).unwrap().unwrap();
+        let replies: Value = serde_json::from_str(&line).unwrap();
+        let replies = replies.as_array().unwrap();
+        assert_eq!(replies.len(), 3);
+        assert_eq!(replies[0]["error"]["code"], INVALID_REQUEST);
+        assert_eq!(replies[0]["id"], 7);
+        assert_eq!(replies[1]["id"], Value::Null);
+        assert_eq!(replies[2]["id"], 1);
+
+        // A batch with nothing to wait for is answered at once
+        let decoded = batches.decode(&json!([{"jsonrpc": "2.0"}]).to_string());
+        let replies: Value = serde_json::from_str(&decoded.replies[0]).unwrap();
+        assert_eq!(replies.as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_incomplete_batches_are_flushed() {
+        let mut batches = Batches::default();
+
+        // Cancelling the last outstanding member sends the rest
+        batches.decode(&json!([request(1), request(2)]).to_string());
+        assert_eq!(batches.encode(response(1)).unwrap(), None);
+        let cancel = json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/cancelled",
+            "params": {"requestId": 2}
+        });
+        let decoded = batches.decode(&cancel.to_string());
+        let replies: Value = serde_json::from_str(&decoded.replies[0]).unwrap();
+        assert_eq!(replies.as_array().unwrap().len(), 1);
+        // A late answer is sent on its own
+        let late: Value =
+            serde_json::from_str(&batches.encode(response(2)).unwrap().unwrap()).unwrap();
+        assert_eq!(late["id"], 2);
+
+        // Expiry sends what has arrived
+        let batch = batches
+            .decode(&json!([request(3), request(4)]).to_string())
+            .batch
+            .unwrap();
+        assert_eq!(batches.encode(response(3)).unwrap(), None);
+        let replies: Value = serde_json::from_str(&batches.expire(batch).unwrap()).unwrap();
+        assert_eq!(replies[0]["id"], 3);
+        assert_eq!(batches.expire(batch), None);
+    }
+}
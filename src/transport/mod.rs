@@ -1,2 +1,3 @@
+mod batch;
 pub mod sse_server;
 pub mod stdio;
@@ -1,15 +1,125 @@
-use anyhow::Result;
-use rmcp::{transport::sse_server::SseServer, RoleServer, ServerHandler, Service};
+use anyhow::{Context, Result};
+use axum::extract::{Query, Request, State};
+use axum::http::{header, HeaderValue, Method, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::sse::{Event, Sse};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::Router;
+use futures::channel::mpsc;
+use futures::{SinkExt, StreamExt};
+use rmcp::model::{
+    CallToolResult, ClientJsonRpcMessage, ClientRequest, Content, JsonRpcMessage, RequestId,
+    ServerJsonRpcMessage, ServerMessage, ServerResult,
+};
+use rmcp::{RoleServer, ServerHandler, Service, ServiceExt};
+use std::collections::HashMap;
+use std::convert::Infallible;
 use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc as tokio_mpsc;
 use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tokio_util::sync::{CancellationToken, DropGuard, PollSender};
+use tower_http::cors::{AllowOrigin, CorsLayer};
 
-pub async fn serve<S>(service: S, port: u16) -> Result<JoinHandle<Result<()>>>
+use super::batch::{Batches, BATCH_TIMEOUT};
+
+// The SSE transport, speaking the same protocol as rmcp's `SseServer`: a
+// client opens `GET /sse`, receives an `endpoint` event naming its
+// `/message?sessionId=...` URL, posts JSON-RPC messages there, and reads the
+// responses as `message` events. rmcp's server offers no hook to refuse a
+// connection, so sessions are tracked here to enforce `max_sessions` and
+// the per-session tool call rate. A POST may carry a JSON-RPC batch (an
+// array); its responses are sent as one `message` event holding an array,
+// like a batch line on stdio.
+
+/// Options for the SSE server (not `Debug`, so the auth token cannot end up in logs)
+#[derive(Clone, Default)]
+pub struct SseOptions {
+    /// Reject new sessions with 429 while this many are open; None for no limit
+    pub max_sessions: Option<usize>,
+    /// Origins allowed to call the server from a browser (`*` for any); empty sends no
+    /// CORS headers, leaving browsers to same-origin only
+    pub allowed_origins: Vec<String>,
+    /// Require `Authorization: Bearer <token>` on every request; None leaves the server open
+    pub auth_token: Option<String>,
+    /// Tool calls each session may make per minute, on top of the router's global limits;
+    /// None for no per-session limit
+    pub session_calls_per_minute: Option<u32>,
+    /// Serve a search page for manual testing at `/`; off by default
+    pub serve_ui: bool,
+}
+
+const SSE_PATH: &str = "/sse";
+const POST_PATH: &str = "/message";
+const UI_PATH: &str = "/";
+
+// The page for `serve_ui`, a small MCP client that talks to the routes above
+const UI_HTML: &str = include_str!("ui.html");
+
+// Messages buffered per direction before the sender waits
+const CHANNEL_CAPACITY: usize = 64;
+
+// Window for `session_calls_per_minute`
+const SESSION_RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// An open session's channels, open batches, and tool call count
+struct Session {
+    to_service: mpsc::Sender<ClientJsonRpcMessage>,
+    /// Serialized messages for the event stream. Weak, so the map never keeps a finished
+    /// session's event stream open
+    to_client: tokio_mpsc::WeakSender<String>,
+    batches: Arc<Mutex<Batches>>,
+    window_start: Instant,
+    calls_in_window: u32,
+}
+
+impl Session {
+    /// Count a tool call against `limit` per minute, or return false when over it
+    fn admit_call(&mut self, limit: u32) -> bool {
+        let now = Instant::now();
+        if now.duration_since(self.window_start) >= SESSION_RATE_WINDOW {
+            self.window_start = now;
+            self.calls_in_window = 0;
+        }
+        if self.calls_in_window >= limit {
+            return false;
+        }
+        self.calls_in_window += 1;
+        true
+    }
+}
+
+type Sessions = Arc<Mutex<HashMap<String, Session>>>;
+
+#[derive(Clone)]
+struct SseApp<S> {
+    service: S,
+    sessions: Sessions,
+    max_sessions: Option<usize>,
+    session_calls_per_minute: Option<u32>,
+    ct: CancellationToken,
+}
+
+/// Serve `service` over SSE on `port` with `options`
+pub async fn serve<S>(service: S, port: u16, options: SseOptions) -> Result<JoinHandle<Result<()>>>
 where
     S: Service<RoleServer> + ServerHandler + Clone + Send + Sync + 'static,
 {
+    let cancellation_token = CancellationToken::new();
+    let app = router(service, options, cancellation_token.clone())?;
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
-    let sse_server = SseServer::serve(addr).await?;
-    let cancellation_token = sse_server.with_service(move || service.clone());
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+
+    let ct = cancellation_token.clone();
+    tokio::spawn(async move {
+        let server = axum::serve(listener, app).with_graceful_shutdown(ct.cancelled_owned());
+        if let Err(e) = server.await {
+            tracing::error!("SSE server shut down with error: {}", e);
+        }
+    });
 
     // Spawn a task that waits for Ctrl+C and then cancels the server
     let handle = tokio::spawn(async move {
@@ -18,7 +128,7 @@ where
             tracing::error!("Failed to listen for ctrl+c: {}", e);
         }
 
-        // Cancel the server
+        // Cancel the server and every open session
         tracing::info!("Shutting down server...");
         cancellation_token.cancel();
 
@@ -27,3 +137,650 @@ where
 
     Ok(handle)
 }
+
+/// The SSE and message routes; cancelling `ct` ends every session
+fn router<S>(service: S, options: SseOptions, ct: CancellationToken) -> Result<Router>
+where
+    S: Service<RoleServer> + Clone + Send + Sync + 'static,
+{
+    let router = Router::new()
+        .route(SSE_PATH, get(sse_handler::<S>))
+        .route(POST_PATH, post(post_handler::<S>))
+        .with_state(SseApp {
+            service,
+            sessions: Arc::default(),
+            max_sessions: options.max_sessions,
+            session_calls_per_minute: options.session_calls_per_minute,
+            ct,
+        });
+    // Route layers run inside the CORS layer, so preflights are answered without a token
+    let router = match options.auth_token {
+        Some(token) => router.route_layer(middleware::from_fn_with_state(
+            Arc::<str>::from(token),
+            require_bearer_token,
+        )),
+        None => router,
+    };
+    // Added after the auth layer: the page holds no secrets and asks for the token itself
+    let router = if options.serve_ui {
+        router.route(UI_PATH, get(|| async { Html(UI_HTML) }))
+    } else {
+        router
+    };
+    Ok(match cors_layer(&options.allowed_origins)? {
+        Some(cors) => router.layer(cors),
+        None => router,
+    })
+}
+
+/// Reject requests without `Authorization: Bearer <token>` with 401
+async fn require_bearer_token(
+    State(token): State<Arc<str>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match presented {
+        Some(presented) if constant_time_eq(presented.as_bytes(), token.as_bytes()) => {
+            next.run(request).await
+        }
+        _ => {
+            tracing::warn!(
+                "Rejecting unauthenticated request to {}",
+                request.uri().path()
+            );
+            (
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, "Bearer")],
+                "Missing or invalid bearer token",
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Compare without returning early, so response times do not reveal how much of a
+/// guessed token was right
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// CORS headers for `origins`, answering preflight requests; None when no origin is allowed
+fn cors_layer(origins: &[String]) -> Result<Option<CorsLayer>> {
+    if origins.is_empty() {
+        return Ok(None);
+    }
+    let allow_origin = if origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        let origins = origins
+            .iter()
+            .map(|origin| {
+                HeaderValue::from_str(origin)
+                    .with_context(|| format!("Invalid allowed origin: {}", origin))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        AllowOrigin::list(origins)
+    };
+    Ok(Some(
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([Method::GET, Method::POST])
+            // Browsers never let a `*` grant cover `Authorization`, so the headers are listed
+            .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE]),
+    ))
+}
+
+/// Ends a session when its event stream is dropped, e.g. because the client disconnected
+struct SessionGuard {
+    id: String,
+    sessions: Sessions,
+    _cancel: DropGuard,
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        self.sessions.lock().unwrap().remove(&self.id);
+        tracing::info!(session = %self.id, "SSE session closed");
+    }
+}
+
+async fn sse_handler<S>(State(app): State<SseApp<S>>) -> Response
+where
+    S: Service<RoleServer> + Clone + Send + Sync + 'static,
+{
+    let id = format!("{:016x}", rand::random::<u128>());
+    let (from_client_tx, from_client_rx) = mpsc::channel::<ClientJsonRpcMessage>(CHANNEL_CAPACITY);
+    let (to_client_tx, mut to_client_rx) = tokio_mpsc::channel::<String>(CHANNEL_CAPACITY);
+    let batches = Arc::new(Mutex::new(Batches::default()));
+    {
+        let mut sessions = app.sessions.lock().unwrap();
+        if let Some(max) = app.max_sessions.filter(|max| sessions.len() >= *max) {
+            tracing::warn!("Rejecting SSE session: {} sessions already open", max);
+            return (
+                StatusCode::TOO_MANY_REQUESTS,
+                format!("Too many open sessions (limit {})", max),
+            )
+                .into_response();
+        }
+        sessions.insert(
+            id.clone(),
+            Session {
+                to_service: from_client_tx,
+                to_client: to_client_tx.downgrade(),
+                batches: batches.clone(),
+                window_start: Instant::now(),
+                calls_in_window: 0,
+            },
+        );
+    }
+    tracing::info!(session = %id, "SSE connection");
+
+    let ct = app.ct.child_token();
+    let guard = SessionGuard {
+        id: id.clone(),
+        sessions: app.sessions.clone(),
+        _cancel: ct.clone().drop_guard(),
+    };
+    let service = app.service.clone();
+    // rmcp wants transport errors as io::Error
+    let to_client_tx = PollSender::new(to_client_tx)
+        .sink_map_err(|_| std::io::Error::from(std::io::ErrorKind::BrokenPipe))
+        .with_flat_map(move |message: ServerJsonRpcMessage| {
+            let data = batches
+                .lock()
+                .unwrap()
+                .encode(message)
+                .map_err(std::io::Error::from)
+                .transpose();
+            futures::stream::iter(data)
+        });
+    tokio::spawn(async move {
+        match service
+            .serve_with_ct((to_client_tx, from_client_rx), ct)
+            .await
+        {
+            Ok(server) => {
+                if let Err(e) = server.waiting().await {
+                    tracing::warn!("SSE session ended with error: {}", e);
+                }
+            }
+            Err(e) => tracing::warn!("SSE session failed to start: {}", e),
+        }
+    });
+
+    let endpoint = Event::default()
+        .event("endpoint")
+        .data(format!("{}?sessionId={}", POST_PATH, id));
+    let messages = futures::stream::poll_fn(move |cx| to_client_rx.poll_recv(cx));
+    let messages = messages.map(move |data| {
+        // The stream owns the guard, so the session lives exactly as long as the response
+        let _session = &guard;
+        Ok::<_, Infallible>(Event::default().event("message").data(data))
+    });
+    Sse::new(futures::stream::once(async { Ok(endpoint) }).chain(messages)).into_response()
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PostQuery {
+    session_id: String,
+}
+
+async fn post_handler<S>(
+    State(app): State<SseApp<S>>,
+    Query(PostQuery { session_id }): Query<PostQuery>,
+    body: String,
+) -> StatusCode {
+    let (sender, to_client, batches, decoded, rejected) = {
+        let mut sessions = app.sessions.lock().unwrap();
+        let Some(session) = sessions.get_mut(&session_id) else {
+            return StatusCode::NOT_FOUND;
+        };
+        let mut decoded = session.batches.lock().unwrap().decode(&body);
+        let mut rejected = Vec::new();
+        if let Some(limit) = app.session_calls_per_minute {
+            decoded.messages.retain(|message| match message {
+                JsonRpcMessage::Request(request)
+                    if matches!(request.request, ClientRequest::CallToolRequest(_))
+                        && !session.admit_call(limit) =>
+                {
+                    rejected.push(session_rate_limited(request.id.clone(), limit));
+                    false
+                }
+                _ => true,
+            });
+        }
+        (
+            session.to_service.clone(),
+            session.to_client.clone(),
+            session.batches.clone(),
+            decoded,
+            rejected,
+        )
+    };
+
+    // Refused calls are answered on the event stream too, so the client is not left waiting
+    let mut replies = decoded.replies;
+    let any_rejected = !rejected.is_empty();
+    for message in rejected {
+        tracing::warn!(session = %session_id, "Session tool call rate limit exceeded");
+        if let Ok(Some(data)) = batches.lock().unwrap().encode(message) {
+            replies.push(data);
+        }
+    }
+    if let Some(batch) = decoded.batch {
+        expire_later(batches, batch, to_client.clone());
+    }
+    if let Some(to_client) = to_client.upgrade() {
+        for data in replies {
+            let _ = to_client.send(data).await;
+        }
+    }
+
+    if decoded.messages.is_empty() {
+        return if any_rejected {
+            StatusCode::TOO_MANY_REQUESTS
+        } else if body.trim().is_empty() {
+            StatusCode::ACCEPTED
+        } else {
+            // Nothing valid to handle; the JSON-RPC error is on the event stream
+            StatusCode::BAD_REQUEST
+        };
+    }
+    let mut sender = sender;
+    for message in decoded.messages {
+        if sender.send(message).await.is_err() {
+            return StatusCode::GONE;
+        }
+    }
+    StatusCode::ACCEPTED
+}
+
+/// Send batch `batch` with whatever responses it has if it is still open after `BATCH_TIMEOUT`
+fn expire_later(
+    batches: Arc<Mutex<Batches>>,
+    batch: u64,
+    to_client: tokio_mpsc::WeakSender<String>,
+) {
+    tokio::spawn(async move {
+        tokio::time::sleep(BATCH_TIMEOUT).await;
+        let data = batches.lock().unwrap().expire(batch);
+        if let (Some(data), Some(to_client)) = (data, to_client.upgrade()) {
+            let _ = to_client.send(data).await;
+        }
+    });
+}
+
+/// The tool result for a call refused by the per-session rate limit
+fn session_rate_limited(id: RequestId, limit: u32) -> ServerJsonRpcMessage {
+    let text = format!(
+        "Error: Session rate limit exceeded ({} tool calls per minute)",
+        limit
+    );
+    let result = CallToolResult::error(vec![Content::text(text)]);
+    ServerMessage::Response(ServerResult::CallToolResult(result), id).into_json_rpc_message()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::bravesearch::BraveSearchRouter;
+    use serde_json::json;
+    use std::future::IntoFuture;
+    use std::time::Duration;
+
+    async fn start(options: SseOptions) -> (String, CancellationToken) {
+        start_with(BraveSearchRouter::new("test_key".to_string()), options).await
+    }
+
+    async fn start_with(
+        service: BraveSearchRouter,
+        options: SseOptions,
+    ) -> (String, CancellationToken) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let ct = CancellationToken::new();
+        let app = router(service, options, ct.clone()).unwrap();
+        let server =
+            axum::serve(listener, app).with_graceful_shutdown(ct.clone().cancelled_owned());
+        tokio::spawn(server.into_future());
+        (base_url, ct)
+    }
+
+    /// Open an event stream and return it with the session's message URL
+    async fn open_session(base_url: &str) -> (reqwest::Response, String) {
+        let mut response = reqwest::get(format!("{}/sse", base_url)).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let chunk = response.chunk().await.unwrap().unwrap();
+        let event = String::from_utf8(chunk.to_vec()).unwrap();
+        let path = event
+            .lines()
+            .find_map(|line| line.strip_prefix("data: "))
+            .expect("endpoint event");
+        (response, format!("{}{}", base_url, path))
+    }
+
+    #[tokio::test]
+    async fn test_sessions_beyond_the_cap_are_rejected() {
+        let (base_url, ct) = start(SseOptions {
+            max_sessions: Some(2),
+            ..Default::default()
+        })
+        .await;
+        let (mut first, first_url) = open_session(&base_url).await;
+        let (second, _) = open_session(&base_url).await;
+
+        let rejected = reqwest::get(format!("{}/sse", base_url)).await.unwrap();
+        assert_eq!(rejected.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+
+        // Open sessions keep working at the cap
+        let initialize = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2024-11-05",
+                "capabilities": {},
+                "clientInfo": {"name": "test", "version": "0.0.0"}
+            }
+        });
+        let client = reqwest::Client::new();
+        let status = client
+            .post(&first_url)
+            .json(&initialize)
+            .send()
+            .await
+            .unwrap()
+            .status();
+        assert_eq!(status, reqwest::StatusCode::ACCEPTED);
+        let reply = first.chunk().await.unwrap().unwrap();
+        let reply = String::from_utf8(reply.to_vec()).unwrap();
+        assert!(
+            reply.starts_with("event: message") && reply.contains("serverInfo"),
+            "{}",
+            reply
+        );
+
+        // Closing a session frees its slot
+        drop(second);
+        let mut admitted = false;
+        for _ in 0..50 {
+            let response = reqwest::get(format!("{}/sse", base_url)).await.unwrap();
+            if response.status() == reqwest::StatusCode::OK {
+                admitted = true;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(admitted, "slot was not released after the session closed");
+        ct.cancel();
+    }
+
+    /// The next response on an open event stream, skipping notifications
+    async fn next_message(events: &mut reqwest::Response) -> serde_json::Value {
+        loop {
+            let chunk = events.chunk().await.unwrap().unwrap();
+            let event = String::from_utf8(chunk.to_vec()).unwrap();
+            let response = event
+                .lines()
+                .filter_map(|line| line.strip_prefix("data: "))
+                .map(|data| serde_json::from_str::<serde_json::Value>(data).unwrap())
+                .find(|message| message.get("id").is_some());
+            if let Some(response) = response {
+                return response;
+            }
+        }
+    }
+
+    /// Open and initialize a session, returning its event stream and message URL
+    async fn initialized_session(
+        client: &reqwest::Client,
+        base_url: &str,
+    ) -> (reqwest::Response, String) {
+        let (mut events, url) = open_session(base_url).await;
+        let initialize = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2024-11-05",
+                "capabilities": {},
+                "clientInfo": {"name": "test", "version": "0.0.0"}
+            }
+        });
+        client.post(&url).json(&initialize).send().await.unwrap();
+        assert_eq!(next_message(&mut events).await["id"], 1);
+        let initialized = json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+        client.post(&url).json(&initialized).send().await.unwrap();
+        (events, url)
+    }
+
+    fn tool_call(id: u64) -> serde_json::Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "tools/call",
+            "params": {"name": "brave_web_search", "arguments": {"query": "rust"}}
+        })
+    }
+
+    #[tokio::test]
+    async fn test_session_over_its_call_rate_does_not_affect_others() {
+        let mut brave = mockito::Server::new_async().await;
+        let _mock = brave
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"type":"search","web":{"results":[{"title":"Rust","description":"d","url":"https://www.rust-lang.org/"}]}}"#)
+            .create_async()
+            .await;
+        // Cached, so the second session's call is not held up by the global per-second limit
+        let service = BraveSearchRouter::new("test_key".to_string())
+            .with_base_url(brave.url())
+            .with_cache_ttl(Some(Duration::from_secs(60)));
+        let (base_url, ct) = start_with(
+            service,
+            SseOptions {
+                session_calls_per_minute: Some(1),
+                ..Default::default()
+            },
+        )
+        .await;
+        let client = reqwest::Client::new();
+        let (mut first, first_url) = initialized_session(&client, &base_url).await;
+        let (mut second, second_url) = initialized_session(&client, &base_url).await;
+
+        let status = |response: reqwest::Response| response.status();
+        let sent = client.post(&first_url).json(&tool_call(2)).send().await;
+        assert_eq!(status(sent.unwrap()), reqwest::StatusCode::ACCEPTED);
+        assert_eq!(next_message(&mut first).await["id"], 2);
+
+        let sent = client.post(&first_url).json(&tool_call(3)).send().await;
+        assert_eq!(
+            status(sent.unwrap()),
+            reqwest::StatusCode::TOO_MANY_REQUESTS
+        );
+        let reply = next_message(&mut first).await;
+        assert_eq!(reply["id"], 3);
+        assert_eq!(reply["result"]["isError"], true);
+        assert_eq!(
+            reply["result"]["content"][0]["text"],
+            "Error: Session rate limit exceeded (1 tool calls per minute)"
+        );
+
+        // The other session still has its own allowance
+        let sent = client.post(&second_url).json(&tool_call(2)).send().await;
+        assert_eq!(status(sent.unwrap()), reqwest::StatusCode::ACCEPTED);
+        let reply = next_message(&mut second).await;
+        assert_eq!(reply["id"], 2);
+        assert!(reply["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("Title: Rust"));
+        ct.cancel();
+    }
+
+    #[tokio::test]
+    async fn test_batch_is_answered_in_one_event() {
+        let (base_url, ct) = start(SseOptions::default()).await;
+        let client = reqwest::Client::new();
+        let (mut events, url) = initialized_session(&client, &base_url).await;
+
+        let batch = json!([
+            {"jsonrpc": "2.0", "id": 2, "method": "tools/list"},
+            {"jsonrpc": "2.0", "id": 3, "method": "ping"},
+            {"jsonrpc": "2.0", "id": 4, "bogus": true}
+        ]);
+        let sent = client.post(&url).json(&batch).send().await.unwrap();
+        assert_eq!(sent.status(), reqwest::StatusCode::ACCEPTED);
+        let replies = loop {
+            let chunk = events.chunk().await.unwrap().unwrap();
+            let event = String::from_utf8(chunk.to_vec()).unwrap();
+            let array = event
+                .lines()
+                .filter_map(|line| line.strip_prefix("data: "))
+                .map(|data| serde_json::from_str::<serde_json::Value>(data).unwrap())
+                .find(|message| message.is_array());
+            if let Some(array) = array {
+                break array;
+            }
+        };
+        let mut ids = replies
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|reply| reply["id"].as_u64().unwrap())
+            .collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, vec![2, 3, 4]);
+
+        // A body that is not JSON is refused and answered with a parse error
+        let sent = client.post(&url).body("{not json").send().await.unwrap();
+        assert_eq!(sent.status(), reqwest::StatusCode::BAD_REQUEST);
+        assert_eq!(next_message(&mut events).await["error"]["code"], -32700);
+        ct.cancel();
+    }
+
+    #[tokio::test]
+    async fn test_cors_headers_for_allowed_origin() {
+        let (base_url, ct) = start(SseOptions {
+            allowed_origins: vec!["https://app.example".to_string()],
+            ..Default::default()
+        })
+        .await;
+        let client = reqwest::Client::new();
+
+        let preflight = client
+            .request(reqwest::Method::OPTIONS, format!("{}/message", base_url))
+            .header("Origin", "https://app.example")
+            .header("Access-Control-Request-Method", "POST")
+            .header(
+                "Access-Control-Request-Headers",
+                "authorization, content-type",
+            )
+            .send()
+            .await
+            .unwrap();
+        assert!(preflight.status().is_success(), "{}", preflight.status());
+        let headers = preflight.headers();
+        assert_eq!(
+            headers["access-control-allow-origin"],
+            "https://app.example"
+        );
+        assert!(headers["access-control-allow-methods"]
+            .to_str()
+            .unwrap()
+            .contains("POST"));
+        // The bearer token header has to be granted by name for a browser to send it
+        let allowed = headers["access-control-allow-headers"].to_str().unwrap();
+        assert!(allowed.contains("authorization"), "{}", allowed);
+        assert!(allowed.contains("content-type"), "{}", allowed);
+
+        let response = client
+            .get(format!("{}/sse", base_url))
+            .header("Origin", "https://app.example")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers()["access-control-allow-origin"],
+            "https://app.example"
+        );
+
+        // Other origins get no CORS grant
+        let response = client
+            .get(format!("{}/sse", base_url))
+            .header("Origin", "https://evil.example")
+            .send()
+            .await
+            .unwrap();
+        assert!(!response
+            .headers()
+            .contains_key("access-control-allow-origin"));
+        ct.cancel();
+    }
+
+    #[tokio::test]
+    async fn test_auth_token_is_required_when_configured() {
+        let (base_url, ct) = start(SseOptions {
+            auth_token: Some("s3cret".to_string()),
+            ..Default::default()
+        })
+        .await;
+        let client = reqwest::Client::new();
+        let sse = format!("{}/sse", base_url);
+
+        let response = client.get(&sse).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()["www-authenticate"], "Bearer");
+        let response = client.get(&sse).bearer_auth("wrong").send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+        // Posting to a session needs the token too
+        let response = client
+            .post(format!("{}/message?sessionId=abc", base_url))
+            .json(&json!({"jsonrpc": "2.0", "method": "notifications/initialized"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let response = client.get(&sse).bearer_auth("s3cret").send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        ct.cancel();
+    }
+
+    #[tokio::test]
+    async fn test_ui_is_served_only_when_enabled() {
+        let (base_url, ct) = start(SseOptions::default()).await;
+        let response = reqwest::get(format!("{}/", base_url)).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+        ct.cancel();
+
+        // Reachable without the token, which the page sends on its own requests
+        let (base_url, ct) = start(SseOptions {
+            serve_ui: true,
+            auth_token: Some("s3cret".to_string()),
+            ..Default::default()
+        })
+        .await;
+        let response = reqwest::get(format!("{}/", base_url)).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert!(response.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/html"));
+        assert_eq!(response.text().await.unwrap(), UI_HTML);
+        ct.cancel();
+    }
+
+    #[test]
+    fn test_no_cors_without_allowed_origins() {
+        assert!(cors_layer(&[]).unwrap().is_none());
+        assert!(cors_layer(&["bad\norigin".to_string()]).is_err());
+    }
+}
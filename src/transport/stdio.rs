@@ -1,17 +1,409 @@
+use super::batch::{Batches, Decoded, BATCH_TIMEOUT};
 use crate::tools::bravesearch::BraveSearchRouter;
 use anyhow::Result;
+use futures::{SinkExt, StreamExt};
+use rmcp::model::{
+    ClientJsonRpcMessage, ClientRequest, JsonRpcMessage, RequestId, ServerJsonRpcMessage,
+};
 use rmcp::transport::stdio;
 use rmcp::ServiceExt;
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::{mpsc, watch};
+use tokio_util::codec::{FramedRead, FramedWrite, LinesCodec, LinesCodecError};
+use tokio_util::sync::CancellationToken;
 
-pub async fn run_stdio_server(api_key: String) -> Result<()> {
-    // Create an instance of our search router with the API key
-    let service = BraveSearchRouter::new(api_key);
+pub async fn run_stdio_server(service: BraveSearchRouter) -> Result<()> {
+    // Use the rust-sdk stdio transport implementation, stopping on Ctrl+C
+    serve_with_shutdown(service, stdio(), ctrl_c(), false).await
+}
+
+/// Serve a single tool call over stdio, then exit (for per-invocation deployments)
+pub async fn run_stdio_server_once(service: BraveSearchRouter) -> Result<()> {
+    serve_with_shutdown(service, stdio(), ctrl_c(), true).await
+}
+
+async fn ctrl_c() {
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        tracing::error!("Failed to listen for ctrl+c: {}", e);
+        // Without a signal handler, keep serving until stdin closes
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Serve `service` over a reader/writer pair until the input closes or `shutdown` resolves,
+/// or, with `once`, until the first tool call has been answered.
+///
+/// On shutdown no further requests are read, and the server exits once every request already
+/// received has been answered, so clients never see a truncated JSON-RPC response.
+pub async fn serve_with_shutdown<R, W>(
+    service: BraveSearchRouter,
+    (reader, writer): (R, W),
+    shutdown: impl Future<Output = ()>,
+    once: bool,
+) -> Result<()>
+where
+    R: AsyncRead + Send + 'static,
+    W: AsyncWrite + Send + 'static,
+{
+    // Lines for the client. The service's sink and the reader (for error replies and flushed
+    // batches) hold weak senders, so the writer ends once this one is dropped below
+    let (lines_tx, lines_rx) = mpsc::unbounded_channel::<String>();
+    let lines = lines_tx.downgrade();
+    let write_lines = tokio::spawn(
+        futures::stream::unfold(lines_rx, |mut lines_rx| async move {
+            let line = lines_rx.recv().await?;
+            Some((Ok::<_, LinesCodecError>(line), lines_rx))
+        })
+        .forward(FramedWrite::new(writer, LinesCodec::new())),
+    );
+
+    // Requests read but not yet answered
+    let (in_flight_tx, in_flight) = watch::channel(0usize);
+    let (stop_reading_tx, mut stop_reading) = watch::channel(false);
+    // Set once a tool call has been answered in `once` mode
+    let (answered_tx, answered) = watch::channel(false);
+    let tool_calls = Arc::new(Mutex::new(HashSet::<RequestId>::new()));
+
+    let requests_tx = in_flight_tx.clone();
+    let pending_calls = tool_calls.clone();
+    let batches = Arc::new(Mutex::new(Batches::default()));
+    let incoming = batches.clone();
+    let replies = lines.clone();
+    let stream = FramedRead::new(reader, LinesCodec::new())
+        .flat_map(move |line| {
+            let decoded = match line {
+                Ok(line) => incoming.lock().unwrap().decode(&line),
+                Err(e) => {
+                    tracing::warn!("Failed to read from the client: {}", e);
+                    Decoded::default()
+                }
+            };
+            if let Some(batch) = decoded.batch {
+                expire_later(incoming.clone(), batch, replies.clone());
+            }
+            if let Some(replies) = replies.upgrade() {
+                for reply in decoded.replies {
+                    let _ = replies.send(reply);
+                }
+            }
+            futures::stream::iter(decoded.messages)
+        })
+        .take_until(async move {
+            let _ = stop_reading.wait_for(|stop| *stop).await;
+        })
+        .inspect(move |message| {
+            if let JsonRpcMessage::Request(request) = message {
+                requests_tx.send_modify(|count| *count += 1);
+                if once && matches!(request.request, ClientRequest::CallToolRequest(_)) {
+                    pending_calls.lock().unwrap().insert(request.id.clone());
+                }
+            }
+        })
+        // Ending the stream would make rmcp drop in-flight calls, so stay open instead
+        .chain(futures::stream::pending());
+    let sink = futures::sink::unfold(lines, |lines, line: String| async move {
+        let sent = lines.upgrade().map(|tx| tx.send(line));
+        match sent {
+            Some(Ok(())) => Ok(lines),
+            _ => Err(LinesCodecError::Io(std::io::ErrorKind::BrokenPipe.into())),
+        }
+    })
+    .with_flat_map(move |message: ServerJsonRpcMessage| {
+        let line = batches
+            .lock()
+            .unwrap()
+            .encode(message)
+            .map_err(|e| LinesCodecError::Io(e.into()))
+            .transpose();
+        futures::stream::iter(line)
+    })
+    .with(move |message: ServerJsonRpcMessage| {
+        if let JsonRpcMessage::Response(response) = &message {
+            if tool_calls.lock().unwrap().remove(&response.id) {
+                answered_tx.send_replace(true);
+            }
+        }
+        if matches!(
+            message,
+            JsonRpcMessage::Response(_) | JsonRpcMessage::Error(_)
+        ) {
+            in_flight_tx.send_modify(|count| *count = count.saturating_sub(1));
+        }
+        futures::future::ready(Ok::<_, LinesCodecError>(message))
+    });
 
-    // Use the rust-sdk stdio transport implementation
-    let server = service.serve(stdio()).await?;
+    let served = serve_messages(
+        service,
+        (sink, stream),
+        shutdown,
+        stop_reading_tx,
+        in_flight,
+        answered,
+    )
+    .await;
+    // Everything queued is still written before returning
+    drop(lines_tx);
+    write_lines.await??;
+    served
+}
+
+/// Flush batch `batch` with whatever responses it has if it is still open after `BATCH_TIMEOUT`
+fn expire_later(
+    batches: Arc<Mutex<Batches>>,
+    batch: u64,
+    lines: mpsc::WeakUnboundedSender<String>,
+) {
+    tokio::spawn(async move {
+        tokio::time::sleep(BATCH_TIMEOUT).await;
+        let line = batches.lock().unwrap().expire(batch);
+        if let (Some(line), Some(lines)) = (line, lines.upgrade()) {
+            let _ = lines.send(line);
+        }
+    });
+}
+
+/// Run the service until the input closes, `shutdown` resolves, or (with `once`) a tool call
+/// has been answered, letting in-flight requests finish on the way out
+async fn serve_messages<Si, St>(
+    service: BraveSearchRouter,
+    (sink, stream): (Si, St),
+    shutdown: impl Future<Output = ()>,
+    stop_reading_tx: watch::Sender<bool>,
+    mut in_flight: watch::Receiver<usize>,
+    mut answered: watch::Receiver<bool>,
+) -> Result<()>
+where
+    Si: futures::Sink<ServerJsonRpcMessage, Error = LinesCodecError> + Send + 'static,
+    St: futures::Stream<Item = ClientJsonRpcMessage> + Send + 'static,
+{
+    let ct = CancellationToken::new();
+    tokio::pin!(shutdown);
+    // A client that never completes the handshake has nothing in flight
+    let server = tokio::select! {
+        server = service.serve_with_ct((sink, stream), ct.clone()) => server?,
+        _ = &mut shutdown => return Ok(()),
+    };
+    let waiting = server.waiting();
+    tokio::pin!(waiting);
 
-    // Wait for the server to complete
-    server.waiting().await?;
+    tokio::select! {
+        result = &mut waiting => {
+            result?;
+            return Ok(());
+        }
+        _ = shutdown => {}
+        _ = answered.wait_for(|answered| *answered) => {
+            tracing::info!("Tool call answered; exiting (--once)");
+        }
+    }
+
+    tracing::info!("Shutting down server after in-flight requests complete...");
+    stop_reading_tx.send_replace(true);
+    tokio::select! {
+        result = &mut waiting => {
+            result?;
+            return Ok(());
+        }
+        _ = in_flight.wait_for(|count| *count == 0) => {}
+    }
+    ct.cancel();
+    waiting.await?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::bravesearch::test_client::TestClient;
+    use serde_json::json;
+    use std::time::Duration;
+    use tokio::sync::oneshot;
+    use tokio::task::JoinHandle;
+
+    fn spawn_server(
+        router: BraveSearchRouter,
+        once: bool,
+    ) -> (
+        tokio::io::DuplexStream,
+        oneshot::Sender<()>,
+        JoinHandle<Result<()>>,
+    ) {
+        let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+        let handle = tokio::spawn(serve_with_shutdown(
+            router,
+            tokio::io::split(server_io),
+            async {
+                let _ = shutdown_rx.await;
+            },
+            once,
+        ));
+        (client_io, shutdown_tx, handle)
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_while_idle_exits_cleanly() {
+        let router = BraveSearchRouter::new("test_key".to_string());
+        let (client_io, shutdown, handle) = spawn_server(router, false);
+        let _client = TestClient::connect(client_io).await;
+
+        shutdown.send(()).unwrap();
+        let result = tokio::time::timeout(Duration::from_secs(5), handle)
+            .await
+            .expect("server did not exit after shutdown");
+        assert!(result.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_lets_in_flight_call_finish() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_chunked_body(|w| {
+                std::thread::sleep(Duration::from_millis(300));
+                w.write_all(br#"{"type":"search","web":{"results":[{"title":"Rust","description":"d","url":"https://www.rust-lang.org/"}]}}"#)
+            })
+            .create_async()
+            .await;
+        let router = BraveSearchRouter::new("test_key".to_string()).with_base_url(server.url());
+        let (client_io, shutdown, handle) = spawn_server(router, false);
+        let mut client = TestClient::connect(client_io).await;
+
+        client
+            .send(json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "tools/call",
+                "params": {"name": "brave_web_search", "arguments": {"query": "rust"}}
+            }))
+            .await;
+        // Give the server time to read the call before signalling shutdown
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        shutdown.send(()).unwrap();
+
+        let response = client.recv().await;
+        assert_eq!(response["id"], json!(2));
+        let text = response["result"]["content"][0]["text"].as_str().unwrap();
+        assert!(text.contains("Title: Rust"), "{}", text);
+        let result = tokio::time::timeout(Duration::from_secs(5), handle)
+            .await
+            .expect("server did not exit after the in-flight call");
+        assert!(result.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_once_exits_after_first_tool_call() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/res/v1/web/search")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"type":"search","web":{"results":[{"title":"Rust","description":"d","url":"https://www.rust-lang.org/"}]}}"#)
+            .create_async()
+            .await;
+        let router = BraveSearchRouter::new("test_key".to_string()).with_base_url(server.url());
+        let (client_io, _shutdown, mut handle) = spawn_server(router, true);
+        let mut client = TestClient::connect(client_io).await;
+
+        // Requests other than tool calls keep the server running
+        client
+            .send(json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}))
+            .await;
+        assert_eq!(client.recv().await["id"], json!(2));
+        assert!(
+            tokio::time::timeout(Duration::from_millis(100), &mut handle)
+                .await
+                .is_err(),
+            "server exited before any tool call"
+        );
+
+        let messages = client
+            .call_tool(3, "brave_web_search", json!({"query": "rust"}))
+            .await;
+        let text = messages.last().unwrap()["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap();
+        assert!(text.contains("Title: Rust"), "{}", text);
+        let result = tokio::time::timeout(Duration::from_secs(5), handle)
+            .await
+            .expect("server did not exit after the tool call");
+        assert!(result.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_batch_of_tool_calls_is_answered_as_a_batch() {
+        let router = BraveSearchRouter::new("test_key".to_string());
+        let (client_io, _shutdown, _handle) = spawn_server(router, false);
+        let mut client = TestClient::connect(client_io).await;
+
+        client
+            .send(json!([
+                {
+                    "jsonrpc": "2.0",
+                    "id": 2,
+                    "method": "tools/call",
+                    "params": {"name": "brave_server_config", "arguments": {}}
+                },
+                {"jsonrpc": "2.0", "method": "notifications/cancelled", "params": {"requestId": 99}},
+                {
+                    "jsonrpc": "2.0",
+                    "id": 3,
+                    "method": "tools/call",
+                    "params": {"name": "brave_server_config", "arguments": {}}
+                }
+            ]))
+            .await;
+
+        let responses = client.recv().await;
+        let responses = responses.as_array().expect("batch answered with an array");
+        assert_eq!(responses.len(), 2);
+        let mut ids = responses
+            .iter()
+            .map(|response| {
+                assert!(response["result"]["content"][0]["text"].is_string());
+                response["id"].as_u64().unwrap()
+            })
+            .collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, vec![2, 3]);
+
+        // Messages outside a batch are still answered on their own
+        client
+            .send(json!({"jsonrpc": "2.0", "id": 4, "method": "tools/list"}))
+            .await;
+        assert_eq!(client.recv().await["id"], json!(4));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_input_is_answered_with_jsonrpc_errors() {
+        let router = BraveSearchRouter::new("test_key".to_string());
+        let (client_io, _shutdown, _handle) = spawn_server(router, false);
+        let mut client = TestClient::connect(client_io).await;
+
+        client.send_line(r#"{"jsonrpc": "2.0", "id": 2,"#).await;
+        let reply = client.recv().await;
+        assert_eq!(reply["error"]["code"], -32700);
+        assert_eq!(reply["id"], serde_json::Value::Null);
+
+        client
+            .send(json!([
+                {"jsonrpc": "2.0", "id": 3, "method": "tools/list"},
+                {"jsonrpc": "2.0", "id": 4, "bogus": true}
+            ]))
+            .await;
+        let replies = client.recv().await;
+        let replies = replies.as_array().expect("batch answered with an array");
+        assert_eq!(replies.len(), 2);
+        let invalid = replies.iter().find(|reply| reply["id"] == 4).unwrap();
+        assert_eq!(invalid["error"]["code"], -32600);
+        assert!(replies
+            .iter()
+            .any(|reply| reply["id"] == 3 && reply["result"].is_object()));
+    }
+}